@@ -0,0 +1,90 @@
+//! Compares verifying a 100-envelope batch serially on the calling task
+//! against spreading it across blocking tasks.
+//!
+//! Run with `cargo bench --bench verify_batch`.
+
+use std::{
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::runtime::Runtime;
+use xcm_lite::{
+    config::ParachainConfig,
+    crypto::KeyRegistry,
+    domain::{
+        Instruction, MessageEnvelope, SignatureScheme, SigningEncoding, TransferReserveAsset,
+        XcmVersion,
+    },
+};
+
+const BATCH: usize = 100;
+const ITERATIONS: u32 = 200;
+
+fn time(label: &str, mut run: impl FnMut()) -> Duration {
+    for _ in 0..ITERATIONS / 10 {
+        run();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{label:<28} {:>8.2} µs/batch",
+        elapsed.as_secs_f64() * 1e6 / f64::from(ITERATIONS)
+    );
+    elapsed
+}
+
+fn main() {
+    let registry = KeyRegistry::from_config(&ParachainConfig {
+        count: 2,
+        ..ParachainConfig::default()
+    })
+    .expect("registry");
+    let batch: Vec<_> = (0..BATCH)
+        .map(|index| {
+            let envelope = MessageEnvelope {
+                message_id: Some(format!("msg-{index}")),
+                sender_para: 1000,
+                dest_para: 1001,
+                via: Vec::new(),
+                xcm_version: XcmVersion::V3,
+                instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+                    asset: "DOT".into(),
+                    amount: 10,
+                    beneficiary: "acct-123".into(),
+                })],
+                signature_scheme: SignatureScheme::Ed25519,
+                prehashed: false,
+                defer_execution: false,
+                nonce: None,
+                signing_encoding: SigningEncoding::Json,
+                signature: None,
+            };
+            let signature = registry.sign_envelope(&envelope).expect("signature");
+            (envelope, signature.to_bytes().to_vec())
+        })
+        .collect();
+    let batch = Arc::new(batch);
+    let runtime = Runtime::new().expect("runtime");
+
+    let serial = time("serial verify_envelope", || {
+        for (envelope, signature) in black_box(batch.as_slice()) {
+            registry
+                .verify_envelope(envelope, signature)
+                .expect("valid");
+        }
+    });
+    let parallel = time("verify_envelopes", || {
+        let results = runtime.block_on(registry.verify_envelopes(batch.clone(), 0));
+        assert!(results.iter().all(Result::is_ok));
+    });
+
+    println!(
+        "speedup: {:.2}x",
+        serial.as_secs_f64() / parallel.as_secs_f64()
+    );
+}
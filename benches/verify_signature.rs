@@ -0,0 +1,61 @@
+//! Compares verifying with the cached key against re-deriving it per call.
+//!
+//! Run with `cargo bench --bench verify_signature`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use ed25519_dalek::{Signer, SigningKey, Verifier};
+use xcm_lite::{config::ParachainConfig, crypto::KeyRegistry};
+
+const ITERATIONS: u32 = 5_000;
+
+fn time(label: &str, mut run: impl FnMut()) -> Duration {
+    for _ in 0..ITERATIONS / 10 {
+        run();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{label:<28} {:>8.2} µs/op",
+        elapsed.as_secs_f64() * 1e6 / f64::from(ITERATIONS)
+    );
+    elapsed
+}
+
+fn main() {
+    let message = b"xcm-lite verification benchmark";
+
+    let registry = KeyRegistry::from_config(&ParachainConfig {
+        count: 1,
+        ..ParachainConfig::default()
+    })
+    .expect("registry");
+    let signature = registry.sign_message(1000, message).expect("signature");
+    let signature_bytes = signature.to_bytes();
+
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+    let baseline_signature = signing_key.sign(message);
+
+    let derived = time("derive key per verification", || {
+        let verifying_key = black_box(&signing_key).verifying_key();
+        verifying_key
+            .verify(black_box(message), &baseline_signature)
+            .expect("valid");
+    });
+    let cached = time("KeyRegistry::verify_signature", || {
+        registry
+            .verify_signature(1000, black_box(message), &signature_bytes)
+            .expect("valid");
+    });
+
+    println!(
+        "speedup: {:.2}x",
+        derived.as_secs_f64() / cached.as_secs_f64()
+    );
+}
@@ -0,0 +1,54 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use super::error::ApiError;
+
+/// Let a request through only when it carries `server.admin_token` as
+/// `Authorization: Bearer <token>`.
+///
+/// Without a configured token every operator route is refused, so a fresh
+/// deployment never exposes them by accident.
+pub async fn require_admin(
+    State(token): State<Option<String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(expected) = token else {
+        return ApiError::new(
+            StatusCode::FORBIDDEN,
+            "Forbidden",
+            "operator routes are disabled; set server.admin_token to enable them",
+        )
+        .into_response();
+    };
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if presented.is_some_and(|presented| tokens_match(presented, &expected)) {
+        next.run(request).await
+    } else {
+        ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "Unauthorized",
+            "missing or invalid admin token",
+        )
+        .into_response()
+    }
+}
+
+/// Compare every byte rather than stopping at the first difference, so
+/// response times do not reveal how much of a guess was right.
+fn tokens_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
@@ -0,0 +1,96 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use super::error::ApiError;
+use crate::domain::{MessageEnvelope, MessageValidationError};
+
+/// Media type selecting MessagePack bodies.
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Body encoding chosen from the request's `Content-Type`.
+///
+/// `application/msgpack` selects MessagePack; anything else is treated as
+/// JSON. The response is encoded the same way as the request. Error bodies
+/// are always JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl Encoding {
+    fn from_content_type(value: Option<&HeaderValue>) -> Self {
+        let media_type = value
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(str::trim);
+        match media_type {
+            Some(media_type) if media_type.eq_ignore_ascii_case(MSGPACK_CONTENT_TYPE) => {
+                Self::MessagePack
+            }
+            _ => Self::Json,
+        }
+    }
+
+    /// Decode an envelope body in this encoding.
+    pub fn decode_envelope(
+        self,
+        body: &[u8],
+        strict: bool,
+    ) -> Result<MessageEnvelope, MessageValidationError> {
+        match self {
+            Self::Json => MessageEnvelope::from_json(body, strict),
+            Self::MessagePack => MessageEnvelope::from_msgpack(body, strict),
+        }
+    }
+
+    /// Wrap `value` so it is serialised in this encoding.
+    pub fn respond<T: Serialize>(self, value: T) -> Encoded<T> {
+        Encoded {
+            encoding: self,
+            value,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Encoding {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_content_type(
+            parts.headers.get(header::CONTENT_TYPE),
+        ))
+    }
+}
+
+/// Response body serialised as JSON or MessagePack.
+#[derive(Debug)]
+pub struct Encoded<T> {
+    encoding: Encoding,
+    value: T,
+}
+
+impl<T: Serialize> IntoResponse for Encoded<T> {
+    fn into_response(self) -> Response {
+        match self.encoding {
+            Encoding::Json => Json(self.value).into_response(),
+            // Named encoding keeps field names, matching the JSON shape.
+            Encoding::MessagePack => match rmp_serde::to_vec_named(&self.value) {
+                Ok(bytes) => {
+                    ([(header::CONTENT_TYPE, MSGPACK_CONTENT_TYPE)], bytes).into_response()
+                }
+                Err(err) => {
+                    ApiError::internal(format!("failed to encode response: {err}")).into_response()
+                }
+            },
+        }
+    }
+}
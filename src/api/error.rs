@@ -0,0 +1,176 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::{
+    crypto::CryptoError,
+    domain::{MessageValidationError, XcmErrorCode},
+    execution::ExecutionError,
+    processor::ProcessorError,
+    state::MessageStoreError,
+};
+
+/// Error returned by HTTP handlers, rendered as `{ code, message }`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: String,
+    pub message: String,
+    /// Individual failures when one request has several problems.
+    pub errors: Vec<ErrorBody>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code: code.into(),
+            message: message.into(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "NotFound", message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal", message)
+    }
+
+    /// The JSON body this error renders as, without its status.
+    pub fn into_body(self) -> ErrorBody {
+        ErrorBody {
+            code: self.code,
+            message: self.message,
+            errors: self.errors,
+        }
+    }
+}
+
+/// JSON body used for every error response.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ErrorBody>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status;
+        (status, Json(self.into_body())).into_response()
+    }
+}
+
+impl From<MessageValidationError> for ApiError {
+    fn from(err: MessageValidationError) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, err.code.to_string(), err.detail)
+    }
+}
+
+impl From<CryptoError> for ApiError {
+    fn from(err: CryptoError) -> Self {
+        match err {
+            CryptoError::UnknownParachain { .. } => Self::not_found(err.to_string()),
+            CryptoError::InvalidKey { .. } => Self::new(
+                StatusCode::BAD_REQUEST,
+                XcmErrorCode::InvalidPayload.to_string(),
+                err.to_string(),
+            ),
+            _ => Self::new(
+                StatusCode::UNAUTHORIZED,
+                XcmErrorCode::InvalidSignature.to_string(),
+                err.to_string(),
+            ),
+        }
+    }
+}
+
+impl From<ExecutionError> for ApiError {
+    fn from(err: ExecutionError) -> Self {
+        match err {
+            ExecutionError::StatePoisoned => Self::internal(err.to_string()),
+            _ => Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "ExecutionFailed",
+                err.to_string(),
+            ),
+        }
+    }
+}
+
+impl From<ProcessorError> for ApiError {
+    fn from(err: ProcessorError) -> Self {
+        match err {
+            ProcessorError::Validation(err) => err.into(),
+            ProcessorError::ValidationErrors(ref errors) => {
+                let code = errors
+                    .first()
+                    .map_or(XcmErrorCode::InvalidPayload, |first| first.code);
+                let mut api = Self::new(StatusCode::BAD_REQUEST, code.to_string(), err.to_string());
+                api.errors = errors
+                    .iter()
+                    .map(|error| ErrorBody {
+                        code: error.code.to_string(),
+                        message: error.detail.clone(),
+                        errors: Vec::new(),
+                    })
+                    .collect();
+                api
+            }
+            ProcessorError::Signature(CryptoError::UnknownParachain { para_id }) => Self::new(
+                StatusCode::BAD_REQUEST,
+                XcmErrorCode::InvalidPayload.to_string(),
+                format!("parachain {para_id} is not registered"),
+            ),
+            ProcessorError::Signature(err) => Self::new(
+                StatusCode::UNAUTHORIZED,
+                XcmErrorCode::InvalidSignature.to_string(),
+                err.to_string(),
+            ),
+            ProcessorError::Store(err @ MessageStoreError::Full { .. }) => Self::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Unavailable",
+                err.to_string(),
+            ),
+            ProcessorError::RouteNotAllowed { .. } => Self::new(
+                StatusCode::FORBIDDEN,
+                XcmErrorCode::RouteNotAllowed.to_string(),
+                err.to_string(),
+            ),
+            ProcessorError::UnknownMessage(_) => Self::not_found(err.to_string()),
+            ProcessorError::NotReplayable(_)
+            | ProcessorError::NotCancellable(_)
+            | ProcessorError::NotDeferred(_) => {
+                Self::new(StatusCode::CONFLICT, "Conflict", err.to_string())
+            }
+            ProcessorError::DuplicateContent { .. } => {
+                Self::new(StatusCode::CONFLICT, "DuplicateContent", err.to_string())
+            }
+            ProcessorError::InvalidNonce { .. } => {
+                Self::new(StatusCode::CONFLICT, "InvalidNonce", err.to_string())
+            }
+            ProcessorError::TooManyInFlight { .. } => Self::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "TooManyInFlight",
+                err.to_string(),
+            ),
+            ProcessorError::DestinationCongested { .. } => Self::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "DestinationCongested",
+                err.to_string(),
+            ),
+            ProcessorError::ChannelClosed => Self::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Unavailable",
+                err.to_string(),
+            ),
+            ProcessorError::StatePoisoned => Self::internal(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,1294 @@
+use std::{collections::BTreeMap, convert::Infallible, sync::atomic::Ordering};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Json,
+};
+use futures_util::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{
+    encoding::Encoding,
+    error::{ApiError, ErrorBody},
+    ApiContext,
+};
+use crate::{
+    audit::AuditVerification,
+    config::AppConfig,
+    domain::{Balance, MessageEnvelope, MessageValidationError, XcmErrorCode, XcmVersion},
+    execution::{
+        self,
+        plan::{self, ExecutionPlan},
+        simulation::SimulationResult,
+    },
+    processor::Cancellation,
+    reload::ReloadReport,
+    state::{MessageRecord, MessageStatus, ParachainState, ServiceState, TrafficSnapshot},
+};
+
+/// Response returned once a message has been accepted for relay.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitResponse {
+    pub message_id: String,
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replayed_from: Option<String>,
+    /// Route reporting the message's status; also sent as `Location`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_url: Option<String>,
+    /// Route that cancels the message, queued or executing, until it
+    /// finishes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_url: Option<String>,
+}
+
+impl SubmitResponse {
+    fn pending(message_id: String, replayed_from: Option<String>) -> Self {
+        let status_url = status_path(&message_id);
+        Self {
+            cancel_url: Some(format!("{status_url}/cancel")),
+            status_url: Some(status_url),
+            message_id,
+            status: "pending",
+            replayed_from,
+        }
+    }
+
+    /// `Location` header pointing at the accepted message's status.
+    fn location(&self) -> [(header::HeaderName, String); 1] {
+        [(
+            header::LOCATION,
+            self.status_url.clone().unwrap_or_default(),
+        )]
+    }
+}
+
+/// `/status/{id}` with the id percent-encoded outside the URL-safe set.
+fn status_path(message_id: &str) -> String {
+    let mut path = String::from("/status/");
+    for byte in message_id.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            path.push(char::from(byte));
+        } else {
+            path.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    path
+}
+
+/// Longest message id accepted in a status lookup path.
+const MAX_MESSAGE_ID_LEN: usize = 128;
+
+/// Audit entries copied out of the log per lock acquisition while
+/// streaming `/audit`.
+const AUDIT_STREAM_BATCH: usize = 256;
+
+/// Maximum number of envelopes accepted by a single batch submission.
+const MAX_BATCH_SUBMIT: usize = 1_000;
+
+/// Maximum number of ids accepted by a single batch status lookup.
+const MAX_BATCH_STATUS_IDS: usize = 1_000;
+
+/// Log entries returned by `/parachains/:id/logs` when no limit is given.
+const DEFAULT_LOG_PAGE: usize = 100;
+
+/// Most log entries returned by one `/parachains/:id/logs` request.
+const MAX_LOG_PAGE: usize = 1_000;
+
+/// Messages returned by `/messages` when no limit is given.
+const DEFAULT_MESSAGE_PAGE: usize = 100;
+
+/// Most messages returned by one `/messages` request.
+const MAX_MESSAGE_PAGE: usize = 1_000;
+
+/// Request body for `/status/batch`.
+#[derive(Debug, Deserialize)]
+pub struct BatchStatusRequest {
+    pub ids: Vec<String>,
+}
+
+/// Canonical bytes a client must sign for an envelope.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningBytesResponse {
+    pub signing_bytes: String,
+}
+
+/// Snapshot of a single parachain's simulated state.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParachainStateResponse {
+    pub para_id: u32,
+    #[serde(flatten)]
+    pub state: ParachainState,
+    #[serde(flatten)]
+    pub traffic: TrafficSnapshot,
+    pub online: bool,
+}
+
+/// Paging and filtering accepted by `GET /parachains/:id/logs`.
+#[derive(Debug, Default, Deserialize)]
+pub struct LogQuery {
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+    /// Keep only entries containing this substring.
+    pub contains: Option<String>,
+}
+
+/// One page of a parachain's log, oldest entry first.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogPage {
+    pub para_id: u32,
+    /// Entries matching the filter, across every page.
+    pub total: usize,
+    pub offset: usize,
+    pub entries: Vec<String>,
+}
+
+/// Weight a message would be charged if every instruction ran.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeightEstimate {
+    pub total_weight: u64,
+    /// Weight of each instruction, in message order.
+    pub instruction_weights: Vec<u64>,
+}
+
+/// XCM versions a parachain accepts messages in.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionsResponse {
+    pub para_id: u32,
+    pub versions: Vec<XcmVersion>,
+}
+
+/// Nonce a sender parachain must use on its next submission.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceResponse {
+    pub para_id: u32,
+    pub next_nonce: u64,
+}
+
+/// Service-wide counters returned by `/stats`.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsResponse {
+    pub parachains: usize,
+    pub messages: MessageCounts,
+    pub traffic: BTreeMap<u32, TrafficSnapshot>,
+}
+
+/// Relay backlog snapshot returned by `/diagnostics`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsResponse {
+    /// Approximate number of messages waiting in the relay queue.
+    pub queue_depth: u64,
+    pub active_workers: u64,
+    pub paused: bool,
+    pub messages: MessageCounts,
+}
+
+/// Number of tracked messages in each status.
+#[derive(Debug, Default, Serialize)]
+pub struct MessageCounts {
+    pub total: usize,
+    pub pending: usize,
+    pub relayed: usize,
+    pub executed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+}
+
+/// Response body for `/healthz`.
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    /// Whether an operator has paused the relay loop.
+    pub paused: bool,
+}
+
+/// Response body for the pause and resume admin routes.
+#[derive(Debug, Serialize)]
+pub struct PauseResponse {
+    pub paused: bool,
+}
+
+/// Accept a signed envelope, verify it, and enqueue it for relay.
+///
+/// The body may be JSON or, with `Content-Type: application/msgpack`,
+/// MessagePack; the response uses the same encoding.
+pub async fn submit_message(
+    State(context): State<ApiContext>,
+    encoding: Encoding,
+    body: Bytes,
+) -> Result<impl IntoResponse, ApiError> {
+    let envelope = encoding.decode_envelope(&body, context.config.validation.strict_fields)?;
+    // In dev mode the signature may be missing or a placeholder.
+    let signature = if context.processor.skips_signatures() {
+        Vec::new()
+    } else {
+        decode_signature(&envelope)?
+    };
+
+    let message_id = context
+        .processor
+        .submit_message(envelope, &signature)
+        .await?;
+
+    let response = SubmitResponse::pending(message_id, None);
+    Ok((
+        StatusCode::ACCEPTED,
+        response.location(),
+        encoding.respond(response),
+    ))
+}
+
+/// Outcome of one envelope in a batch submission.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum BatchSubmitResult {
+    Accepted(SubmitResponse),
+    Rejected { error: ErrorBody },
+}
+
+/// Accept a JSON array of signed envelopes, answering with one result per
+/// envelope in the same order.
+///
+/// Entries are independent: a malformed or rejected envelope is reported
+/// in its slot and the rest are still enqueued.
+pub async fn submit_batch(
+    State(context): State<ApiContext>,
+    Json(raw): Json<Vec<serde_json::Value>>,
+) -> Result<Json<Vec<BatchSubmitResult>>, ApiError> {
+    if raw.len() > MAX_BATCH_SUBMIT {
+        return Err(MessageValidationError::invalid_payload(format!(
+            "at most {MAX_BATCH_SUBMIT} envelopes may be submitted at once"
+        ))
+        .into());
+    }
+
+    let strict = context.config.validation.strict_fields;
+    let skip_signatures = context.processor.skips_signatures();
+    let mut results: Vec<Option<BatchSubmitResult>> = Vec::with_capacity(raw.len());
+    let mut batch = Vec::new();
+    for value in raw {
+        let decoded = MessageEnvelope::from_value(value, strict)
+            .map_err(ApiError::from)
+            .and_then(|envelope| {
+                let signature = if skip_signatures {
+                    Vec::new()
+                } else {
+                    decode_signature(&envelope)?
+                };
+                Ok((envelope, signature))
+            });
+        match decoded {
+            Ok(signed) => {
+                batch.push(signed);
+                results.push(None);
+            }
+            Err(err) => results.push(Some(BatchSubmitResult::Rejected {
+                error: err.into_body(),
+            })),
+        }
+    }
+
+    let mut submitted = context.processor.submit_batch(batch).await.into_iter();
+    let results = results
+        .into_iter()
+        .map(|slot| {
+            slot.unwrap_or_else(|| match submitted.next() {
+                Some(Ok(message_id)) => {
+                    BatchSubmitResult::Accepted(SubmitResponse::pending(message_id, None))
+                }
+                Some(Err(err)) => BatchSubmitResult::Rejected {
+                    error: ApiError::from(err).into_body(),
+                },
+                None => BatchSubmitResult::Rejected {
+                    error: ApiError::internal("missing batch result").into_body(),
+                },
+            })
+        })
+        .collect();
+    Ok(Json(results))
+}
+
+/// Re-enqueue a finished message's envelope under a fresh id.
+pub async fn replay_message(
+    State(context): State<ApiContext>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let message_id = context.processor.replay_message(&id).await?;
+    let response = SubmitResponse::pending(message_id, Some(id));
+    Ok((StatusCode::ACCEPTED, response.location(), Json(response)))
+}
+
+/// Cancel a message that has not finished.
+///
+/// Answers 200 `cancelled` when the message will not run, or 202
+/// `cancelling` when it is executing and has been asked to stop; poll
+/// `/status/:id` for the outcome.
+pub async fn cancel_message(
+    State(context): State<ApiContext>,
+    Path(id): Path<String>,
+) -> Result<(StatusCode, Json<SubmitResponse>), ApiError> {
+    let (status, label) = match context.processor.cancel_message(&id)? {
+        Cancellation::Cancelled => (StatusCode::OK, "cancelled"),
+        Cancellation::Requested => (StatusCode::ACCEPTED, "cancelling"),
+    };
+    Ok((
+        status,
+        Json(SubmitResponse {
+            message_id: id,
+            status: label,
+            replayed_from: None,
+            status_url: None,
+            cancel_url: None,
+        }),
+    ))
+}
+
+/// Execute a message held at `relayed` by deferred execution.
+pub async fn execute_message(
+    State(context): State<ApiContext>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    context.processor.execute_deferred(&id).await?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(SubmitResponse {
+            message_id: id,
+            status: "relayed",
+            replayed_from: None,
+            status_url: None,
+            cancel_url: None,
+        }),
+    ))
+}
+
+/// Return the hex-encoded canonical signing bytes for an envelope.
+///
+/// Any signature on the request is ignored, so clients can compare the bytes
+/// they sign against the server's encoding.
+pub async fn signing_bytes(
+    State(context): State<ApiContext>,
+    body: Bytes,
+) -> Result<Json<SigningBytesResponse>, ApiError> {
+    let envelope = MessageEnvelope::from_json(&body, context.config.validation.strict_fields)?;
+    Ok(Json(SigningBytesResponse {
+        signing_bytes: hex::encode(envelope.signing_bytes()),
+    }))
+}
+
+/// Dry-run an envelope against a snapshot of current state.
+///
+/// The envelope is validated like a submission, but its signature is not
+/// required and no state is changed.
+pub async fn simulate_message(
+    State(context): State<ApiContext>,
+    body: Bytes,
+) -> Result<Json<SimulationResult>, ApiError> {
+    let envelope = MessageEnvelope::from_json(&body, context.config.validation.strict_fields)?;
+    let envelope = context.processor.normalized(envelope);
+    context.processor.validate_envelope(&envelope)?;
+    let result = context.simulator.simulate(&envelope)?;
+    Ok(Json(result))
+}
+
+/// Report the balances a message needs on its destination, without
+/// executing it.
+pub async fn plan_message(
+    State(context): State<ApiContext>,
+    body: Bytes,
+) -> Result<Json<ExecutionPlan>, ApiError> {
+    let envelope = MessageEnvelope::from_json(&body, context.config.validation.strict_fields)?;
+    let envelope = context.processor.normalized(envelope);
+    context.processor.validate_envelope(&envelope)?;
+    Ok(Json(plan::plan(&envelope)))
+}
+
+/// Report the weight a message would be charged if every instruction ran,
+/// without submitting it.
+pub async fn estimate_weight(
+    State(context): State<ApiContext>,
+    body: Bytes,
+) -> Result<Json<WeightEstimate>, ApiError> {
+    let envelope = MessageEnvelope::from_json(&body, context.config.validation.strict_fields)?;
+    let envelope = context.processor.normalized(envelope);
+    context.processor.validate_envelope(&envelope)?;
+    let instruction_weights: Vec<u64> = envelope
+        .instructions
+        .iter()
+        .map(execution::instruction_weight)
+        .collect();
+    Ok(Json(WeightEstimate {
+        total_weight: instruction_weights
+            .iter()
+            .fold(0, |sum, w| sum.saturating_add(*w)),
+        instruction_weights,
+    }))
+}
+
+/// Stream every held audit entry together with the current chain head.
+///
+/// The response is `{ "head": ..., "entries": [...] }`. Entries are copied
+/// out of the log a batch at a time, so memory stays bounded however long
+/// the log is; entries appended after the head was read are not included,
+/// and neither are entries dropped under `audit.max_entries`.
+pub async fn get_audit(State(context): State<ApiContext>) -> impl IntoResponse {
+    let audit = context.state.audit.clone();
+    let first = usize::try_from(audit.first_index()).unwrap_or(usize::MAX);
+    let head = audit.head();
+    let length = usize::try_from(head.length).unwrap_or(usize::MAX);
+    let prefix = format!(
+        "{{\"head\":{},\"entries\":[",
+        serde_json::to_string(&head).expect("audit head serializes")
+    );
+
+    let batches = stream::unfold((first, false), move |(start, mut separate)| {
+        let audit = audit.clone();
+        async move {
+            if start >= length {
+                return None;
+            }
+            let end = start.saturating_add(AUDIT_STREAM_BATCH).min(length);
+            let mut chunk = Vec::new();
+            for entry in audit.entries_between(start, end) {
+                if separate {
+                    chunk.push(b',');
+                }
+                separate = true;
+                serde_json::to_writer(&mut chunk, &entry).expect("audit entry serializes");
+            }
+            Some((Bytes::from(chunk), (end, separate)))
+        }
+    });
+    let body = stream::once(async move { Bytes::from(prefix) })
+        .chain(batches)
+        .chain(stream::once(async { Bytes::from_static(b"]}") }))
+        .map(Ok::<_, Infallible>);
+
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        Body::from_stream(body),
+    )
+}
+
+/// Walk the audit chain and report whether every link is intact.
+pub async fn verify_audit(State(context): State<ApiContext>) -> Json<AuditVerification> {
+    Json(context.state.audit.verify())
+}
+
+/// Render service metrics in the Prometheus text format.
+pub async fn get_metrics(State(context): State<ApiContext>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        context.metrics.render(),
+    )
+}
+
+/// Look up the lifecycle record for a submitted message.
+pub async fn get_status(
+    State(context): State<ApiContext>,
+    Path(id): Path<String>,
+) -> Result<Json<MessageRecord>, ApiError> {
+    validate_message_id(&id)?;
+    let messages = context
+        .state
+        .messages
+        .read()
+        .map_err(|_| ApiError::internal("state lock poisoned"))?;
+    messages
+        .get(&id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("message {id} not found")))
+}
+
+/// Filters and paging accepted by `GET /messages`; every given filter
+/// must match.
+#[derive(Debug, Default, Deserialize)]
+pub struct MessageListQuery {
+    /// Status tag, e.g. `executed`.
+    pub status: Option<String>,
+    pub sender: Option<u32>,
+    pub dest: Option<u32>,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// One record in a `GET /messages` listing.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageListEntry {
+    pub message_id: String,
+    #[serde(flatten)]
+    pub record: MessageRecord,
+}
+
+/// List one page of tracked messages in submission order, optionally
+/// filtered by status, sender parachain, and destination parachain.
+pub async fn list_messages(
+    State(context): State<ApiContext>,
+    Query(query): Query<MessageListQuery>,
+) -> Result<Json<Vec<MessageListEntry>>, ApiError> {
+    if let Some(status) = &query.status {
+        let statuses = MessageStatus::names();
+        if !statuses.contains(&status.as_str()) {
+            return Err(MessageValidationError::invalid_payload(format!(
+                "unknown status `{status}`; expected one of {}",
+                statuses.join(", ")
+            ))
+            .into());
+        }
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_MESSAGE_PAGE);
+    if limit > MAX_MESSAGE_PAGE {
+        return Err(MessageValidationError::invalid_payload(format!(
+            "limit {limit} exceeds the maximum of {MAX_MESSAGE_PAGE}"
+        ))
+        .into());
+    }
+
+    let messages = context
+        .state
+        .messages
+        .read()
+        .map_err(|_| ApiError::internal("state lock poisoned"))?;
+    let entries = messages
+        .iter()
+        .filter(|(_, record)| {
+            query
+                .status
+                .as_ref()
+                .is_none_or(|status| record.status.name() == status)
+                && query.sender.is_none_or(|id| record.sender_para == id)
+                && query.dest.is_none_or(|id| record.dest_para == id)
+        })
+        .skip(query.offset)
+        .take(limit)
+        .map(|(message_id, record)| MessageListEntry {
+            message_id: message_id.clone(),
+            record: record.clone(),
+        })
+        .collect();
+    Ok(Json(entries))
+}
+
+/// Look up many message records under a single read lock.
+///
+/// Unknown ids map to `null`.
+pub async fn get_status_batch(
+    State(context): State<ApiContext>,
+    Json(request): Json<BatchStatusRequest>,
+) -> Result<Json<BTreeMap<String, Option<MessageRecord>>>, ApiError> {
+    if request.ids.len() > MAX_BATCH_STATUS_IDS {
+        return Err(MessageValidationError::invalid_payload(format!(
+            "at most {MAX_BATCH_STATUS_IDS} ids may be requested at once"
+        ))
+        .into());
+    }
+
+    let messages = context
+        .state
+        .messages
+        .read()
+        .map_err(|_| ApiError::internal("state lock poisoned"))?;
+    let records = request
+        .ids
+        .into_iter()
+        .map(|id| {
+            let record = messages.get(&id).cloned();
+            (id, record)
+        })
+        .collect();
+    Ok(Json(records))
+}
+
+/// Stream every message status transition as server-sent events.
+///
+/// Each `status` event carries `{ messageId, status, ..., timestamp }`. A
+/// subscriber that falls behind receives a `lagged` event with the number of
+/// transitions it missed. The subscription is dropped when the client
+/// disconnects.
+pub async fn events(
+    State(context): State<ApiContext>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = context.state.subscribe_events();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let event = match receiver.recv().await {
+            Ok(transition) => Event::default()
+                .event("status")
+                .json_data(&transition)
+                .unwrap_or_else(|_| Event::default().event("status")),
+            Err(RecvError::Lagged(missed)) => {
+                Event::default().event("lagged").data(missed.to_string())
+            }
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), receiver))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Return balances, logs, and traffic counters for one parachain.
+pub async fn get_parachain_state(
+    State(context): State<ApiContext>,
+    Path(para_id): Path<u32>,
+) -> Result<Json<ParachainStateResponse>, ApiError> {
+    let state = context
+        .state
+        .parachain(para_id)
+        .ok_or_else(|| ApiError::not_found(format!("parachain {para_id} not registered")))?
+        .read()
+        .map_err(|_| ApiError::internal(format!("state lock for parachain {para_id} poisoned")))?
+        .clone();
+    let traffic = context
+        .state
+        .traffic
+        .get(&para_id)
+        .map(|counters| counters.snapshot())
+        .unwrap_or_default();
+
+    Ok(Json(ParachainStateResponse {
+        para_id,
+        state,
+        traffic,
+        online: !context.state.is_offline(para_id),
+    }))
+}
+
+/// Return a page of one parachain's log, optionally keeping only entries
+/// that contain `contains`.
+pub async fn get_parachain_logs(
+    State(context): State<ApiContext>,
+    Path(para_id): Path<u32>,
+    Query(query): Query<LogQuery>,
+) -> Result<Json<LogPage>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LOG_PAGE);
+    if limit > MAX_LOG_PAGE {
+        return Err(MessageValidationError::invalid_payload(format!(
+            "limit {limit} exceeds the maximum of {MAX_LOG_PAGE}"
+        ))
+        .into());
+    }
+    let state = context
+        .state
+        .parachain(para_id)
+        .ok_or_else(|| ApiError::not_found(format!("parachain {para_id} not registered")))?
+        .read()
+        .map_err(|_| ApiError::internal(format!("state lock for parachain {para_id} poisoned")))?;
+    let matching = state.logs.iter().filter(|entry| {
+        query
+            .contains
+            .as_deref()
+            .is_none_or(|needle| entry.contains(needle))
+    });
+    let mut total = 0;
+    let mut entries = Vec::new();
+    for entry in matching {
+        if total >= query.offset && entries.len() < limit {
+            entries.push(entry.clone());
+        }
+        total += 1;
+    }
+
+    Ok(Json(LogPage {
+        para_id,
+        total,
+        offset: query.offset,
+        entries,
+    }))
+}
+
+/// Return the XCM versions `para_id` accepts, so clients can pick one
+/// before composing a message.
+pub async fn get_parachain_versions(
+    State(context): State<ApiContext>,
+    Path(para_id): Path<u32>,
+) -> Result<Json<VersionsResponse>, ApiError> {
+    if context.state.parachain(para_id).is_none() {
+        return Err(ApiError::not_found(format!(
+            "parachain {para_id} not registered"
+        )));
+    }
+    let configured = context.config.parachains.xcm_version_for(para_id);
+    Ok(Json(VersionsResponse {
+        para_id,
+        versions: configured.parse().into_iter().collect(),
+    }))
+}
+
+/// Return the nonce `para_id` must put on its next envelope.
+///
+/// Refused unless the service enforces nonces (`processor.enforce_nonces`).
+pub async fn get_parachain_nonce(
+    State(context): State<ApiContext>,
+    Path(para_id): Path<u32>,
+) -> Result<Json<NonceResponse>, ApiError> {
+    if context.state.parachain(para_id).is_none() {
+        return Err(ApiError::not_found(format!(
+            "parachain {para_id} not registered"
+        )));
+    }
+    let next_nonce = context.processor.next_nonce(para_id).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::CONFLICT,
+            "Conflict",
+            "the service does not track nonces; set processor.enforce_nonces",
+        )
+    })?;
+    Ok(Json(NonceResponse {
+        para_id,
+        next_nonce,
+    }))
+}
+
+/// Export every parachain's balances as `para_id,account,asset,amount` CSV
+/// rows, ordered by parachain, account, and then asset.
+pub async fn export_balances_csv(
+    State(context): State<ApiContext>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut para_ids: Vec<u32> = context.state.parachains.keys().copied().collect();
+    para_ids.sort_unstable();
+
+    let mut csv = String::from("para_id,account,asset,amount\n");
+    for para_id in para_ids {
+        let parachain = context.state.parachains[&para_id].read().map_err(|_| {
+            ApiError::internal(format!("state lock for parachain {para_id} poisoned"))
+        })?;
+        let mut balances: Vec<_> = parachain
+            .balances
+            .iter()
+            .flat_map(|(account, assets)| {
+                assets
+                    .iter()
+                    .map(move |(asset, balance)| (account, asset, balance))
+            })
+            .collect();
+        balances.sort_unstable_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        for (account, asset, balance) in balances {
+            csv.push_str(&format!(
+                "{para_id},{},{},{balance}\n",
+                csv_field(account),
+                csv_field(asset)
+            ));
+        }
+    }
+
+    Ok(([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], csv))
+}
+
+/// Quote a CSV field when it contains a separator, quote, or line break.
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+fn count_messages(state: &ServiceState) -> Result<MessageCounts, ApiError> {
+    let messages = state
+        .messages
+        .read()
+        .map_err(|_| ApiError::internal("state lock poisoned"))?;
+    let mut counts = MessageCounts::default();
+    for (_, record) in messages.iter() {
+        counts.total += 1;
+        match record.status {
+            MessageStatus::Pending => counts.pending += 1,
+            MessageStatus::Relayed => counts.relayed += 1,
+            MessageStatus::Executed { .. } => counts.executed += 1,
+            MessageStatus::Failed { .. } => counts.failed += 1,
+            MessageStatus::Cancelled => counts.cancelled += 1,
+        }
+    }
+    Ok(counts)
+}
+
+/// Report relay backlog and worker status for operators.
+pub async fn get_diagnostics(
+    State(context): State<ApiContext>,
+) -> Result<Json<DiagnosticsResponse>, ApiError> {
+    let relay = &context.state.relay;
+    Ok(Json(DiagnosticsResponse {
+        queue_depth: relay.queue_depth.load(Ordering::Relaxed),
+        active_workers: relay.active_workers.load(Ordering::Relaxed),
+        paused: context.pause.is_paused(),
+        messages: count_messages(&context.state)?,
+    }))
+}
+
+/// Summarise message statuses and per-parachain traffic.
+pub async fn get_stats(State(context): State<ApiContext>) -> Result<Json<StatsResponse>, ApiError> {
+    let counts = count_messages(&context.state)?;
+    let traffic = context
+        .state
+        .traffic
+        .iter()
+        .map(|(para_id, counters)| (*para_id, counters.snapshot()))
+        .collect();
+
+    Ok(Json(StatsResponse {
+        parachains: context.state.parachain_count(),
+        messages: counts,
+        traffic,
+    }))
+}
+
+/// Return the configuration in effect, including reloaded settings, minus
+/// secrets.
+pub async fn get_config(State(context): State<ApiContext>) -> Json<AppConfig> {
+    Json(context.reloader.effective().redacted())
+}
+
+/// Optional body for a key rotation; a key is generated when omitted.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateKeyRequest {
+    pub secret_key: Option<String>,
+}
+
+/// Public key installed by a rotation.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateKeyResponse {
+    pub para_id: u32,
+    pub public_key: String,
+}
+
+/// Swap in a new signing key for a parachain.
+pub async fn rotate_key(
+    State(context): State<ApiContext>,
+    Path(para_id): Path<u32>,
+    request: Option<Json<RotateKeyRequest>>,
+) -> Result<Json<RotateKeyResponse>, ApiError> {
+    let Json(request) = request.unwrap_or_default();
+    let public_key = context
+        .keys
+        .rotate(para_id, request.secret_key.as_deref())?;
+    tracing::info!(target: "xcm_lite::admin", para_id, "rotated parachain key");
+    Ok(Json(RotateKeyResponse {
+        para_id,
+        public_key,
+    }))
+}
+
+/// One balance to write in `/admin/balances/import`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceImportEntry {
+    pub para_id: u32,
+    pub account: String,
+    pub asset: String,
+    #[serde(with = "crate::domain::message::amount_format")]
+    pub amount: u128,
+}
+
+/// Response body for `/admin/balances/import`.
+#[derive(Debug, Serialize)]
+pub struct BalanceImportResponse {
+    /// Indexes of the entries written, in request order.
+    pub applied: Vec<usize>,
+}
+
+/// Overwrite balances directly, for fast test setup.
+///
+/// Every entry is checked before any is written, so a request naming an
+/// unregistered parachain changes nothing.
+pub async fn import_balances(
+    State(context): State<ApiContext>,
+    Json(entries): Json<Vec<BalanceImportEntry>>,
+) -> Result<Json<BalanceImportResponse>, ApiError> {
+    let problems: Vec<ErrorBody> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, entry)| {
+            let message = if context.state.parachain(entry.para_id).is_none() {
+                format!(
+                    "entry {idx} refers to unregistered parachain {}",
+                    entry.para_id
+                )
+            } else if entry.account.trim().is_empty() {
+                format!("entry {idx} has no account")
+            } else {
+                return None;
+            };
+            Some(ErrorBody {
+                code: XcmErrorCode::InvalidPayload.to_string(),
+                message,
+                errors: Vec::new(),
+            })
+        })
+        .collect();
+    if !problems.is_empty() {
+        let mut err = ApiError::new(
+            StatusCode::BAD_REQUEST,
+            XcmErrorCode::InvalidPayload.to_string(),
+            format!(
+                "{} of {} entries are invalid",
+                problems.len(),
+                entries.len()
+            ),
+        );
+        err.errors = problems;
+        return Err(err);
+    }
+
+    let mut applied = Vec::with_capacity(entries.len());
+    for (idx, entry) in entries.into_iter().enumerate() {
+        let mut parachain = context
+            .state
+            .parachain(entry.para_id)
+            .ok_or_else(|| {
+                ApiError::not_found(format!("parachain {} not registered", entry.para_id))
+            })?
+            .write()
+            .map_err(|_| {
+                ApiError::internal(format!(
+                    "state lock for parachain {} poisoned",
+                    entry.para_id
+                ))
+            })?;
+        *parachain.balance_mut(&entry.account, &entry.asset) = Balance(entry.amount);
+        applied.push(idx);
+    }
+    tracing::info!(target: "xcm_lite::admin", entries = applied.len(), "imported balances");
+    Ok(Json(BalanceImportResponse { applied }))
+}
+
+/// Stop the relay loop from taking messages off the queue.
+pub async fn pause_relay(State(context): State<ApiContext>) -> Json<PauseResponse> {
+    context.pause.set_paused(true);
+    tracing::info!(target: "xcm_lite::admin", "relay paused");
+    Json(PauseResponse { paused: true })
+}
+
+/// Let the relay loop drain its queue again.
+pub async fn resume_relay(State(context): State<ApiContext>) -> Json<PauseResponse> {
+    context.pause.set_paused(false);
+    tracing::info!(target: "xcm_lite::admin", "relay resumed");
+    Json(PauseResponse { paused: false })
+}
+
+/// Re-read the configuration, applying the settings that can change in
+/// place and listing the changes that need a restart.
+pub async fn reload_config(
+    State(context): State<ApiContext>,
+) -> Result<Json<ReloadReport>, ApiError> {
+    let report = context.reloader.reload().map_err(|err| {
+        ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "InvalidConfig",
+            err.to_string(),
+        )
+    })?;
+    Ok(Json(report))
+}
+
+/// Response body for the parachain offline and online toggles.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailabilityResponse {
+    pub para_id: u32,
+    pub online: bool,
+}
+
+/// Mark a parachain offline so messages to it fail with
+/// `DestinationOffline` until it is brought back online.
+pub async fn set_parachain_offline(
+    State(context): State<ApiContext>,
+    Path(para_id): Path<u32>,
+) -> Result<Json<AvailabilityResponse>, ApiError> {
+    set_availability(&context, para_id, false)
+}
+
+/// Bring an offline parachain back online.
+pub async fn set_parachain_online(
+    State(context): State<ApiContext>,
+    Path(para_id): Path<u32>,
+) -> Result<Json<AvailabilityResponse>, ApiError> {
+    set_availability(&context, para_id, true)
+}
+
+fn set_availability(
+    context: &ApiContext,
+    para_id: u32,
+    online: bool,
+) -> Result<Json<AvailabilityResponse>, ApiError> {
+    if !context.state.set_offline(para_id, !online) {
+        return Err(ApiError::not_found(format!(
+            "parachain {para_id} not registered"
+        )));
+    }
+    tracing::info!(target: "xcm_lite::admin", para_id, online, "parachain availability changed");
+    Ok(Json(AvailabilityResponse { para_id, online }))
+}
+
+/// Request body for `/admin/clock/advance`.
+#[cfg(feature = "dev-mode")]
+#[derive(Debug, Deserialize)]
+pub struct AdvanceClockRequest {
+    pub seconds: u64,
+}
+
+/// Clock reading after an advance.
+#[cfg(feature = "dev-mode")]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdvanceClockResponse {
+    pub unix_millis: u64,
+}
+
+/// Move the service's test clock forward, so expiry and cooldowns can be
+/// exercised without sleeping.
+///
+/// Only routed in `dev-mode` builds, and refused unless the service runs on
+/// a test clock (`server.dev_test_clock`).
+#[cfg(feature = "dev-mode")]
+pub async fn advance_clock(
+    State(context): State<ApiContext>,
+    Json(request): Json<AdvanceClockRequest>,
+) -> Result<Json<AdvanceClockResponse>, ApiError> {
+    let clock = &context.state.clock;
+    let test_clock = clock.as_test_clock().ok_or_else(|| {
+        ApiError::new(
+            StatusCode::CONFLICT,
+            "Conflict",
+            "the service is running on the system clock; set server.dev_test_clock",
+        )
+    })?;
+    test_clock.advance(std::time::Duration::from_secs(request.seconds));
+    tracing::info!(target: "xcm_lite::admin", seconds = request.seconds, "advanced test clock");
+    Ok(Json(AdvanceClockResponse {
+        unix_millis: clock.unix_millis(),
+    }))
+}
+
+/// Stand-in for mutating routes on a read-only replica.
+pub async fn read_only() -> ApiError {
+    ApiError::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "ReadOnly",
+        "this instance is a read-only replica and does not accept changes",
+    )
+}
+
+/// Report readiness; returns 503 until startup has completed.
+pub async fn healthz(State(context): State<ApiContext>) -> impl IntoResponse {
+    let paused = context.pause.is_paused();
+    if context.ready.load(Ordering::Acquire) {
+        (
+            StatusCode::OK,
+            Json(HealthResponse {
+                status: "ok",
+                paused,
+            }),
+        )
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(HealthResponse {
+                status: "starting",
+                paused,
+            }),
+        )
+    }
+}
+
+/// Most synthetic messages one `/admin/benchmark` call may submit.
+#[cfg(feature = "dev-mode")]
+const MAX_BENCHMARK_MESSAGES: usize = 10_000;
+
+/// Request body for `/admin/benchmark`.
+#[cfg(feature = "dev-mode")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkRequest {
+    pub count: usize,
+    pub dest_para: u32,
+    /// Defaults to the lowest registered parachain other than `dest_para`.
+    #[serde(default)]
+    pub sender_para: Option<u32>,
+}
+
+/// Aggregate timing of a benchmark run, in microseconds.
+#[cfg(feature = "dev-mode")]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResponse {
+    pub submitted: usize,
+    pub rejected: usize,
+    /// First rejection, when any message was refused.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<ErrorBody>,
+    pub sign_micros: u64,
+    pub submit_micros: u64,
+    pub mean_submit_micros: u64,
+}
+
+/// Sign `count` synthetic transfers with the sender's own key and submit
+/// them through the normal processor path, timing both phases.
+///
+/// Each transfer moves 1 unit of `DOT` to a distinct `bench-<n>` account,
+/// so content deduplication does not refuse them. When nonces are
+/// enforced the transfers carry the sender's next nonces in order. Only
+/// routed in `dev-mode` builds.
+#[cfg(feature = "dev-mode")]
+pub async fn run_benchmark(
+    State(context): State<ApiContext>,
+    Json(request): Json<BenchmarkRequest>,
+) -> Result<Json<BenchmarkResponse>, ApiError> {
+    use std::time::Instant;
+
+    use crate::{
+        crypto::CryptoError,
+        domain::{Instruction, SignatureScheme, SigningEncoding, TransferReserveAsset, XcmVersion},
+    };
+
+    if request.count == 0 || request.count > MAX_BENCHMARK_MESSAGES {
+        return Err(MessageValidationError::invalid_payload(format!(
+            "count must be between 1 and {MAX_BENCHMARK_MESSAGES}"
+        ))
+        .into());
+    }
+    if context.state.parachain(request.dest_para).is_none() {
+        return Err(ApiError::not_found(format!(
+            "parachain {} not registered",
+            request.dest_para
+        )));
+    }
+    let sender_para = match request.sender_para {
+        Some(para_id) => para_id,
+        None => context
+            .state
+            .parachains
+            .keys()
+            .copied()
+            .filter(|para_id| *para_id != request.dest_para)
+            .min()
+            .ok_or_else(|| {
+                MessageValidationError::invalid_payload("no parachain available to send from")
+            })?,
+    };
+    let xcm_version: XcmVersion = context.config.parachains.xcm_version.parse()?;
+
+    let first_nonce = context.processor.next_nonce(sender_para);
+
+    let signing = Instant::now();
+    let mut envelopes = Vec::with_capacity(request.count);
+    for index in 0..request.count {
+        envelopes.push(MessageEnvelope {
+            message_id: None,
+            sender_para,
+            dest_para: request.dest_para,
+            via: Vec::new(),
+            xcm_version,
+            instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount: 1,
+                beneficiary: format!("bench-{index}"),
+            })],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: first_nonce.map(|first| first.saturating_add(index as u64)),
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        });
+    }
+    // A remote signer blocks while it waits for each signature.
+    let keys = context.keys.clone();
+    let batch = tokio::task::spawn_blocking(move || {
+        envelopes
+            .into_iter()
+            .map(|envelope| {
+                let signature = keys.sign_envelope(&envelope)?;
+                Ok((envelope, signature.to_bytes()))
+            })
+            .collect::<Result<Vec<_>, CryptoError>>()
+    })
+    .await
+    .map_err(|err| ApiError::internal(format!("signing task failed: {err}")))??;
+    let sign_micros = micros_since(signing);
+
+    let submitting = Instant::now();
+    let mut submitted = 0;
+    let mut first_error = None;
+    for (envelope, signature) in batch {
+        match context.processor.submit_message(envelope, &signature).await {
+            Ok(_) => submitted += 1,
+            Err(err) if first_error.is_none() => {
+                first_error = Some(ApiError::from(err).into_body());
+            }
+            Err(_) => {}
+        }
+    }
+    let submit_micros = micros_since(submitting);
+
+    tracing::info!(
+        target: "xcm_lite::admin",
+        count = request.count,
+        submitted,
+        submit_micros,
+        "benchmark run finished"
+    );
+    Ok(Json(BenchmarkResponse {
+        submitted,
+        rejected: request.count - submitted,
+        first_error,
+        sign_micros,
+        submit_micros,
+        mean_submit_micros: submit_micros / request.count as u64,
+    }))
+}
+
+#[cfg(feature = "dev-mode")]
+fn micros_since(started: std::time::Instant) -> u64 {
+    u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX)
+}
+
+fn validate_message_id(id: &str) -> Result<(), MessageValidationError> {
+    if id.len() > MAX_MESSAGE_ID_LEN {
+        return Err(MessageValidationError::invalid_payload(format!(
+            "message id must be at most {MAX_MESSAGE_ID_LEN} bytes"
+        )));
+    }
+    if id.chars().any(char::is_control) {
+        return Err(MessageValidationError::invalid_payload(
+            "message id must not contain control characters",
+        ));
+    }
+    Ok(())
+}
+
+fn decode_signature(envelope: &MessageEnvelope) -> Result<Vec<u8>, ApiError> {
+    let encoded = envelope.signature.as_deref().ok_or_else(|| {
+        ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            XcmErrorCode::InvalidSignature.to_string(),
+            "signature must be provided",
+        )
+    })?;
+    hex::decode(encoded.trim().trim_start_matches("0x")).map_err(|err| {
+        ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            XcmErrorCode::InvalidSignature.to_string(),
+            format!("signature is not valid hex: {err}"),
+        )
+    })
+}
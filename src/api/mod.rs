@@ -0,0 +1,2052 @@
+pub mod auth;
+pub mod encoding;
+pub mod error;
+pub mod handlers;
+pub mod wrap;
+
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use axum::{
+    error_handling::HandleErrorLayer,
+    http::StatusCode,
+    middleware,
+    routing::{any, get, post, MethodRouter},
+    BoxError, Router,
+};
+use tower::{
+    limit::GlobalConcurrencyLimitLayer, load_shed::error::Overloaded, timeout::error::Elapsed,
+    ServiceBuilder,
+};
+
+use crate::{
+    config::AppConfig,
+    crypto::KeyRegistry,
+    execution::simulation::Simulator,
+    metrics::Metrics,
+    processor::{MessageProcessor, PauseSwitch},
+    reload::ConfigReloader,
+    state::ServiceState,
+};
+
+/// Shared handles made available to every HTTP handler.
+#[derive(Clone)]
+pub struct ApiContext {
+    pub state: ServiceState,
+    /// Shared with the processor, so rotations apply to verification.
+    pub keys: KeyRegistry,
+    pub processor: Arc<MessageProcessor>,
+    pub simulator: Arc<Simulator>,
+    pub metrics: Arc<Metrics>,
+    pub config: Arc<AppConfig>,
+    /// Set once background subsystems are running; gates `/healthz`.
+    pub ready: Arc<AtomicBool>,
+    /// Shared with the relay loop; toggled by `/admin/pause` and `/admin/resume`.
+    pub pause: PauseSwitch,
+    /// Applies `POST /admin/config/reload`; `config` keeps the settings
+    /// the service started with.
+    pub reloader: ConfigReloader,
+}
+
+/// Routes that change messages or balances for clients.
+fn mutating_routes() -> Vec<(&'static str, MethodRouter<ApiContext>)> {
+    let routes = vec![
+        ("/submit", post(handlers::submit_message)),
+        ("/submit/batch", post(handlers::submit_batch)),
+        ("/replay/:id", post(handlers::replay_message)),
+        ("/status/:id/cancel", post(handlers::cancel_message)),
+        ("/status/:id/execute", post(handlers::execute_message)),
+    ];
+    #[cfg(feature = "dev-mode")]
+    let routes = {
+        let mut routes = routes;
+        routes.extend([
+            ("/admin/clock/advance", post(handlers::advance_clock)),
+            ("/admin/benchmark", post(handlers::run_benchmark)),
+        ]);
+        routes
+    };
+    routes
+}
+
+/// Operator routes, which also require `server.admin_token`.
+fn admin_routes() -> Vec<(&'static str, MethodRouter<ApiContext>)> {
+    vec![
+        ("/admin/keys/:id/rotate", post(handlers::rotate_key)),
+        ("/admin/pause", post(handlers::pause_relay)),
+        ("/admin/resume", post(handlers::resume_relay)),
+        ("/admin/balances/import", post(handlers::import_balances)),
+        ("/admin/config/reload", post(handlers::reload_config)),
+        (
+            "/parachains/:id/offline",
+            post(handlers::set_parachain_offline),
+        ),
+        (
+            "/parachains/:id/online",
+            post(handlers::set_parachain_online),
+        ),
+    ]
+}
+
+/// Build the HTTP router for the service.
+///
+/// Read-only replicas answer every mutating and operator route with 405.
+pub fn router(context: ApiContext) -> Router {
+    let read_only = context.config.server.read_only;
+    let mount = |routes: Router<ApiContext>, (path, route)| {
+        if read_only {
+            routes.route(path, any(handlers::read_only))
+        } else {
+            routes.route(path, route)
+        }
+    };
+    let mutating = mutating_routes().into_iter().fold(Router::new(), mount);
+    let admin = admin_routes().into_iter().fold(Router::new(), mount);
+    let admin = if read_only {
+        admin
+    } else {
+        admin.route_layer(middleware::from_fn_with_state(
+            context.config.server.admin_token.clone(),
+            auth::require_admin,
+        ))
+    };
+
+    let timeout = Duration::from_millis(context.config.server.request_timeout_ms);
+    let bounded_routes = Router::new()
+        .merge(mutating)
+        .merge(admin)
+        .route("/signing-bytes", post(handlers::signing_bytes))
+        .route("/simulate", post(handlers::simulate_message))
+        .route("/plan", post(handlers::plan_message))
+        .route("/estimate-weight", post(handlers::estimate_weight))
+        .route("/messages", get(handlers::list_messages))
+        .route("/status/batch", post(handlers::get_status_batch))
+        .route("/status/:id", get(handlers::get_status))
+        .route("/parachains/:id/state", get(handlers::get_parachain_state))
+        .route("/parachains/:id/logs", get(handlers::get_parachain_logs))
+        .route("/parachains/:id/nonce", get(handlers::get_parachain_nonce))
+        .route(
+            "/parachains/:id/versions",
+            get(handlers::get_parachain_versions),
+        )
+        .route(
+            "/parachains/balances.csv",
+            get(handlers::export_balances_csv),
+        )
+        .route("/stats", get(handlers::get_stats))
+        .route("/diagnostics", get(handlers::get_diagnostics))
+        .route("/audit", get(handlers::get_audit))
+        .route("/audit/verify", get(handlers::verify_audit))
+        .route("/metrics", get(handlers::get_metrics))
+        .route("/config", get(handlers::get_config))
+        .route("/healthz", get(handlers::healthz));
+
+    let limit = context.config.server.max_concurrent_requests;
+    let routes = Router::new()
+        .merge(with_request_timeout(bounded_routes, timeout))
+        .route("/events", get(handlers::events));
+    let mode = context.config.server.response_mode;
+    with_concurrency_limit(routes, limit)
+        .layer(middleware::from_fn_with_state(mode, wrap::wrap_responses))
+        .with_state(context)
+}
+
+/// Answer with 503 when a route takes longer than `timeout`.
+fn with_request_timeout(routes: Router<ApiContext>, timeout: Duration) -> Router<ApiContext> {
+    routes.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(middleware_error))
+            .timeout(timeout),
+    )
+}
+
+/// Answer with 503 while `limit` requests are already being handled. The
+/// limit is shared by every route.
+fn with_concurrency_limit(routes: Router<ApiContext>, limit: usize) -> Router<ApiContext> {
+    routes.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(middleware_error))
+            .load_shed()
+            .layer(GlobalConcurrencyLimitLayer::new(limit)),
+    )
+}
+
+async fn middleware_error(err: BoxError) -> error::ApiError {
+    if err.is::<Elapsed>() {
+        error::ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Unavailable",
+            "request timed out",
+        )
+    } else if err.is::<Overloaded>() {
+        error::ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Unavailable",
+            "too many concurrent requests",
+        )
+    } else {
+        error::ApiError::internal(format!("unhandled middleware error: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use tokio::sync::mpsc::Receiver;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        config::{ConfigSource, MessageIdMode, ParachainKeyConfig, ParachainOverride},
+        domain::{
+            Balance, Instruction, MessageEnvelope, SignatureScheme, SigningEncoding, Transact,
+            TransferReserveAsset, WithdrawAsset, XcmVersion,
+        },
+        execution::{
+            DefaultExecutionEngine, ExecutionEngine, ExecutionFailure, ExecutionOutcome,
+            NoopExecutionEngine, XcmOutcome,
+        },
+        processor::{breaker::CircuitBreaker, run_relay_loop, QueuedMessage, RelayOptions},
+        state::MessageStatus,
+    };
+
+    const ADMIN_TOKEN: &str = "test-admin-token";
+
+    fn test_context() -> (ApiContext, KeyRegistry, Receiver<QueuedMessage>) {
+        let mut config = AppConfig::default();
+        config.server.admin_token = Some(ADMIN_TOKEN.into());
+        context_with_config(config)
+    }
+
+    fn context_with_config(
+        config: AppConfig,
+    ) -> (ApiContext, KeyRegistry, Receiver<QueuedMessage>) {
+        let clock = crate::clock::from_config(&config);
+        let state = ServiceState::initialize(&config.parachains, &config.state)
+            .expect("state")
+            .with_clock(clock.clone());
+        let keys = KeyRegistry::from_config(&config.parachains)
+            .expect("keys")
+            .with_clock(clock);
+        let (processor, receiver) = MessageProcessor::new(state.clone(), keys.clone(), &config);
+        let metrics = Arc::new(Metrics::default());
+        let simulator = Simulator::new(state.clone(), &config.execution, metrics.clone())
+            .with_privileged_senders(&config.validation.privileged_senders);
+        let context = ApiContext {
+            state,
+            keys: keys.clone(),
+            processor: Arc::new(processor),
+            simulator: Arc::new(simulator),
+            metrics,
+            reloader: ConfigReloader::new(ConfigSource::Default, &config),
+            config: Arc::new(config),
+            ready: Arc::new(AtomicBool::new(false)),
+            pause: PauseSwitch::default(),
+        };
+        (context, keys, receiver)
+    }
+
+    fn signed_envelope(keys: &KeyRegistry) -> MessageEnvelope {
+        let mut envelope = MessageEnvelope {
+            message_id: Some("msg-1".into()),
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount: 10,
+                beneficiary: "acct-123".into(),
+            })],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        };
+        let signature = keys.sign_envelope(&envelope).expect("signature");
+        envelope.signature = Some(hex::encode(signature.to_bytes()));
+        envelope
+    }
+
+    async fn send(router: Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = router.oneshot(request).await.expect("response");
+        let status = response.status();
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        (status, body)
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).expect("request")
+    }
+
+    fn post_empty(uri: &str) -> Request<Body> {
+        Request::post(uri).body(Body::empty()).expect("request")
+    }
+
+    /// `request` authenticated with the test admin token.
+    fn admin(mut request: Request<Body>) -> Request<Body> {
+        let value = format!("Bearer {ADMIN_TOKEN}").parse().expect("header");
+        request.headers_mut().insert(header::AUTHORIZATION, value);
+        request
+    }
+
+    fn post_json(uri: &str, body: &impl serde::Serialize) -> Request<Body> {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(body).expect("json")))
+            .expect("request")
+    }
+
+    #[tokio::test]
+    async fn healthz_reports_unavailable_until_ready() {
+        let (context, _keys, _receiver) = test_context();
+        let ready = context.ready.clone();
+        let app = router(context);
+
+        let (status, body) = send(app.clone(), get("/healthz")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "starting");
+
+        ready.store(true, Ordering::Release);
+        let (status, body) = send(app, get("/healthz")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn submit_then_query_status() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+
+        let envelope = signed_envelope(&keys);
+        let (status, body) = send(app.clone(), post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::ACCEPTED, "{body}");
+        assert_eq!(body["messageId"], "msg-1");
+
+        let (status, body) = send(app, get("/status/msg-1")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "pending");
+    }
+
+    #[tokio::test]
+    async fn rejects_tampered_signature() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+
+        let mut envelope = signed_envelope(&keys);
+        envelope.dest_para = 1002;
+        let (status, body) = send(app, post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "InvalidSignature");
+    }
+
+    #[tokio::test]
+    async fn unknown_status_is_not_found() {
+        let (context, _keys, _receiver) = test_context();
+        let (status, body) = send(router(context), get("/status/missing")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "NotFound");
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_status_ids() {
+        let (context, _, _receiver) = test_context();
+        let app = router(context);
+
+        let long_id = "a".repeat(129);
+        let (status, body) = send(app.clone(), get(&format!("/status/{long_id}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "InvalidPayload");
+
+        let (status, _) = send(app, get("/status/msg%0A1")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn reports_every_validation_error_together() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+
+        let mut envelope = signed_envelope(&keys);
+        envelope
+            .instructions
+            .push(Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount: 0,
+                beneficiary: String::new(),
+            }));
+        envelope.xcm_version = XcmVersion::V4;
+
+        let (status, body) = send(app, post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "VersionMismatch");
+        let errors = body["errors"].as_array().expect("errors");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1]["code"], "InvalidPayload");
+    }
+
+    #[tokio::test]
+    async fn replays_finished_message_under_new_id() {
+        let (context, keys, _receiver) = test_context();
+        let state = context.state.clone();
+        let app = router(context);
+
+        let envelope = signed_envelope(&keys);
+        send(app.clone(), post_json("/submit", &envelope)).await;
+
+        let (status, _) = send(app.clone(), post_empty("/replay/msg-1")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        state
+            .messages
+            .write()
+            .expect("lock")
+            .get_mut("msg-1")
+            .expect("record")
+            .status = MessageStatus::Executed {
+            outcome: XcmOutcome::Complete { weight_used: 0 },
+            timings: None,
+        };
+
+        let (status, body) = send(app.clone(), post_empty("/replay/msg-1")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["replayedFrom"], "msg-1");
+        let replay_id = body["messageId"].as_str().expect("id").to_string();
+        assert_ne!(replay_id, "msg-1");
+
+        let (status, body) = send(app, get(&format!("/status/{replay_id}"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "pending");
+        assert_eq!(body["replayedFrom"], "msg-1");
+    }
+
+    #[tokio::test]
+    async fn batch_status_marks_unknown_ids_null() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+
+        let request = serde_json::json!({ "ids": ["msg-1", "missing"] });
+        let (status, body) = send(app, post_json("/status/batch", &request)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["msg-1"]["status"], "pending");
+        assert!(body["missing"].is_null());
+    }
+
+    #[tokio::test]
+    async fn accepted_submissions_point_at_their_status() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+
+        let response = app
+            .clone()
+            .oneshot(post_json("/submit", &signed_envelope(&keys)))
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(response.headers()[header::LOCATION], "/status/msg-1");
+
+        let mut spaced = signed_envelope(&keys);
+        spaced.message_id = Some("msg 2".into());
+        spaced.signature = Some(hex::encode(
+            keys.sign_envelope(&spaced).expect("signature").to_bytes(),
+        ));
+        let (_, body) = send(app, post_json("/submit/batch", &[spaced])).await;
+        assert_eq!(body[0]["statusUrl"], "/status/msg%202");
+    }
+
+    #[tokio::test]
+    async fn nonce_endpoint_tracks_accepted_submissions() {
+        let mut config = AppConfig::default();
+        config.processor.enforce_nonces = true;
+        let (context, keys, _receiver) = context_with_config(config);
+        let app = router(context);
+
+        let (status, body) = send(app.clone(), get("/parachains/1000/nonce")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["nextNonce"], 0);
+
+        let (status, body) = send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "InvalidNonce");
+
+        let sign = |message_id: &str| {
+            let mut envelope = signed_envelope(&keys);
+            envelope.message_id = Some(message_id.into());
+            envelope.nonce = Some(0);
+            envelope.signature = Some(hex::encode(
+                keys.sign_envelope(&envelope).expect("signature").to_bytes(),
+            ));
+            envelope
+        };
+        let (status, _) = send(app.clone(), post_json("/submit", &sign("msg-1"))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let (_, body) = send(app.clone(), get("/parachains/1000/nonce")).await;
+        assert_eq!(body["nextNonce"], 1);
+
+        let (status, _) = send(app.clone(), post_json("/submit", &sign("msg-2"))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send(app, get("/parachains/4000/nonce")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn parachain_logs_are_paged_and_filtered() {
+        let (context, _keys, _receiver) = test_context();
+        context
+            .state
+            .parachain(1001)
+            .expect("para")
+            .write()
+            .expect("lock")
+            .logs = (0..5)
+            .map(|index| {
+                format!(
+                    "{} entry {index}",
+                    if index % 2 == 0 { "even" } else { "odd" }
+                )
+            })
+            .collect();
+        let app = router(context);
+
+        let (status, body) = send(app.clone(), get("/parachains/1001/logs?limit=2&offset=1")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 5);
+        assert_eq!(
+            body["entries"],
+            serde_json::json!(["odd entry 1", "even entry 2"])
+        );
+
+        let (_, body) = send(
+            app.clone(),
+            get("/parachains/1001/logs?contains=even&offset=1"),
+        )
+        .await;
+        assert_eq!(body["total"], 3);
+        assert_eq!(
+            body["entries"],
+            serde_json::json!(["even entry 2", "even entry 4"])
+        );
+
+        let (status, _) = send(app.clone(), get("/parachains/1001/logs?limit=5000")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send(app, get("/parachains/4000/logs")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn parachains_report_their_xcm_versions() {
+        let mut config = AppConfig::default();
+        config.parachains.overrides = vec![ParachainOverride {
+            para_id: 1001,
+            xcm_version: Some(XcmVersion::V4),
+            ..ParachainOverride::default()
+        }];
+        let (context, keys, _receiver) = context_with_config(config);
+        let app = router(context);
+
+        let (status, body) = send(app.clone(), get("/parachains/1001/versions")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["versions"], serde_json::json!(["V4"]));
+        let (_, body) = send(app.clone(), get("/parachains/1000/versions")).await;
+        assert_eq!(body["versions"], serde_json::json!(["V3"]));
+        let (status, _) = send(app.clone(), get("/parachains/4000/versions")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = send(app, post_json("/submit", &signed_envelope(&keys))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "VersionMismatch");
+    }
+
+    #[tokio::test]
+    async fn lists_messages_filtered_by_route_and_status() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        let mut reverse = signed_envelope(&keys);
+        reverse.message_id = Some("msg-2".into());
+        reverse.sender_para = 1001;
+        reverse.dest_para = 1000;
+        reverse.signature = Some(hex::encode(
+            keys.sign_envelope(&reverse).expect("signature").to_bytes(),
+        ));
+        send(app.clone(), post_json("/submit", &reverse)).await;
+
+        let ids = |body: serde_json::Value| -> Vec<String> {
+            body.as_array()
+                .expect("list")
+                .iter()
+                .map(|entry| entry["messageId"].as_str().expect("id").to_string())
+                .collect()
+        };
+        let (status, body) = send(app.clone(), get("/messages")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(body), ["msg-1", "msg-2"]);
+        let (_, body) = send(app.clone(), get("/messages?sender=1000")).await;
+        assert_eq!(body[0]["destPara"], 1001);
+        assert_eq!(ids(body), ["msg-1"]);
+        let (_, body) = send(app.clone(), get("/messages?dest=1000&status=pending")).await;
+        assert_eq!(ids(body), ["msg-2"]);
+        let (_, body) = send(app.clone(), get("/messages?status=executed")).await;
+        assert!(ids(body).is_empty());
+
+        let (_, body) = send(app.clone(), get("/messages?limit=1&offset=1")).await;
+        assert_eq!(ids(body), ["msg-2"]);
+        let (status, _) = send(app.clone(), get("/messages?limit=1001")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send(app, get("/messages?status=done")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "InvalidPayload");
+        assert!(body["message"].as_str().is_some_and(
+            |message| message.ends_with("pending, relayed, executed, failed, cancelled")
+        ));
+    }
+
+    #[tokio::test]
+    async fn wrapped_mode_wraps_successes_and_errors() {
+        let mut config = AppConfig::default();
+        config.server.response_mode = crate::config::ResponseMode::Wrapped;
+        let (context, keys, _receiver) = context_with_config(config);
+        let app = router(context);
+
+        let (status, body) = send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["success"], true);
+        assert_eq!(body["data"]["messageId"], "msg-1");
+
+        let (status, body) = send(app.clone(), get("/status/missing")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"]["code"], "NotFound");
+        assert!(body.get("data").is_none());
+
+        let (status, body) = send(app, post_json("/status/batch", &"not an object")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"]["code"], "UnprocessableEntity");
+    }
+
+    #[tokio::test]
+    async fn accept_header_selects_wrapped_responses() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+
+        let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+        assert_eq!(body["status"], "pending");
+
+        let request = Request::get("/status/msg-1")
+            .header(header::ACCEPT, wrap::WRAPPED_CONTENT_TYPE)
+            .body(Body::empty())
+            .expect("request");
+        let (_, body) = send(app, request).await;
+        assert_eq!(body["success"], true);
+        assert_eq!(body["data"]["status"], "pending");
+    }
+
+    #[tokio::test]
+    async fn signing_bytes_match_envelope_encoding() {
+        let (context, keys, _receiver) = test_context();
+        let envelope = signed_envelope(&keys);
+        let (status, body) = send(router(context), post_json("/signing-bytes", &envelope)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["signingBytes"], hex::encode(envelope.signing_bytes()));
+    }
+
+    #[tokio::test]
+    async fn counts_sent_messages_per_parachain() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+
+        let (status, body) = send(app.clone(), get("/parachains/1000/state")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["sent"], 1);
+        assert_eq!(body["received"], 0);
+
+        let (_, body) = send(app, get("/stats")).await;
+        assert_eq!(body["messages"]["pending"], 1);
+        assert_eq!(body["traffic"]["1000"]["sent"], 1);
+    }
+
+    #[tokio::test]
+    async fn exports_balances_as_csv() {
+        let (context, _keys, _receiver) = test_context();
+        {
+            let mut dest = context
+                .state
+                .parachain(1001)
+                .expect("dest")
+                .write()
+                .expect("lock");
+            *dest.balance_mut("acct-123", "KSM") = Balance(3);
+            *dest.balance_mut("acct-123", "DOT") = Balance(10);
+            *dest.balance_mut("acct,quoted", "DOT") = Balance(5);
+        }
+
+        let response = router(context)
+            .oneshot(get("/parachains/balances.csv"))
+            .await
+            .expect("response");
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let text = String::from_utf8(bytes.to_vec()).expect("utf8");
+        assert_eq!(
+            text,
+            "para_id,account,asset,amount\n1001,\"acct,quoted\",DOT,5\n1001,acct-123,DOT,10\n1001,acct-123,KSM,3\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn imports_balances_only_when_every_entry_is_valid() {
+        let (context, _keys, _receiver) = test_context();
+        let state = context.state.clone();
+        let app = router(context);
+        let entry = |para_id: u32, account: &str| serde_json::json!({ "paraId": para_id, "account": account, "asset": "DOT", "amount": 40 });
+
+        let body = serde_json::json!([entry(1001, "acct-1"), entry(4000, "acct-2")]);
+        let (status, response) = send(
+            app.clone(),
+            admin(post_json("/admin/balances/import", &body)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response["errors"].as_array().expect("errors").len(), 1);
+        assert!(state
+            .parachain(1001)
+            .expect("para")
+            .read()
+            .expect("lock")
+            .balances
+            .is_empty());
+
+        let body = serde_json::json!([entry(1001, "acct-1"), entry(1002, "acct-2")]);
+        let (status, response) = send(app, admin(post_json("/admin/balances/import", &body))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response["applied"], serde_json::json!([0, 1]));
+        let para = state.parachain(1002).expect("para").read().expect("lock");
+        assert_eq!(para.balance("acct-2", "DOT"), Balance(40));
+    }
+
+    #[tokio::test]
+    async fn imports_a_separate_balance_per_asset() {
+        let (context, _keys, _receiver) = test_context();
+        let state = context.state.clone();
+        let app = router(context);
+        let body = serde_json::json!([
+            { "paraId": 1001, "account": "acct-1", "asset": "DOT", "amount": 40 },
+            { "paraId": 1001, "account": "acct-1", "asset": "KSM", "amount": 7 },
+        ]);
+
+        let (status, _) = send(app.clone(), post_json("/admin/balances/import", &body)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = send(app, admin(post_json("/admin/balances/import", &body))).await;
+        assert_eq!(status, StatusCode::OK);
+        let para = state.parachain(1001).expect("para").read().expect("lock");
+        assert_eq!(para.balance("acct-1", "DOT"), Balance(40));
+        assert_eq!(para.balance("acct-1", "KSM"), Balance(7));
+    }
+
+    #[tokio::test]
+    async fn queues_the_signed_payload_before_normalizing_assets() {
+        let mut config = AppConfig::default();
+        config.validation.multilocation_assets = true;
+        let (context, keys, mut receiver) = context_with_config(config);
+        let mut envelope = signed_envelope(&keys);
+        envelope.instructions = vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+            asset: r#"{"parents":1}"#.into(),
+            amount: 10,
+            beneficiary: "acct-123".into(),
+        })];
+        let signature = keys.sign_envelope(&envelope).expect("signature");
+        envelope.signature = Some(hex::encode(signature.to_bytes()));
+
+        let (status, _) = send(router(context), post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let queued = receiver.recv().await.expect("queued");
+        assert_eq!(queued.raw_payload, envelope.signing_bytes());
+        assert!(matches!(
+            &queued.envelope.instructions[0],
+            Instruction::TransferReserveAsset(transfer) if transfer.asset == "1"
+        ));
+    }
+
+    #[tokio::test]
+    async fn transfer_caps_count_every_spelling_of_an_asset() {
+        let mut config = AppConfig::default();
+        config.validation.multilocation_assets = true;
+        config.validation.max_message_transfer_total = 15;
+        let (context, keys, _receiver) = context_with_config(config);
+        let mut envelope = signed_envelope(&keys);
+        envelope.instructions = ["1", r#"{"parents":1}"#]
+            .into_iter()
+            .map(|asset| {
+                Instruction::TransferReserveAsset(TransferReserveAsset {
+                    asset: asset.into(),
+                    amount: 10,
+                    beneficiary: "acct-123".into(),
+                })
+            })
+            .collect();
+        let signature = keys.sign_envelope(&envelope).expect("signature");
+        envelope.signature = Some(hex::encode(signature.to_bytes()));
+
+        let (status, body) = send(router(context), post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["message"]
+            .as_str()
+            .is_some_and(|message| message.contains("per-message maximum of 15")));
+    }
+
+    #[tokio::test]
+    async fn read_only_replica_rejects_submissions() {
+        let mut config = AppConfig::default();
+        config.server.read_only = true;
+        let (context, keys, _receiver) = context_with_config(config);
+        let pause = context.pause.clone();
+        let app = router(context);
+
+        let (status, body) = send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(body["code"], "ReadOnly");
+
+        let (status, body) = send(app.clone(), post_empty("/admin/pause")).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(body["code"], "ReadOnly");
+        assert!(!pause.is_paused());
+
+        let (status, _) = send(app, get("/status/msg-1")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn estimate_weight_sums_instruction_weights() {
+        let (context, keys, _receiver) = test_context();
+        let state = context.state.clone();
+        let app = router(context);
+        let mut envelope = signed_envelope(&keys);
+        envelope.instructions.extend([
+            Instruction::Transact(Transact {
+                call_data: "0x00".into(),
+                weight: Some(5_000),
+                origin_kind: None,
+            }),
+            Instruction::Transact(Transact {
+                call_data: "0x01".into(),
+                weight: None,
+                origin_kind: None,
+            }),
+        ]);
+        let signature = keys.sign_envelope(&envelope).expect("signature");
+        envelope.signature = Some(hex::encode(signature.to_bytes()));
+
+        let (status, body) = send(app.clone(), post_json("/estimate-weight", &envelope)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["instructionWeights"],
+            serde_json::json!([1_000, 6_000, 1_000])
+        );
+        assert_eq!(body["totalWeight"], 8_000);
+        assert!(state.messages.read().expect("lock").get("msg-1").is_none());
+
+        envelope.instructions.clear();
+        let (status, _) = send(app, post_json("/estimate-weight", &envelope)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn simulate_reports_cache_hits_in_metrics() {
+        let mut config = AppConfig::default();
+        config.execution.simulation_cache_size = 8;
+        let (context, keys, _receiver) = context_with_config(config);
+        let app = router(context);
+        let envelope = signed_envelope(&keys);
+
+        let (status, body) = send(app.clone(), post_json("/simulate", &envelope)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["cached"], false);
+        let (_, body) = send(app.clone(), post_json("/simulate", &envelope)).await;
+        assert_eq!(body["cached"], true);
+
+        let response = app.oneshot(get("/metrics")).await.expect("response");
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let text = String::from_utf8(bytes.to_vec()).expect("utf8");
+        assert!(text.contains("xcm_lite_simulation_cache_hits_total 1"));
+        assert!(text.contains("xcm_lite_simulation_cache_misses_total 1"));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "dev-mode")]
+    async fn dev_mode_accepts_unsigned_submissions() {
+        let mut config = AppConfig::default();
+        config.processor.dev_skip_signature = true;
+        let (context, keys, _receiver) = context_with_config(config);
+        let mut envelope = signed_envelope(&keys);
+        envelope.signature = Some("placeholder".into());
+
+        let (status, body) = send(router(context), post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["status"], "pending");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "dev-mode")]
+    async fn benchmark_submits_signed_synthetic_messages() {
+        let (context, _keys, _receiver) = test_context();
+        let app = router(context);
+
+        let request = serde_json::json!({ "count": 3, "destPara": 1001 });
+        let (status, body) = send(app.clone(), post_json("/admin/benchmark", &request)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["submitted"], 3);
+        assert_eq!(body["rejected"], 0);
+        let (_, body) = send(app.clone(), get("/messages?sender=1000&dest=1001")).await;
+        assert_eq!(body.as_array().expect("list").len(), 3);
+
+        let request = serde_json::json!({ "count": 0, "destPara": 1001 });
+        let (status, _) = send(app, post_json("/admin/benchmark", &request)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "dev-mode")]
+    async fn benchmark_numbers_messages_when_nonces_are_enforced() {
+        let mut config = AppConfig::default();
+        config.processor.enforce_nonces = true;
+        let (context, _keys, _receiver) = context_with_config(config);
+        let app = router(context);
+
+        let request = serde_json::json!({ "count": 3, "destPara": 1001 });
+        for _ in 0..2 {
+            let (status, body) = send(app.clone(), post_json("/admin/benchmark", &request)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["submitted"], 3, "{body}");
+            assert_eq!(body["rejected"], 0);
+        }
+        let (_, body) = send(app, get("/parachains/1000/nonce")).await;
+        assert_eq!(body["nextNonce"], 6);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "dev-mode")]
+    async fn advancing_the_test_clock_expires_rotated_keys() {
+        let (context, _keys, _receiver) = test_context();
+        let (status, _) = send(
+            router(context),
+            post_json("/admin/clock/advance", &serde_json::json!({ "seconds": 1 })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let mut config = AppConfig::default();
+        config.server.dev_test_clock = true;
+        config.server.admin_token = Some(ADMIN_TOKEN.into());
+        config.parachains.rotation_grace_ms = 60_000;
+        let (context, keys, _receiver) = context_with_config(config);
+        let first = signed_envelope(&keys);
+        let mut second = first.clone();
+        second.message_id = Some("msg-2".into());
+        let signature = keys.sign_envelope(&second).expect("signature");
+        second.signature = Some(hex::encode(signature.to_bytes()));
+        let app = router(context);
+
+        send(app.clone(), admin(post_empty("/admin/keys/1000/rotate"))).await;
+        let (status, _) = send(app.clone(), post_json("/submit", &first)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let (status, _) = send(
+            app.clone(),
+            post_json(
+                "/admin/clock/advance",
+                &serde_json::json!({ "seconds": 61 }),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = send(app, post_json("/submit", &second)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "InvalidSignature");
+    }
+
+    #[tokio::test]
+    async fn rejects_undeclared_key_scheme() {
+        let (context, keys, _receiver) = test_context();
+        let mut envelope = signed_envelope(&keys);
+        envelope.signature_scheme = SignatureScheme::Sr25519;
+        envelope.signature = None;
+        let signature = keys.sign_envelope(&envelope).expect("signature");
+        envelope.signature = Some(hex::encode(signature.to_bytes()));
+
+        let (status, body) = send(router(context), post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "InvalidSignature");
+        assert!(body["message"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("declares sr25519"));
+    }
+
+    #[tokio::test]
+    async fn streams_status_transitions_as_events() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+
+        let response = app.clone().oneshot(get("/events")).await.expect("response");
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        let (status, _) = send(app, post_json("/submit", &signed_envelope(&keys))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let mut body = response.into_body();
+        let frame = body.frame().await.expect("frame").expect("event");
+        let text = String::from_utf8(frame.into_data().expect("data").to_vec()).expect("utf8");
+        assert!(text.starts_with("event: status\n"));
+        let data: serde_json::Value = serde_json::from_str(
+            text.lines()
+                .find_map(|line| line.strip_prefix("data: "))
+                .expect("data line"),
+        )
+        .expect("json");
+        assert_eq!(data["messageId"], "msg-1");
+        assert_eq!(data["status"], "pending");
+        assert!(data["timestamp"].as_u64().is_some());
+    }
+
+    #[tokio::test]
+    async fn content_hash_ids_are_stable_across_resubmission() {
+        let mut config = AppConfig::default();
+        config.processor.message_ids = MessageIdMode::ContentHash;
+        let (context, keys, mut receiver) = context_with_config(config);
+        let app = router(context);
+
+        let mut envelope = signed_envelope(&keys);
+        envelope.message_id = None;
+        let signature = keys.sign_envelope(&envelope).expect("signature");
+        envelope.signature = Some(hex::encode(signature.to_bytes()));
+
+        let (_, first) = send(app.clone(), post_json("/submit", &envelope)).await;
+        let (status, second) = send(app, post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let id = first["messageId"].as_str().expect("id");
+        assert_eq!(id.len(), 64);
+        assert_eq!(first["messageId"], second["messageId"]);
+
+        // The resubmission is answered with the tracked message, not run again.
+        assert_eq!(receiver.recv().await.expect("queued").message_id, id);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn batch_submit_reports_each_envelope() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+
+        let mut second = signed_envelope(&keys);
+        second.message_id = Some("msg-2".into());
+        second.signature = Some(hex::encode(
+            keys.sign_envelope(&second).expect("signature").to_bytes(),
+        ));
+        let mut forged = second.clone();
+        forged.message_id = Some("msg-3".into());
+        let batch = serde_json::json!([
+            signed_envelope(&keys),
+            { "senderPara": 1000 },
+            forged,
+            second,
+        ]);
+
+        let (status, body) = send(app.clone(), post_json("/submit/batch", &batch)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["messageId"], "msg-1");
+        assert_eq!(body[1]["error"]["code"], "InvalidPayload");
+        assert_eq!(body[2]["error"]["code"], "InvalidSignature");
+        assert_eq!(body[3]["messageId"], "msg-2");
+
+        let (_, body) = send(app, get("/status/msg-2")).await;
+        assert_eq!(body["status"], "pending");
+    }
+
+    #[tokio::test]
+    async fn identical_content_is_rejected_within_the_dedup_window() {
+        let mut config = AppConfig::default();
+        config.processor.dedup_window_ms = 60_000;
+        let (context, keys, _receiver) = context_with_config(config);
+        let app = router(context);
+
+        let (status, _) = send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let mut retry = signed_envelope(&keys);
+        retry.message_id = Some("msg-2".into());
+        let signature = keys.sign_envelope(&retry).expect("signature");
+        retry.signature = Some(hex::encode(signature.to_bytes()));
+        let (status, body) = send(app, post_json("/submit", &retry)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "DuplicateContent");
+        assert!(body["message"]
+            .as_str()
+            .is_some_and(|message| message.contains("msg-1")));
+    }
+
+    #[tokio::test]
+    async fn operator_routes_require_the_admin_token() {
+        let (context, keys, _receiver) = context_with_config(AppConfig::default());
+        let (status, body) = send(router(context), post_empty("/admin/keys/1000/rotate")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "Forbidden");
+
+        let (context, _, _receiver) = test_context();
+        let app = router(context);
+        let before = keys.get(1000).expect("key").public_key_hex();
+        let forged = serde_json::json!({ "secretKey": hex::encode([9u8; 32]) });
+        let (status, body) = send(app.clone(), post_json("/admin/keys/1000/rotate", &forged)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "Unauthorized");
+
+        let mut wrong = post_empty("/admin/keys/1000/rotate");
+        let value = "Bearer not-the-token".parse().expect("header");
+        wrong.headers_mut().insert(header::AUTHORIZATION, value);
+        let (status, _) = send(app, wrong).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(keys.get(1000).expect("key").public_key_hex(), before);
+    }
+
+    #[tokio::test]
+    async fn config_reload_lists_changes_that_need_a_restart() {
+        let (context, _keys, _receiver) = test_context();
+        let app = router(context);
+        let (status, _) = send(app.clone(), post_empty("/admin/config/reload")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // The reloaded defaults lack the token the test context was given.
+        let (status, body) = send(app, admin(post_empty("/admin/config/reload"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["applied"], serde_json::json!([]));
+        assert_eq!(
+            body["restartRequired"],
+            serde_json::json!(["server.admin_token"])
+        );
+    }
+
+    #[tokio::test]
+    async fn rotated_key_rejects_old_signatures() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+        let before = keys.get(1000).expect("key").public_key_hex();
+        let envelope = signed_envelope(&keys);
+
+        let (status, body) = send(app.clone(), admin(post_empty("/admin/keys/1000/rotate"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["paraId"], 1000);
+        assert_ne!(body["publicKey"], before);
+
+        let (status, body) = send(app.clone(), post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "InvalidSignature");
+
+        let (status, _) = send(app, admin(post_empty("/admin/keys/4000/rotate"))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn paused_relay_leaves_submissions_pending() {
+        let (context, keys, receiver) = test_context();
+        context.ready.store(true, Ordering::Release);
+        let state = context.state.clone();
+        let options = RelayOptions {
+            pause: context.pause.clone(),
+            ..RelayOptions::default()
+        };
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        tokio::spawn(run_relay_loop(state, engine, receiver, options));
+        let app = router(context);
+
+        let (status, _) = send(app.clone(), post_empty("/admin/pause")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (_, body) = send(app.clone(), get("/healthz")).await;
+        assert_eq!(body["paused"], false);
+
+        let (status, _) = send(app.clone(), admin(post_empty("/admin/pause"))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, body) = send(app.clone(), get("/healthz")).await;
+        assert_eq!(body["paused"], true);
+
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+        assert_eq!(body["status"], "pending");
+
+        let (_, body) = send(app.clone(), admin(post_empty("/admin/resume"))).await;
+        assert_eq!(body["paused"], false);
+        for _ in 0..50 {
+            let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+            if body["status"] == "executed" {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("message was not executed after resuming");
+    }
+
+    #[tokio::test]
+    async fn relay_records_statuses_through_a_poisoned_store() {
+        let (context, keys, receiver) = test_context();
+        context.ready.store(true, Ordering::Release);
+        let state = context.state.clone();
+        let options = RelayOptions {
+            pause: context.pause.clone(),
+            ..RelayOptions::default()
+        };
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        tokio::spawn(run_relay_loop(state.clone(), engine, receiver, options));
+        let app = router(context);
+
+        send(app.clone(), admin(post_empty("/admin/pause"))).await;
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        let messages = state.messages.clone();
+        std::thread::spawn(move || {
+            let _guard = messages.write().expect("lock");
+            panic!("poison the message store");
+        })
+        .join()
+        .unwrap_err();
+        assert!(state.messages.is_poisoned());
+
+        send(app, admin(post_empty("/admin/resume"))).await;
+        for _ in 0..50 {
+            let status = state
+                .messages
+                .read()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .get("msg-1")
+                .map(|record| record.status.name());
+            if status == Some("executed") {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("status was not recorded through the poisoned lock");
+    }
+
+    #[tokio::test]
+    async fn messages_queued_past_the_max_age_fail_as_expired() {
+        let (context, keys, _receiver) = test_context();
+        let state = context.state.clone();
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let options = RelayOptions {
+            max_queue_age: Some(std::time::Duration::from_secs(1)),
+            ..RelayOptions::default()
+        };
+        tokio::spawn(run_relay_loop(state.clone(), engine, receiver, options));
+
+        let envelope = signed_envelope(&keys);
+        let now = std::time::Instant::now();
+        sender
+            .send(QueuedMessage {
+                message_id: "msg-1".into(),
+                raw_payload: serde_json::to_vec(&envelope).expect("payload"),
+                envelope,
+                submitted_at: now,
+                enqueued_at: now - std::time::Duration::from_secs(2),
+                release_deferred: false,
+            })
+            .await
+            .expect("send");
+
+        for _ in 0..50 {
+            let status = state
+                .messages
+                .read()
+                .expect("lock")
+                .get("msg-1")
+                .map(|record| record.status.clone());
+            if let Some(MessageStatus::Failed { error, .. }) = status {
+                assert_eq!(error, "expired in queue");
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("expired message was not failed");
+    }
+
+    #[tokio::test]
+    async fn message_failures_leave_the_breaker_closed() {
+        let (context, keys, _receiver) = test_context();
+        let state = context.state.clone();
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        let breaker = Arc::new(CircuitBreaker::new(
+            1,
+            std::time::Duration::from_secs(60),
+            crate::clock::system(),
+        ));
+        let (sender, receiver) = tokio::sync::mpsc::channel(4);
+        let options = RelayOptions {
+            breaker: Some(breaker.clone()),
+            ..RelayOptions::default()
+        };
+        tokio::spawn(run_relay_loop(state.clone(), engine, receiver, options));
+
+        let relay = |id: &str| {
+            let mut envelope = signed_envelope(&keys);
+            envelope.message_id = Some(id.into());
+            envelope.instructions = vec![Instruction::WithdrawAsset(WithdrawAsset {
+                asset: "DOT".into(),
+                amount: 5,
+            })];
+            QueuedMessage {
+                message_id: id.into(),
+                raw_payload: Vec::new(),
+                envelope,
+                submitted_at: std::time::Instant::now(),
+                enqueued_at: std::time::Instant::now(),
+                release_deferred: false,
+            }
+        };
+        let status_of = |id: &str| {
+            state
+                .messages
+                .read()
+                .expect("lock")
+                .get(id)
+                .map(|record| record.status.clone())
+        };
+        let wait_for_failure = |id: &'static str| async move {
+            for _ in 0..50 {
+                if let Some(MessageStatus::Failed { error, .. }) = status_of(id) {
+                    return error;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            panic!("{id} did not fail");
+        };
+
+        sender.send(relay("msg-1")).await.expect("send");
+        let error = wait_for_failure("msg-1").await;
+        assert!(error.contains("too little DOT"), "{error}");
+        assert!(breaker.allows(1001));
+
+        state.set_offline(1001, true);
+        sender.send(relay("msg-2")).await.expect("send");
+        wait_for_failure("msg-2").await;
+        assert!(!breaker.allows(1001));
+    }
+
+    #[tokio::test]
+    async fn messages_to_offline_parachains_fail_without_effects() {
+        let (context, keys, receiver) = test_context();
+        let state = context.state.clone();
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        tokio::spawn(run_relay_loop(
+            state,
+            engine,
+            receiver,
+            RelayOptions::default(),
+        ));
+        let app = router(context);
+
+        let (status, _) = send(app.clone(), post_empty("/parachains/1001/offline")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = send(app.clone(), admin(post_empty("/parachains/1001/offline"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["online"], false);
+        let (_, body) = send(app.clone(), get("/parachains/1001/state")).await;
+        assert_eq!(body["online"], false);
+
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        let mut failed = false;
+        for _ in 0..50 {
+            let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+            if body["status"] == "failed" {
+                assert!(body["error"]
+                    .as_str()
+                    .is_some_and(|reason| reason.contains("offline")));
+                failed = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(failed, "message to an offline parachain did not fail");
+        let (_, body) = send(app.clone(), get("/parachains/1001/state")).await;
+        assert!(body["balances"].get("acct-123").is_none());
+
+        let (_, body) = send(app.clone(), admin(post_empty("/parachains/1001/online"))).await;
+        assert_eq!(body["online"], true);
+        let (status, _) = send(app, admin(post_empty("/parachains/4000/offline"))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    /// Records the order messages execute in; `slow` takes 100ms.
+    struct RecordingEngine(std::sync::Mutex<Vec<String>>);
+
+    impl ExecutionEngine for RecordingEngine {
+        fn execute(&self, message: &MessageEnvelope) -> Result<ExecutionOutcome, ExecutionFailure> {
+            let id = message.message_id.clone().unwrap_or_default();
+            if id == "slow" {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            self.0.lock().expect("order").push(id);
+            Ok(ExecutionOutcome {
+                logs: Vec::new(),
+                instructions_executed: message.instructions.len(),
+                timings: None,
+            })
+        }
+
+        fn engine_name(&self) -> &str {
+            "recording"
+        }
+    }
+
+    async fn submit_to(app: &Router, keys: &KeyRegistry, id: &str, dest_para: u32) {
+        let mut envelope = signed_envelope(keys);
+        envelope.message_id = Some(id.into());
+        envelope.dest_para = dest_para;
+        let signature = keys.sign_envelope(&envelope).expect("signature");
+        envelope.signature = Some(hex::encode(signature.to_bytes()));
+        let (status, _) = send(app.clone(), post_json("/submit", &envelope)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn per_destination_ordering_keeps_each_destination_in_order() {
+        let (context, keys, receiver) = test_context();
+        let state = context.state.clone();
+        let app = router(context);
+        for (id, dest_para) in [("slow", 1001), ("after", 1001), ("other", 1002)] {
+            submit_to(&app, &keys, id, dest_para).await;
+        }
+
+        let engine = Arc::new(RecordingEngine(std::sync::Mutex::default()));
+        let options = RelayOptions {
+            ordering: crate::config::RelayOrdering::SequentialPerDestination,
+            ..RelayOptions::default()
+        };
+        tokio::spawn(run_relay_loop(state, engine.clone(), receiver, options));
+        for _ in 0..100 {
+            let order = engine.0.lock().expect("order").clone();
+            if order.len() == 3 {
+                assert_eq!(order, ["other", "slow", "after"]);
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("messages were not all executed");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pausing_holds_messages_already_handed_to_a_lane() {
+        let (context, keys, receiver) = test_context();
+        let state = context.state.clone();
+        let app = router(context);
+        submit_to(&app, &keys, "slow", 1001).await;
+        submit_to(&app, &keys, "after", 1001).await;
+
+        let engine = Arc::new(RecordingEngine(std::sync::Mutex::default()));
+        let pause = PauseSwitch::default();
+        let options = RelayOptions {
+            ordering: crate::config::RelayOrdering::SequentialPerDestination,
+            pause: pause.clone(),
+            ..RelayOptions::default()
+        };
+        tokio::spawn(run_relay_loop(state, engine.clone(), receiver, options));
+        // Both messages reach the lane while `slow` is still executing.
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        pause.set_paused(true);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(*engine.0.lock().expect("order"), ["slow"]);
+
+        pause.set_paused(false);
+        for _ in 0..50 {
+            if engine.0.lock().expect("order").len() == 2 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("the lane did not resume");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn fully_parallel_ordering_caps_concurrent_messages() {
+        #[derive(Default)]
+        struct ConcurrencyEngine {
+            running: AtomicUsize,
+            peak: AtomicUsize,
+            done: AtomicUsize,
+        }
+
+        impl ExecutionEngine for ConcurrencyEngine {
+            fn execute(&self, _: &MessageEnvelope) -> Result<ExecutionOutcome, ExecutionFailure> {
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(running, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                self.done.fetch_add(1, Ordering::SeqCst);
+                Ok(ExecutionOutcome {
+                    logs: Vec::new(),
+                    instructions_executed: 0,
+                    timings: None,
+                })
+            }
+
+            fn engine_name(&self) -> &str {
+                "concurrency"
+            }
+        }
+
+        let (context, keys, receiver) = test_context();
+        let state = context.state.clone();
+        let app = router(context);
+        for idx in 0..6 {
+            submit_to(&app, &keys, &format!("msg-{idx}"), 1001).await;
+        }
+
+        let engine = Arc::new(ConcurrencyEngine::default());
+        let options = RelayOptions {
+            ordering: crate::config::RelayOrdering::FullyParallel,
+            max_parallel: Some(2),
+            ..RelayOptions::default()
+        };
+        tokio::spawn(run_relay_loop(state, engine.clone(), receiver, options));
+        for _ in 0..100 {
+            if engine.done.load(Ordering::SeqCst) == 6 {
+                assert_eq!(engine.peak.load(Ordering::SeqCst), 2);
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("messages were not all executed");
+    }
+
+    #[tokio::test]
+    async fn deferred_messages_wait_for_an_explicit_execute() {
+        let (context, keys, receiver) = test_context();
+        let state = context.state.clone();
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        let options = RelayOptions {
+            defer_execution: true,
+            ..RelayOptions::default()
+        };
+        tokio::spawn(run_relay_loop(state, engine, receiver, options));
+        let app = router(context);
+
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        let mut deferred = false;
+        for _ in 0..50 {
+            let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+            if body["deferred"] == true {
+                assert_eq!(body["status"], "relayed");
+                deferred = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(deferred, "message was not deferred");
+        let (_, body) = send(app.clone(), get("/parachains/1001/state")).await;
+        assert!(body["balances"].get("acct-123").is_none());
+
+        let (status, _) = send(app.clone(), post_empty("/status/msg-1/execute")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        for _ in 0..50 {
+            let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+            if body["status"] == "executed" {
+                let (status, _) = send(app, post_empty("/status/msg-1/execute")).await;
+                assert_eq!(status, StatusCode::CONFLICT);
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("deferred message was not executed");
+    }
+
+    #[tokio::test]
+    async fn deferred_execution_keeps_the_submit_time_and_survives_a_closed_queue() {
+        let (context, keys, mut receiver) = test_context();
+        let state = context.state.clone();
+        let app = router(context);
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        let queued = receiver.recv().await.expect("queued");
+        let defer = || {
+            let mut messages = state.messages.write().expect("lock");
+            let record = messages.get_mut("msg-1").expect("record");
+            record.status = MessageStatus::Relayed;
+            record.deferred = true;
+        };
+
+        defer();
+        let (status, _) = send(app.clone(), post_empty("/status/msg-1/execute")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let executed = receiver.recv().await.expect("queued");
+        assert_eq!(executed.submitted_at, queued.submitted_at);
+
+        defer();
+        drop(receiver);
+        let (status, _) = send(app.clone(), post_empty("/status/msg-1/execute")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let (_, body) = send(app, get("/status/msg-1")).await;
+        assert_eq!(body["deferred"], true);
+    }
+
+    #[tokio::test]
+    async fn status_names_the_engine_that_executed_it() {
+        let (context, keys, receiver) = test_context();
+        tokio::spawn(run_relay_loop(
+            context.state.clone(),
+            Arc::new(NoopExecutionEngine),
+            receiver,
+            RelayOptions::default(),
+        ));
+        let app = router(context);
+
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        for _ in 0..50 {
+            let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+            if body["status"] == "executed" {
+                assert_eq!(body["engine"], "noop");
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("message was not executed");
+    }
+
+    #[tokio::test]
+    async fn destinations_use_their_assigned_engine() {
+        let mut config = AppConfig::default();
+        config.execution.engines = vec![crate::config::EngineAssignmentConfig {
+            para_id: 1001,
+            engine: crate::config::EngineKind::Noop,
+        }];
+        let (context, keys, receiver) = context_with_config(config);
+        let state = context.state.clone();
+        let engine: Arc<dyn ExecutionEngine> = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        let options = RelayOptions {
+            engines: crate::execution::engines_by_destination(&context.config.execution, &engine),
+            ..RelayOptions::default()
+        };
+        tokio::spawn(run_relay_loop(state, engine, receiver, options));
+        let app = router(context);
+
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        let mut other = signed_envelope(&keys);
+        other.message_id = Some("msg-2".into());
+        other.dest_para = 1002;
+        other.signature = Some(hex::encode(
+            keys.sign_envelope(&other).expect("signature").to_bytes(),
+        ));
+        send(app.clone(), post_json("/submit", &other)).await;
+
+        for _ in 0..50 {
+            let (_, first) = send(app.clone(), get("/status/msg-1")).await;
+            let (_, second) = send(app.clone(), get("/status/msg-2")).await;
+            if first["status"] == "executed" && second["status"] == "executed" {
+                assert_eq!(first["engine"], "noop");
+                assert_eq!(second["engine"], "default");
+                let (_, body) = send(app.clone(), get("/parachains/1001/state")).await;
+                assert!(body["balances"].get("acct-123").is_none());
+                let (_, body) = send(app, get("/parachains/1002/state")).await;
+                assert!(body["balances"].get("acct-123").is_some());
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("messages were not executed");
+    }
+
+    #[tokio::test]
+    async fn cancelled_messages_are_skipped_by_the_relay() {
+        let (context, keys, receiver) = test_context();
+        let state = context.state.clone();
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        let app = router(context);
+
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        let (status, body) = send(app.clone(), post_empty("/status/msg-1/cancel")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "cancelled");
+        let (status, body) = send(app.clone(), post_empty("/status/msg-1/cancel")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "Conflict");
+
+        tokio::spawn(run_relay_loop(
+            state.clone(),
+            engine,
+            receiver,
+            RelayOptions::default(),
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let (_, body) = send(app, get("/status/msg-1")).await;
+        assert_eq!(body["status"], "cancelled");
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert!(dest.balances.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn executing_messages_stop_when_cancelled() {
+        struct BlockingEngine;
+
+        impl ExecutionEngine for BlockingEngine {
+            fn execute(&self, _: &MessageEnvelope) -> Result<ExecutionOutcome, ExecutionFailure> {
+                Ok(ExecutionOutcome {
+                    logs: Vec::new(),
+                    instructions_executed: 0,
+                    timings: None,
+                })
+            }
+
+            fn execute_until_cancelled(
+                &self,
+                message: &MessageEnvelope,
+                cancel: &tokio_util::sync::CancellationToken,
+            ) -> Result<ExecutionOutcome, ExecutionFailure> {
+                for _ in 0..500 {
+                    if cancel.is_cancelled() {
+                        return Err(crate::execution::ExecutionError::Cancelled.into());
+                    }
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                self.execute(message)
+            }
+
+            fn engine_name(&self) -> &str {
+                "blocking"
+            }
+        }
+
+        let (context, keys, receiver) = test_context();
+        let state = context.state.clone();
+        tokio::spawn(run_relay_loop(
+            state,
+            Arc::new(BlockingEngine),
+            receiver,
+            RelayOptions::default(),
+        ));
+        let app = router(context);
+
+        let (_, body) = send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        assert_eq!(body["cancelUrl"], "/status/msg-1/cancel");
+        for _ in 0..50 {
+            let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+            if body["status"] == "relayed" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let (status, body) = send(app.clone(), post_empty("/status/msg-1/cancel")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["status"], "cancelling");
+
+        for _ in 0..100 {
+            let (_, body) = send(app.clone(), get("/status/msg-1")).await;
+            if body["status"] == "cancelled" {
+                let (status, _) = send(app, post_empty("/status/msg-1/cancel")).await;
+                assert_eq!(status, StatusCode::CONFLICT);
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("executing message was not cancelled");
+    }
+
+    #[tokio::test]
+    async fn audit_chain_records_each_transition() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        send(app.clone(), post_empty("/status/msg-1/cancel")).await;
+
+        let (status, body) = send(app.clone(), get("/audit")).await;
+        assert_eq!(status, StatusCode::OK);
+        let entries = body["entries"].as_array().expect("entries");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1]["status"], "cancelled");
+        assert_eq!(entries[1]["prevHash"], entries[0]["hash"]);
+        assert_eq!(body["head"]["hash"], entries[1]["hash"]);
+
+        let (_, body) = send(app, get("/audit/verify")).await;
+        assert_eq!(body["valid"], true);
+        assert_eq!(body["entries"], 2);
+    }
+
+    #[tokio::test]
+    async fn audit_streams_logs_longer_than_one_batch() {
+        let (context, _keys, _receiver) = test_context();
+        let audit = context.state.audit.clone();
+        for idx in 0..600 {
+            audit.append(&format!("msg-{idx}"), &MessageStatus::Pending, idx);
+        }
+
+        let (status, body) = send(router(context), get("/audit")).await;
+        assert_eq!(status, StatusCode::OK);
+        let entries = body["entries"].as_array().expect("entries");
+        assert_eq!(entries.len(), 600);
+        assert_eq!(entries[599]["messageId"], "msg-599");
+        assert_eq!(body["head"]["hash"], entries[599]["hash"]);
+    }
+
+    #[tokio::test]
+    async fn congested_destinations_reject_submissions() {
+        let mut config = AppConfig::default();
+        config.parachains.overrides = vec![ParachainOverride {
+            para_id: 1001,
+            max_pending_messages: Some(1),
+            ..ParachainOverride::default()
+        }];
+        let (context, keys, _receiver) = context_with_config(config);
+        let app = router(context);
+
+        let (status, _) = send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let mut second = signed_envelope(&keys);
+        second.message_id = Some("msg-2".into());
+        let signature = keys.sign_envelope(&second).expect("signature");
+        second.signature = Some(hex::encode(signature.to_bytes()));
+        let (status, body) = send(app.clone(), post_json("/submit", &second)).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["code"], "DestinationCongested");
+
+        send(app.clone(), post_empty("/status/msg-1/cancel")).await;
+        let (status, _) = send(app, post_json("/submit", &second)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn diagnostics_report_queue_depth_and_workers() {
+        let (context, keys, receiver) = test_context();
+        let state = context.state.clone();
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        let app = router(context);
+
+        send(app.clone(), post_json("/submit", &signed_envelope(&keys))).await;
+        let (status, body) = send(app.clone(), get("/diagnostics")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["queueDepth"], 1);
+        assert_eq!(body["activeWorkers"], 0);
+        assert_eq!(body["paused"], false);
+        assert_eq!(body["messages"]["pending"], 1);
+
+        tokio::spawn(run_relay_loop(
+            state,
+            engine,
+            receiver,
+            RelayOptions::default(),
+        ));
+        for _ in 0..50 {
+            let (_, body) = send(app.clone(), get("/diagnostics")).await;
+            if body["messages"]["executed"] == 1 {
+                assert_eq!(body["queueDepth"], 0);
+                assert_eq!(body["activeWorkers"], 1);
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("message was not relayed");
+    }
+
+    #[tokio::test]
+    async fn submit_accepts_and_returns_msgpack() {
+        let (context, keys, _receiver) = test_context();
+        let app = router(context);
+
+        let body = rmp_serde::to_vec_named(&signed_envelope(&keys)).expect("msgpack");
+        let request = Request::post("/submit")
+            .header("content-type", encoding::MSGPACK_CONTENT_TYPE)
+            .body(Body::from(body))
+            .expect("request");
+        let response = app.oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_TYPE],
+            encoding::MSGPACK_CONTENT_TYPE
+        );
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let body: serde_json::Value = rmp_serde::from_slice(&bytes).expect("decode");
+        assert_eq!(body["messageId"], "msg-1");
+        assert_eq!(body["status"], "pending");
+    }
+
+    #[tokio::test]
+    async fn senders_are_limited_to_max_inflight_messages() {
+        let mut config = AppConfig::default();
+        config.processor.max_inflight_per_sender = Some(1);
+        let (context, keys, receiver) = context_with_config(config);
+        let state = context.state.clone();
+        let engine = Arc::new(DefaultExecutionEngine::new(
+            state.clone(),
+            &context.config.execution,
+        ));
+        let app = router(context);
+
+        let envelope_with_id = |id: &str| {
+            let mut envelope = signed_envelope(&keys);
+            envelope.message_id = Some(id.into());
+            let signature = keys.sign_envelope(&envelope).expect("signature");
+            envelope.signature = Some(hex::encode(signature.to_bytes()));
+            envelope
+        };
+
+        let (status, _) = send(
+            app.clone(),
+            post_json("/submit", &envelope_with_id("msg-1")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let (status, body) = send(
+            app.clone(),
+            post_json("/submit", &envelope_with_id("msg-2")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["code"], "TooManyInFlight");
+
+        tokio::spawn(run_relay_loop(
+            state,
+            engine,
+            receiver,
+            RelayOptions::default(),
+        ));
+        for _ in 0..50 {
+            let (status, _) = send(
+                app.clone(),
+                post_json("/submit", &envelope_with_id("msg-2")),
+            )
+            .await;
+            if status == StatusCode::ACCEPTED {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("sender slot was not released after the first message finished");
+    }
+
+    #[tokio::test]
+    async fn slow_routes_time_out_with_503() {
+        let (context, _, _receiver) = test_context();
+        let slow = Router::new().route(
+            "/slow",
+            axum::routing::get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "done"
+            }),
+        );
+        let app = with_request_timeout(slow, Duration::from_millis(10)).with_state(context);
+
+        let (status, body) = send(app, get("/slow")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["code"], "Unavailable");
+    }
+
+    #[tokio::test]
+    async fn requests_beyond_the_concurrency_limit_get_503() {
+        let (context, _, _receiver) = test_context();
+        let slow = Router::new()
+            .route(
+                "/slow",
+                axum::routing::get(|| async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    "done"
+                }),
+            )
+            .route("/fast", axum::routing::get(|| async { "done" }));
+        let app = with_concurrency_limit(slow, 1).with_state(context);
+
+        let first = tokio::spawn(app.clone().oneshot(get("/slow")));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (status, body) = send(app.clone(), get("/fast")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["message"], "too many concurrent requests");
+
+        let response = first.await.expect("join").expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(get("/fast")).await.expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn config_endpoint_redacts_key_material() {
+        let mut config = AppConfig::default();
+        config.parachains.keys = vec![ParachainKeyConfig {
+            para_id: 1000,
+            seed_phrase: Some("correct horse battery staple".into()),
+            secret_key: None,
+            public_key: None,
+        }];
+        let (context, _, _receiver) = context_with_config(config);
+
+        let (status, body) = send(router(context), get("/config")).await;
+        assert_eq!(status, StatusCode::OK);
+        let key = &body["parachains"]["keys"][0];
+        assert_eq!(key["para_id"], 1000);
+        assert_eq!(key["seed_phrase"], "***");
+        assert!(key["secret_key"].is_null());
+        assert!(!body.to_string().contains("correct horse"));
+    }
+}
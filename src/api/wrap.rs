@@ -0,0 +1,97 @@
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use futures_util::{stream, StreamExt};
+
+use super::error::ErrorBody;
+use crate::config::ResponseMode;
+
+/// `Accept` media type asking for the wrapped shape whatever
+/// `server.response_mode` says.
+pub const WRAPPED_CONTENT_TYPE: &str = "application/vnd.xcm-lite.wrapped+json";
+
+/// Longest plain-text error body folded into a wrapped error message.
+const MAX_TEXT_ERROR_LEN: usize = 16 * 1024;
+
+/// Rewrite responses into `{ success, data | error }` when wrapping is
+/// configured or requested.
+///
+/// JSON bodies are spliced into the wrapper as they stream, so large
+/// bodies such as `/audit` are never buffered. Plain-text errors, such as
+/// extractor rejections and unmatched routes, become `{ code, message }`.
+/// Other successful bodies (CSV, metrics, MessagePack, server-sent events)
+/// are not JSON and pass through untouched.
+pub async fn wrap_responses(
+    State(mode): State<ResponseMode>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let wrapped = mode == ResponseMode::Wrapped || accepts_wrapped(request.headers());
+    let response = next.run(request).await;
+    if !wrapped {
+        return response;
+    }
+
+    let success = response.status().is_success();
+    let (mut parts, body) = response.into_parts();
+    let body = if is_json(&parts.headers) {
+        let prefix = if success {
+            r#"{"success":true,"data":"#
+        } else {
+            r#"{"success":false,"error":"#
+        };
+        Body::from_stream(
+            stream::once(async move { Ok(Bytes::from_static(prefix.as_bytes())) })
+                .chain(body.into_data_stream())
+                .chain(stream::once(async { Ok(Bytes::from_static(b"}")) })),
+        )
+    } else if !success {
+        let text = to_bytes(body, MAX_TEXT_ERROR_LEN).await.unwrap_or_default();
+        let reason = parts.status.canonical_reason().unwrap_or("Error");
+        let message = String::from_utf8_lossy(&text).trim().to_string();
+        let error = ErrorBody {
+            code: reason.replace(' ', ""),
+            message: if message.is_empty() {
+                reason.to_string()
+            } else {
+                message
+            },
+            errors: Vec::new(),
+        };
+        Body::from(
+            serde_json::to_vec(&serde_json::json!({ "success": false, "error": error }))
+                .expect("error body serializes"),
+        )
+    } else {
+        return Response::from_parts(parts, body);
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, body)
+}
+
+fn accepts_wrapped(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|media_type| media_type.split(';').next())
+        .any(|media_type| media_type.trim().eq_ignore_ascii_case(WRAPPED_CONTENT_TYPE))
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"))
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::AuditConfig,
+    crypto::{signing_key_from_hex, KeypairBuildError},
+    state::MessageStatus,
+};
+
+/// Hash linked from the first entry in the chain.
+const GENESIS_HASH: [u8; 32] = [0; 32];
+
+/// One recorded status transition.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub index: u64,
+    pub message_id: String,
+    #[serde(flatten)]
+    pub status: MessageStatus,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Hex SHA-256 of the previous entry; all zeros for the first.
+    pub prev_hash: String,
+    /// Hex SHA-256 over `prev_hash` and this entry's contents.
+    pub hash: String,
+}
+
+/// Latest entry of the chain, optionally signed by the service key.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditHead {
+    pub length: u64,
+    pub hash: String,
+    /// Hex ed25519 signature over the raw head hash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+}
+
+/// Result of walking the chain.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries: u64,
+    /// Oldest entries dropped to stay within `audit.max_entries`; the chain
+    /// is checked from the first entry still held.
+    pub pruned: u64,
+    /// Index of the first entry whose hash or link does not match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken_at: Option<u64>,
+    /// Current head; clients holding an earlier signed head can check it
+    /// is still a prefix of this chain.
+    pub head: AuditHead,
+}
+
+/// Append-only, hash-chained log of message status transitions.
+///
+/// Each entry commits to the one before it, so editing or removing any
+/// entry breaks every later link. Once `audit.max_entries` is reached the
+/// oldest entries are dropped; indexes and the head keep counting from the
+/// start of the chain. Clones share the same log.
+#[derive(Clone, Default)]
+pub struct AuditLog {
+    entries: Arc<Mutex<Chain>>,
+    signer: Option<Arc<SigningKey>>,
+    /// Entries kept; zero keeps every entry.
+    max_entries: usize,
+}
+
+#[derive(Default)]
+struct Chain {
+    entries: VecDeque<AuditEntry>,
+    /// Entries dropped from the front, which is also the index of the
+    /// first entry still held.
+    pruned: u64,
+    /// Hash of the last dropped entry, which the first held entry links to;
+    /// `None` until an entry is dropped.
+    anchor: Option<String>,
+}
+
+impl Chain {
+    fn len(&self) -> u64 {
+        self.pruned + self.entries.len() as u64
+    }
+
+    /// Hash the next entry should link to.
+    fn last_hash(&self) -> String {
+        self.entries
+            .back()
+            .map(|entry| entry.hash.clone())
+            .or_else(|| self.anchor.clone())
+            .unwrap_or_else(|| hex::encode(GENESIS_HASH))
+    }
+}
+
+impl AuditLog {
+    /// Build a log that signs its head when `signing_key` is configured.
+    pub fn from_config(config: &AuditConfig) -> Result<Self, KeypairBuildError> {
+        let signer = config
+            .signing_key
+            .as_deref()
+            .map(signing_key_from_hex)
+            .transpose()?;
+        Ok(Self {
+            entries: Arc::default(),
+            signer: signer.map(Arc::new),
+            max_entries: config.max_entries,
+        })
+    }
+
+    pub fn append(&self, message_id: &str, status: &MessageStatus, timestamp: u64) {
+        // Entries are pushed whole, so a poisoned log is still consistent.
+        let mut chain = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut entry = AuditEntry {
+            index: chain.len(),
+            message_id: message_id.to_string(),
+            status: status.clone(),
+            timestamp,
+            prev_hash: chain.last_hash(),
+            hash: String::new(),
+        };
+        entry.hash = hex::encode(entry_hash(&entry));
+        chain.entries.push_back(entry);
+        if self.max_entries > 0 && chain.entries.len() > self.max_entries {
+            if let Some(dropped) = chain.entries.pop_front() {
+                chain.pruned += 1;
+                chain.anchor = Some(dropped.hash);
+            }
+        }
+    }
+
+    /// Entries still held, oldest first.
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        let chain = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        chain.entries.iter().cloned().collect()
+    }
+
+    /// Index of the oldest entry still held.
+    pub fn first_index(&self) -> u64 {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pruned
+    }
+
+    /// Held entries with indexes in `start..end`.
+    pub fn entries_between(&self, start: usize, end: usize) -> Vec<AuditEntry> {
+        let chain = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let pruned = usize::try_from(chain.pruned).unwrap_or(usize::MAX);
+        let start = start.saturating_sub(pruned);
+        let end = end.saturating_sub(pruned).min(chain.entries.len());
+        if start >= end {
+            return Vec::new();
+        }
+        chain.entries.range(start..end).cloned().collect()
+    }
+
+    pub fn head(&self) -> AuditHead {
+        let chain = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        self.head_of(&chain)
+    }
+
+    fn head_of(&self, chain: &Chain) -> AuditHead {
+        let hash = chain.last_hash();
+        let signed = self.signer.as_ref().and_then(|signer| {
+            let bytes = hex::decode(&hash).ok()?;
+            Some((
+                hex::encode(signer.sign(&bytes).to_bytes()),
+                hex::encode(signer.verifying_key().as_bytes()),
+            ))
+        });
+        let (signature, public_key) = signed.unzip();
+        AuditHead {
+            length: chain.len(),
+            hash,
+            signature,
+            public_key,
+        }
+    }
+
+    /// Recompute every hash and link from the first held entry onwards,
+    /// starting from the genesis hash or the last dropped entry's hash.
+    pub fn verify(&self) -> AuditVerification {
+        let chain = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut expected_prev = chain
+            .anchor
+            .clone()
+            .unwrap_or_else(|| hex::encode(GENESIS_HASH));
+        let broken_at = chain
+            .entries
+            .iter()
+            .find(|entry| {
+                let intact = entry.prev_hash == expected_prev
+                    && entry.hash == hex::encode(entry_hash(entry));
+                expected_prev = entry.hash.clone();
+                !intact
+            })
+            .map(|entry| entry.index);
+
+        AuditVerification {
+            valid: broken_at.is_none(),
+            entries: chain.entries.len() as u64,
+            pruned: chain.pruned,
+            broken_at,
+            head: self.head_of(&chain),
+        }
+    }
+
+    #[cfg(test)]
+    fn tamper(&self, index: usize, edit: impl FnOnce(&mut AuditEntry)) {
+        edit(&mut self.entries.lock().expect("audit lock").entries[index]);
+    }
+}
+
+fn entry_hash(entry: &AuditEntry) -> [u8; 32] {
+    let status = serde_json::to_vec(&entry.status).expect("status serialization is infallible");
+    let mut hasher = Sha256::new();
+    hasher.update(entry.prev_hash.as_bytes());
+    hasher.update(entry.index.to_le_bytes());
+    hasher.update(entry.timestamp.to_le_bytes());
+    hasher.update((entry.message_id.len() as u64).to_le_bytes());
+    hasher.update(entry.message_id.as_bytes());
+    hasher.update(status);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signature, Verifier};
+
+    use super::*;
+    use crate::execution::XcmOutcome;
+
+    fn signed_log() -> AuditLog {
+        AuditLog::from_config(&AuditConfig {
+            signing_key: Some(hex::encode([5u8; 32])),
+            ..AuditConfig::default()
+        })
+        .expect("audit log")
+    }
+
+    #[test]
+    fn untouched_chain_verifies() {
+        let log = signed_log();
+        log.append("msg-1", &MessageStatus::Pending, 1);
+        log.append(
+            "msg-1",
+            &MessageStatus::Executed {
+                outcome: XcmOutcome::Complete { weight_used: 0 },
+                timings: None,
+            },
+            2,
+        );
+
+        let entries = log.entries();
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        let verification = log.verify();
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 2);
+        let head = verification.head;
+        let signature =
+            Signature::from_slice(&hex::decode(head.signature.expect("signed")).expect("hex"))
+                .expect("signature");
+        let key = SigningKey::from_bytes(&[5u8; 32]).verifying_key();
+        assert_eq!(head.public_key, Some(hex::encode(key.as_bytes())));
+        assert!(key
+            .verify(&hex::decode(&head.hash).expect("hex"), &signature)
+            .is_ok());
+    }
+
+    #[test]
+    fn edited_entry_breaks_the_chain() {
+        let log = signed_log();
+        log.append("msg-1", &MessageStatus::Pending, 1);
+        log.append("msg-2", &MessageStatus::Pending, 2);
+        log.append("msg-1", &MessageStatus::Cancelled, 3);
+
+        log.tamper(1, |entry| entry.message_id = "msg-3".into());
+        let verification = log.verify();
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at, Some(1));
+    }
+
+    #[test]
+    fn capped_log_drops_the_oldest_entries_but_keeps_the_chain() {
+        let log = AuditLog::from_config(&AuditConfig {
+            max_entries: 2,
+            ..AuditConfig::default()
+        })
+        .expect("audit log");
+        for timestamp in 0..5 {
+            log.append("msg-1", &MessageStatus::Pending, timestamp);
+        }
+
+        let entries = log.entries();
+        assert_eq!(
+            entries.iter().map(|entry| entry.index).collect::<Vec<_>>(),
+            [3, 4]
+        );
+        assert_eq!(log.first_index(), 3);
+        assert_eq!(log.entries_between(0, 4).len(), 1);
+        let verification = log.verify();
+        assert!(verification.valid);
+        assert_eq!((verification.entries, verification.pruned), (2, 3));
+        assert_eq!(verification.head.length, 5);
+        assert_eq!(verification.head.hash, entries[1].hash);
+
+        log.tamper(0, |entry| entry.prev_hash = hex::encode(GENESIS_HASH));
+        assert_eq!(log.verify().broken_at, Some(3));
+    }
+}
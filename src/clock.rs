@@ -0,0 +1,106 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::config::AppConfig;
+
+/// Source of the current time for expiry, cooldowns, and timestamps.
+///
+/// Everything time-based reads from a shared clock so tests can swap in a
+/// [`TestClock`] and move time forward instead of sleeping.
+pub trait Clock: Send + Sync {
+    /// Monotonic time, for deadlines and expiry.
+    fn now(&self) -> Instant;
+
+    /// Wall-clock milliseconds since the Unix epoch, for timestamps.
+    fn unix_millis(&self) -> u64;
+
+    /// The controllable clock behind this one, if any.
+    fn as_test_clock(&self) -> Option<&TestClock> {
+        None
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real clock.
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Pick the clock for a service: a [`TestClock`] when
+/// `server.dev_test_clock` is set in a `dev-mode` build, otherwise the
+/// system clock.
+pub fn from_config(config: &AppConfig) -> SharedClock {
+    if cfg!(feature = "dev-mode") && config.server.dev_test_clock {
+        tracing::warn!("server.dev_test_clock is enabled: time only moves when advanced");
+        Arc::new(TestClock::new())
+    } else {
+        system()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default()
+    }
+}
+
+/// Clock frozen at its creation time until [`TestClock::advance`] is called.
+#[derive(Debug)]
+pub struct TestClock {
+    start: Instant,
+    start_unix_millis: u64,
+    offset: Mutex<Duration>,
+}
+
+impl TestClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            start_unix_millis: SystemClock.unix_millis(),
+            offset: Mutex::default(),
+        }
+    }
+
+    /// Move the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let mut offset = self.offset.lock().unwrap_or_else(PoisonError::into_inner);
+        *offset = offset.saturating_add(by);
+    }
+
+    fn offset(&self) -> Duration {
+        *self.offset.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Instant {
+        self.start + self.offset()
+    }
+
+    fn unix_millis(&self) -> u64 {
+        self.start_unix_millis + self.offset().as_millis() as u64
+    }
+
+    fn as_test_clock(&self) -> Option<&TestClock> {
+        Some(self)
+    }
+}
@@ -1,7 +1,13 @@
-use config::{Config, ConfigError as RawConfigError, Environment, File};
-use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use config::{
+    Config, ConfigError as RawConfigError, Environment, File, FileFormat, FileSourceFile,
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::domain::{MultiLocation, OriginKind, XcmVersion};
+
 /// Result alias for configuration loading.
 pub type Result<T> = std::result::Result<T, ConfigError>;
 
@@ -14,27 +20,60 @@ pub enum ConfigError {
     Invalid(String),
 }
 
+/// Where the configuration was read from, kept so it can be reloaded.
+#[derive(Debug, Clone, Default)]
+pub enum ConfigSource {
+    /// The optional `config/default` and `config/local` files.
+    #[default]
+    Default,
+    /// A file named with `--config`.
+    File(PathBuf),
+}
+
+/// Placeholder served in place of secret configuration values.
+const REDACTED: &str = "***";
+
 /// Root configuration for the XCM Lite service.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct AppConfig {
     pub server: ServerConfig,
     pub parachains: ParachainConfig,
-}
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            server: ServerConfig::default(),
-            parachains: ParachainConfig::default(),
-        }
-    }
+    pub state: StateConfig,
+    pub validation: ValidationConfig,
+    pub processor: ProcessorConfig,
+    pub relay: RelayConfig,
+    pub execution: ExecutionConfig,
+    pub audit: AuditConfig,
 }
 
 impl AppConfig {
     /// Load configuration values from files and environment variables.
+    ///
+    /// Reads the optional `config/default` and `config/local` files.
     pub fn load() -> Result<Self> {
-        let builder = Config::builder()
+        Self::load_with_files(&[
+            File::with_name("config/default").required(false),
+            File::with_name("config/local").required(false),
+        ])
+    }
+
+    /// Load configuration from the file at `path` instead of the default
+    /// search paths. Environment variables still take precedence.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_with_files(&[File::from(path.as_ref()).required(true)])
+    }
+
+    /// Load configuration again from wherever `source` says it came from.
+    pub fn load_source(source: &ConfigSource) -> Result<Self> {
+        match source {
+            ConfigSource::Default => Self::load(),
+            ConfigSource::File(path) => Self::load_from(path),
+        }
+    }
+
+    fn load_with_files(files: &[File<FileSourceFile, FileFormat>]) -> Result<Self> {
+        let mut builder = Config::builder()
             .set_default("server.host", ServerConfig::default().host)?
             .set_default("server.port", ServerConfig::default().port)?
             .set_default("parachains.count", ParachainConfig::default().count)?
@@ -42,9 +81,14 @@ impl AppConfig {
                 "parachains.xcm_version",
                 ParachainConfig::default().xcm_version,
             )?
-            .add_source(File::with_name("config/default").required(false))
-            .add_source(File::with_name("config/local").required(false))
-            .add_source(Environment::with_prefix("XCM_LITE").separator("__"));
+            .set_default(
+                "state.max_tracked_messages",
+                StateConfig::default().max_tracked_messages as u64,
+            )?;
+        for file in files {
+            builder = builder.add_source(file.clone());
+        }
+        let builder = builder.add_source(Environment::with_prefix("XCM_LITE").separator("__"));
 
         let config = builder.build()?;
         let mut parsed: AppConfig = config.try_deserialize()?;
@@ -52,18 +96,203 @@ impl AppConfig {
         Ok(parsed)
     }
 
+    /// Copy of the configuration with key material and tokens replaced by `"***"`,
+    /// safe to serve over HTTP.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        let key_secrets = config
+            .parachains
+            .keys
+            .iter_mut()
+            .flat_map(|key| [&mut key.secret_key, &mut key.seed_phrase]);
+        let other_secrets = [
+            &mut config.audit.signing_key,
+            &mut config.server.admin_token,
+        ];
+        for secret in key_secrets.chain(other_secrets) {
+            if secret.is_some() {
+                *secret = Some(REDACTED.to_owned());
+            }
+        }
+        config
+    }
+
     fn normalize(&mut self) -> Result<()> {
         self.parachains.normalize()?;
+        if let Some(directives) = &self.server.log_filter {
+            tracing_subscriber::EnvFilter::try_new(directives).map_err(|err| {
+                ConfigError::Invalid(format!("server.log_filter is invalid: {err}"))
+            })?;
+        }
+        if self.server.request_timeout_ms == 0 {
+            return Err(ConfigError::Invalid(
+                "server.request_timeout_ms must be greater than zero".into(),
+            ));
+        }
+        if self.server.max_concurrent_requests == 0 {
+            return Err(ConfigError::Invalid(
+                "server.max_concurrent_requests must be greater than zero".into(),
+            ));
+        }
+        if self.processor.max_inflight_per_sender == Some(0) {
+            return Err(ConfigError::Invalid(
+                "processor.max_inflight_per_sender must be greater than zero".into(),
+            ));
+        }
+        if self.processor.dev_skip_signature && !cfg!(feature = "dev-mode") {
+            return Err(ConfigError::Invalid(
+                "processor.dev_skip_signature requires a build with the dev-mode feature".into(),
+            ));
+        }
+        if self.relay.breaker_threshold == Some(0) {
+            return Err(ConfigError::Invalid(
+                "relay.breaker_threshold must be greater than zero".into(),
+            ));
+        }
+        if self.relay.max_parallel_messages == 0 {
+            return Err(ConfigError::Invalid(
+                "relay.max_parallel_messages must be greater than zero".into(),
+            ));
+        }
+        if self.server.dev_test_clock && !cfg!(feature = "dev-mode") {
+            return Err(ConfigError::Invalid(
+                "server.dev_test_clock requires a build with the dev-mode feature".into(),
+            ));
+        }
+        if self.state.max_tracked_messages == 0 {
+            return Err(ConfigError::Invalid(
+                "state.max_tracked_messages must be greater than zero".into(),
+            ));
+        }
+        if self.validation.multilocation_assets {
+            self.execution.normalize_assets()?;
+        }
+        let known = self.parachains.parachain_ids();
+        for reserve in &self.execution.reserves {
+            if let Some(unknown) = reserve.para_ids.iter().find(|id| !known.contains(id)) {
+                return Err(ConfigError::Invalid(format!(
+                    "reserve for {} refers to unknown parachain {unknown}",
+                    reserve.asset
+                )));
+            }
+        }
+        if let Some(unknown) = self
+            .validation
+            .privileged_senders
+            .iter()
+            .find(|id| !known.contains(id))
+        {
+            return Err(ConfigError::Invalid(format!(
+                "validation.privileged_senders refers to unknown parachain {unknown}"
+            )));
+        }
+        if let Some(unknown) = self
+            .validation
+            .disabled_instructions
+            .iter()
+            .find(|kind| XcmVersion::introducing(kind).is_none())
+        {
+            return Err(ConfigError::Invalid(format!(
+                "validation.disabled_instructions names unknown instruction {unknown}"
+            )));
+        }
+        if let Some(entry) = self
+            .validation
+            .instruction_versions
+            .iter()
+            .find(|entry| XcmVersion::introducing(&entry.instruction).is_none())
+        {
+            return Err(ConfigError::Invalid(format!(
+                "validation.instruction_versions names unknown instruction {}",
+                entry.instruction
+            )));
+        }
+        if let Some(secret) = &self.audit.signing_key {
+            crate::crypto::signing_key_from_hex(secret).map_err(|err| {
+                ConfigError::Invalid(format!("audit.signing_key is invalid: {err}"))
+            })?;
+        }
+        if let Some(fee) = self
+            .execution
+            .transfer_fees
+            .iter()
+            .find(|fee| fee.basis_points > 10_000)
+        {
+            return Err(ConfigError::Invalid(format!(
+                "execution.transfer_fees for {} exceeds 10000 basis points",
+                fee.asset
+            )));
+        }
+        if let Some(entry) = self
+            .execution
+            .accepted_senders
+            .iter()
+            .find(|entry| !known.contains(&entry.para_id))
+        {
+            return Err(ConfigError::Invalid(format!(
+                "execution.accepted_senders refers to unknown parachain {}",
+                entry.para_id
+            )));
+        }
+        if let Some(entry) = self
+            .execution
+            .engines
+            .iter()
+            .find(|entry| !known.contains(&entry.para_id))
+        {
+            return Err(ConfigError::Invalid(format!(
+                "execution.engines refers to unknown parachain {}",
+                entry.para_id
+            )));
+        }
+        if let Some(recipient) = &self.execution.fee_recipient {
+            if !known.contains(&recipient.para_id) {
+                return Err(ConfigError::Invalid(format!(
+                    "execution.fee_recipient refers to unknown parachain {}",
+                    recipient.para_id
+                )));
+            }
+            if recipient.account.trim().is_empty() {
+                return Err(ConfigError::Invalid(
+                    "execution.fee_recipient.account must be provided".into(),
+                ));
+            }
+        }
         Ok(())
     }
 }
 
 /// HTTP server configuration.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Serve read endpoints only: mutating routes are rejected with 405 and the
+    /// relay loop is not started.
+    pub read_only: bool,
+    /// Requests still running after this many milliseconds get a 503. The
+    /// `/events` stream is exempt.
+    pub request_timeout_ms: u64,
+    /// Most requests handled at once; further requests get a 503 instead of
+    /// queueing, so a flood cannot exhaust the process.
+    pub max_concurrent_requests: usize,
+    /// Whether JSON responses are returned as-is or wrapped in
+    /// `{ success, data | error }`. Clients can also ask for the wrapped
+    /// shape per request through `Accept`.
+    pub response_mode: ResponseMode,
+    /// Sign and verify a probe with every parachain key before serving, so
+    /// a broken key stops startup instead of failing the first message.
+    pub startup_selftest: bool,
+    /// Bearer token operator routes such as key rotation require. When
+    /// unset those routes answer 403.
+    pub admin_token: Option<String>,
+    /// Run on a clock that only moves through `POST /admin/clock/advance`.
+    /// Like `processor.dev_skip_signature`, only `dev-mode` builds accept it.
+    pub dev_test_clock: bool,
+    /// Log filter directives such as `xcm_lite=debug`, used instead of
+    /// `RUST_LOG` when set. Reloadable without a restart.
+    pub log_filter: Option<String>,
 }
 
 impl Default for ServerConfig {
@@ -71,18 +300,71 @@ impl Default for ServerConfig {
         Self {
             host: "0.0.0.0".to_owned(),
             port: 8080,
+            read_only: false,
+            request_timeout_ms: 30_000,
+            max_concurrent_requests: 4_096,
+            response_mode: ResponseMode::default(),
+            startup_selftest: true,
+            admin_token: None,
+            dev_test_clock: false,
+            log_filter: None,
         }
     }
 }
 
+/// Shape of HTTP response bodies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseMode {
+    /// Success bodies are the resource itself; errors are `{ code, message }`.
+    #[default]
+    Bare,
+    /// Every body is `{ "success": true, "data": ... }` or
+    /// `{ "success": false, "error": { code, message } }`.
+    Wrapped,
+}
+
 /// Configuration for the simulated parachain environment.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ParachainConfig {
     pub count: u32,
     pub xcm_version: String,
+    /// Explicit topology. When set it fully defines the registered
+    /// parachains, `count` is ignored, and every key must reference an id
+    /// from this list.
+    #[serde(default, rename = "parachain_ids")]
+    pub explicit_ids: Vec<u32>,
     #[serde(default)]
     pub keys: Vec<ParachainKeyConfig>,
+    /// Directed `(from, to)` channels messages may travel over. When empty,
+    /// every route between registered parachains is permitted.
+    #[serde(default)]
+    pub channels: Vec<(u32, u32)>,
+    /// Per-parachain settings; parachains without an entry use defaults.
+    #[serde(default)]
+    pub overrides: Vec<ParachainOverride>,
+    /// How long a rotated-out key keeps verifying signatures, so messages
+    /// signed just before a rotation are not dropped. 0 retires it at once.
+    #[serde(default)]
+    pub rotation_grace_ms: u64,
+    /// JSON Web Key Set publishing ed25519 verifying keys, each with the
+    /// parachain id as its `kid`. Published keys replace verify-only and
+    /// generated ones; parachains with a configured signing key keep it.
+    #[serde(default)]
+    pub jwks_url: Option<String>,
+    /// How often the key set is re-fetched; 0 fetches only at startup.
+    #[serde(default = "default_jwks_refresh_ms")]
+    pub jwks_refresh_ms: u64,
+    /// HTTP signing service used instead of the in-memory keys; see
+    /// `crypto::RemoteSigner`. Verification still uses the registered
+    /// keys, so configure the service's keys as `public_key` entries.
+    #[serde(default)]
+    pub remote_signer_url: Option<String>,
+}
+
+fn default_jwks_refresh_ms() -> u64 {
+    300_000
 }
 
 impl Default for ParachainConfig {
@@ -90,13 +372,58 @@ impl Default for ParachainConfig {
         Self {
             count: 3,
             xcm_version: "V3".to_owned(),
+            explicit_ids: Vec::new(),
             keys: Vec::new(),
+            channels: Vec::new(),
+            overrides: Vec::new(),
+            rotation_grace_ms: 0,
+            jwks_url: None,
+            jwks_refresh_ms: default_jwks_refresh_ms(),
+            remote_signer_url: None,
         }
     }
 }
 
+/// Settings that apply to a single registered parachain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParachainOverride {
+    pub para_id: u32,
+    /// Consensus system the parachain belongs to. Parachains without one
+    /// share the relay chain's consensus.
+    pub consensus_id: Option<String>,
+    /// Most messages that may be in flight to this parachain at once;
+    /// further submissions are rejected until some finish.
+    pub max_pending_messages: Option<u64>,
+    /// XCM version messages to this parachain must use, in place of
+    /// `parachains.xcm_version`.
+    pub xcm_version: Option<XcmVersion>,
+}
+
 impl ParachainConfig {
     fn normalize(&mut self) -> Result<()> {
+        if !self.explicit_ids.is_empty() {
+            let mut seen = std::collections::HashSet::new();
+            for &para_id in &self.explicit_ids {
+                if para_id == 0 {
+                    return Err(ConfigError::Invalid(
+                        "parachain id 0 is reserved and cannot be registered".into(),
+                    ));
+                }
+                if !seen.insert(para_id) {
+                    return Err(ConfigError::Invalid(format!(
+                        "duplicate parachain id {para_id} in parachain_ids"
+                    )));
+                }
+            }
+            if let Some(key) = self.keys.iter().find(|key| !seen.contains(&key.para_id)) {
+                return Err(ConfigError::Invalid(format!(
+                    "key configured for parachain {} which is not listed in parachain_ids",
+                    key.para_id
+                )));
+            }
+        }
+
         if !self.keys.is_empty() {
             let mut seen = std::collections::HashSet::new();
             for key in &self.keys {
@@ -109,23 +436,571 @@ impl ParachainConfig {
             }
             self.count = self.count.max(self.keys.len() as u32);
         }
+        if !self.explicit_ids.is_empty() {
+            self.count = self.explicit_ids.len() as u32;
+        }
+        let ids = self.parachain_ids();
+        if ids.is_empty() {
+            return Err(ConfigError::Invalid(
+                "configuration yields zero parachains; set parachains.count or parachains.keys"
+                    .into(),
+            ));
+        }
+        for &(from, to) in &self.channels {
+            if from == to {
+                return Err(ConfigError::Invalid(format!(
+                    "channel {from} -> {to} must connect two different parachains"
+                )));
+            }
+            if let Some(unknown) = [from, to].into_iter().find(|id| !ids.contains(id)) {
+                return Err(ConfigError::Invalid(format!(
+                    "channel {from} -> {to} references unknown parachain {unknown}"
+                )));
+            }
+        }
+        let mut overridden = std::collections::HashSet::new();
+        for entry in &self.overrides {
+            if !ids.contains(&entry.para_id) {
+                return Err(ConfigError::Invalid(format!(
+                    "override configured for unknown parachain {}",
+                    entry.para_id
+                )));
+            }
+            if !overridden.insert(entry.para_id) {
+                return Err(ConfigError::Invalid(format!(
+                    "duplicate override for parachain {}",
+                    entry.para_id
+                )));
+            }
+            if entry.max_pending_messages == Some(0) {
+                return Err(ConfigError::Invalid(format!(
+                    "max_pending_messages for parachain {} must be greater than zero",
+                    entry.para_id
+                )));
+            }
+        }
         Ok(())
     }
 
+    /// Settings for `para_id`, if any were configured.
+    pub fn override_for(&self, para_id: u32) -> Option<&ParachainOverride> {
+        self.overrides.iter().find(|entry| entry.para_id == para_id)
+    }
+
     /// Return the list of parachain ids that should be initialised.
+    ///
+    /// Explicit `parachain_ids` win; otherwise the ids of configured keys are
+    /// used, falling back to `count` ids starting at 1000.
     pub fn parachain_ids(&self) -> Vec<u32> {
-        if self.keys.is_empty() {
+        if !self.explicit_ids.is_empty() {
+            self.explicit_ids.clone()
+        } else if self.keys.is_empty() {
             (0..self.count).map(|idx| 1_000 + idx).collect()
         } else {
             self.keys.iter().map(|entry| entry.para_id).collect()
         }
     }
+
+    /// XCM version messages to `para_id` must use: its override, or
+    /// `xcm_version` otherwise.
+    pub fn xcm_version_for(&self, para_id: u32) -> String {
+        self.overrides
+            .iter()
+            .find(|entry| entry.para_id == para_id)
+            .and_then(|entry| entry.xcm_version)
+            .map_or_else(|| self.xcm_version.clone(), |version| version.to_string())
+    }
+}
+
+/// Message validation rules applied on submission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationConfig {
+    /// Reject envelopes containing fields the service does not recognise.
+    pub strict_fields: bool,
+    /// Largest amount a single transfer instruction may carry.
+    #[serde(with = "crate::domain::message::amount_format")]
+    pub max_transfer_amount: u128,
+    /// Largest total of all `transferReserveAsset` amounts in one message,
+    /// counted separately for each asset.
+    #[serde(with = "crate::domain::message::amount_format")]
+    pub max_message_transfer_total: u128,
+    /// Reject envelopes that repeat an identical instruction.
+    pub reject_duplicate_instructions: bool,
+    /// Largest hex-decoded `Transact.call_data` accepted, in bytes.
+    pub max_call_data_bytes: usize,
+    /// Limit messages between different consensus systems to asset and
+    /// query instructions; `transact` may not cross the boundary.
+    pub restrict_cross_consensus: bool,
+    /// Parachains allowed to send privileged instructions such as
+    /// `setBalance`.
+    pub privileged_senders: Vec<u32>,
+    /// Origin kinds a `transact` may request. `superuser` additionally
+    /// requires a privileged sender at execution time.
+    pub allowed_origin_kinds: Vec<OriginKind>,
+    /// Instruction kinds, by wire name, rejected service-wide regardless of
+    /// sender or version.
+    pub disabled_instructions: Vec<String>,
+    /// Replace the built-in earliest XCM version of an instruction kind,
+    /// e.g. to allow `queryResponse` under V3.
+    pub instruction_versions: Vec<InstructionVersionConfig>,
+    /// Accept messages a parachain sends to itself; they execute against
+    /// its own state.
+    pub allow_loopback: bool,
+    /// Most parachains a route may visit, counting the sender, every `via`
+    /// hop, and the destination.
+    pub max_hops: usize,
+    /// What to do when `dest_para` also appears in `via`.
+    pub dest_in_via: DestInViaPolicy,
+    /// Require every asset identifier to be a MultiLocation, which is
+    /// rewritten to its canonical string form before execution. Bare
+    /// names such as `DOT` are rejected, and the asset keys of the
+    /// per-asset `execution` settings are rewritten the same way.
+    pub multilocation_assets: bool,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            strict_fields: false,
+            max_transfer_amount: u128::MAX,
+            max_message_transfer_total: u128::MAX,
+            reject_duplicate_instructions: false,
+            max_call_data_bytes: 64 * 1024,
+            restrict_cross_consensus: false,
+            privileged_senders: Vec::new(),
+            allowed_origin_kinds: vec![
+                OriginKind::Native,
+                OriginKind::SovereignAccount,
+                OriginKind::Superuser,
+            ],
+            disabled_instructions: Vec::new(),
+            instruction_versions: Vec::new(),
+            allow_loopback: false,
+            max_hops: 3,
+            dest_in_via: DestInViaPolicy::default(),
+            multilocation_assets: false,
+        }
+    }
+}
+
+/// Earliest XCM version in which `instruction` is accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstructionVersionConfig {
+    /// Instruction kind by wire name, e.g. `queryResponse`.
+    pub instruction: String,
+    pub min_version: XcmVersion,
+}
+
+/// How the processor treats accepted submissions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessorConfig {
+    /// Source of ids for envelopes submitted without a `messageId`.
+    pub message_ids: MessageIdMode,
+    /// Most messages one sender may have in flight at once; unlimited when
+    /// unset.
+    pub max_inflight_per_sender: Option<u64>,
+    /// Refuse submissions whose content, ignoring `messageId`, matches one
+    /// accepted within this many milliseconds. 0 disables the check.
+    pub dedup_window_ms: u64,
+    /// Blocking tasks a batch submission's signatures are verified across;
+    /// 0 uses one per available CPU.
+    pub verify_parallelism: usize,
+    /// Require every envelope to carry the `nonce` its sender is expected
+    /// to use next, starting from `initial_nonce`.
+    pub enforce_nonces: bool,
+    pub initial_nonce: u64,
+    /// Accept submissions with a missing or placeholder signature, for local
+    /// development without wallets.
+    ///
+    /// Only honoured by builds with the `dev-mode` feature; any other build
+    /// refuses to start with this set, so it cannot reach production by a
+    /// config mistake alone.
+    pub dev_skip_signature: bool,
+}
+
+/// Strategy for assigning ids to envelopes that do not carry one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageIdMode {
+    /// A fresh random UUID per submission.
+    #[default]
+    Random,
+    /// A SHA-256 of the canonical signing bytes, so identical content from
+    /// the same sender always maps to the same id.
+    ContentHash,
+}
+
+/// Behaviour of the relay loop that routes and executes queued messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayConfig {
+    /// Endpoint notified with `{ messageId, status, ... }` whenever a message
+    /// reaches a terminal status. Reloadable without a restart.
+    pub webhook_url: Option<String>,
+    /// Delivery attempts per notification before giving up. Reloadable
+    /// without a restart.
+    pub webhook_max_attempts: u32,
+    /// Consecutive execution failures to a destination after which further
+    /// messages to it fail fast; the breaker is disabled when unset.
+    pub breaker_threshold: Option<u32>,
+    /// How long an open breaker rejects messages before trying again.
+    pub breaker_cooldown_ms: u64,
+    /// Order in which queued messages are executed.
+    pub ordering: RelayOrdering,
+    /// Record messages as `relayed` without executing them until
+    /// `POST /status/:id/execute`, so intermediate state can be inspected.
+    pub defer_execution: bool,
+    /// Fail messages still queued this many seconds after acceptance with
+    /// `expired in queue` instead of executing them; 0 disables the check.
+    pub max_queue_age_secs: u64,
+    /// Most messages executing at once under `fully_parallel` ordering;
+    /// further messages stay queued until one finishes.
+    pub max_parallel_messages: usize,
+}
+
+/// How the relay loop schedules queued messages.
+///
+/// More parallelism means more throughput under load, at the cost of
+/// weaker ordering between messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayOrdering {
+    /// One worker executes every message in submission order. A slow
+    /// message holds up all the others.
+    #[default]
+    Serial,
+    /// Each destination gets its own worker, so messages to one parachain
+    /// run in submission order while different parachains run side by
+    /// side. A destination whose queue fills up stalls dispatch to the rest.
+    SequentialPerDestination,
+    /// Every message runs in its own task as soon as it is dequeued, up to
+    /// `relay.max_parallel_messages` at once. Fastest, but two messages to
+    /// the same parachain may execute in either order.
+    FullyParallel,
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            webhook_max_attempts: 3,
+            breaker_threshold: None,
+            breaker_cooldown_ms: 30_000,
+            ordering: RelayOrdering::default(),
+            defer_execution: false,
+            max_queue_age_secs: 0,
+            max_parallel_messages: 64,
+        }
+    }
+}
+
+/// Settings for the execution engine and dry-run simulations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionConfig {
+    /// Number of simulation outcomes cached by payload hash; zero disables
+    /// the cache.
+    pub simulation_cache_size: usize,
+    /// Account credited with deducted fees. Fees are burned when unset.
+    pub fee_recipient: Option<FeeRecipientConfig>,
+    /// Parachains trusted as reserves per asset. When empty every sender is
+    /// trusted; otherwise transfers of unlisted assets are rejected.
+    pub reserves: Vec<ReserveConfig>,
+    /// How debits larger than the available balance are handled.
+    pub underflow_policy: UnderflowPolicy,
+    /// Measure how long each instruction takes and report it on executed
+    /// messages. Off by default to keep timing out of the hot path.
+    pub record_timings: bool,
+    /// Proportional fees deducted from reserve transfers, per asset. Assets
+    /// without an entry are transferred free of charge.
+    pub transfer_fees: Vec<TransferFeeConfig>,
+    /// Senders each destination accepts messages from. Destinations
+    /// without an entry accept every sender.
+    pub accepted_senders: Vec<AcceptedSendersConfig>,
+    /// Existential deposits per asset: no instruction may leave an account
+    /// holding more than zero but less than its asset's limit. Assets
+    /// without an entry have no limit.
+    pub dust_limits: Vec<DustLimitConfig>,
+    /// Engine executing messages for particular destinations; the rest use
+    /// the default engine.
+    pub engines: Vec<EngineAssignmentConfig>,
+}
+
+/// Execution engine implementations a destination can be assigned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineKind {
+    /// Applies instruction effects to parachain state.
+    #[default]
+    Default,
+    /// Accepts every message without touching state.
+    Noop,
+}
+
+/// Engine used for messages to `para_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineAssignmentConfig {
+    pub para_id: u32,
+    pub engine: EngineKind,
+}
+
+/// Handling of a route whose destination is also listed as a `via` hop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DestInViaPolicy {
+    /// Reject the message with `InvalidPayload`.
+    #[default]
+    Reject,
+    /// Deliver at the first occurrence, dropping the hops after it.
+    DeliverAtFirst,
+}
+
+/// Behaviour when a debit exceeds the account's balance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnderflowPolicy {
+    /// Fail the message with `InsufficientBalance`.
+    #[default]
+    Error,
+    /// Debit whatever is available, leaving the account at zero.
+    Saturate,
+}
+
+impl ExecutionConfig {
+    /// Rewrite the asset keys of per-asset settings into canonical
+    /// MultiLocation form, so they match normalised submissions.
+    fn normalize_assets(&mut self) -> Result<()> {
+        let assets = self
+            .reserves
+            .iter_mut()
+            .map(|entry| &mut entry.asset)
+            .chain(self.transfer_fees.iter_mut().map(|entry| &mut entry.asset))
+            .chain(self.dust_limits.iter_mut().map(|entry| &mut entry.asset));
+        for asset in assets {
+            let location: MultiLocation = asset.parse().map_err(|err| {
+                ConfigError::Invalid(format!(
+                    "execution asset keys must be multilocations when \
+                     validation.multilocation_assets is set: {err}"
+                ))
+            })?;
+            *asset = location.to_string();
+        }
+        Ok(())
+    }
+}
+
+/// Parachains trusted to act as the reserve for one asset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReserveConfig {
+    pub asset: String,
+    pub para_ids: Vec<u32>,
+}
+
+/// Smallest non-zero balance an account may hold after a transfer of
+/// `asset`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DustLimitConfig {
+    pub asset: String,
+    #[serde(with = "crate::domain::message::amount_format")]
+    pub limit: u128,
+}
+
+/// Sender parachains a destination trusts to execute messages on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptedSendersConfig {
+    pub para_id: u32,
+    pub senders: Vec<u32>,
+}
+
+/// Share of each reserve transfer of `asset` paid to the fee recipient.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferFeeConfig {
+    pub asset: String,
+    /// Fee in basis points: 100 takes 1% of the amount, 10000 all of it.
+    pub basis_points: u32,
+}
+
+/// Parachain account that collects execution fees.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeRecipientConfig {
+    pub para_id: u32,
+    pub account: String,
+}
+
+/// Limits applied to the in-memory state store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateConfig {
+    /// Maximum number of message records retained before the oldest
+    /// terminal-state records are evicted.
+    pub max_tracked_messages: usize,
+    /// Log entries kept per parachain; the oldest are dropped beyond this.
+    /// Zero keeps every entry.
+    pub max_logs_per_parachain: usize,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self {
+            max_tracked_messages: 10_000,
+            max_logs_per_parachain: 10_000,
+        }
+    }
+}
+
+/// Settings for the hash-chained audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Hex ed25519 secret used to sign the chain head; unsigned when unset.
+    pub signing_key: Option<String>,
+    /// Entries kept in memory; the oldest are dropped beyond this while the
+    /// head keeps its full length. Zero keeps every entry.
+    pub max_entries: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            signing_key: None,
+            max_entries: 100_000,
+        }
+    }
 }
 
 /// Configuration for pre-defined parachain keypairs.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParachainKeyConfig {
     pub para_id: u32,
     pub seed_phrase: Option<String>,
     pub secret_key: Option<String>,
+    /// Hex ed25519 public key for parachains that are only verified, never
+    /// signed for. Mutually exclusive with the secret sources.
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_empty_topology() {
+        let mut config = AppConfig::default();
+        config.parachains.count = 0;
+        let err = config.normalize().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid(_)));
+    }
+
+    #[test]
+    fn rejects_channel_to_unknown_parachain() {
+        let mut config = AppConfig::default();
+        config.parachains.channels = vec![(1000, 4000)];
+        let err = config.normalize().unwrap_err();
+        assert!(err.to_string().contains("unknown parachain 4000"));
+    }
+
+    #[test]
+    fn rejects_fee_recipient_on_unknown_parachain() {
+        let mut config = AppConfig::default();
+        config.execution.fee_recipient = Some(FeeRecipientConfig {
+            para_id: 4000,
+            account: "treasury".into(),
+        });
+        let err = config.normalize().unwrap_err();
+        assert!(err.to_string().contains("unknown parachain 4000"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "dev-mode"))]
+    fn dev_skip_signature_requires_dev_mode_build() {
+        let mut config = AppConfig::default();
+        config.processor.dev_skip_signature = true;
+        let err = config.normalize().unwrap_err();
+        assert!(err.to_string().contains("dev-mode feature"));
+    }
+
+    #[test]
+    fn normalizes_asset_keys_in_multilocation_mode() {
+        let mut config = AppConfig::default();
+        config.validation.multilocation_assets = true;
+        config.execution.dust_limits = vec![DustLimitConfig {
+            asset: r#"{"parents":1,"interior":[{"parachain":1000}]}"#.into(),
+            limit: 5,
+        }];
+        config.normalize().expect("valid config");
+        assert_eq!(config.execution.dust_limits[0].asset, "1/Parachain(1000)");
+
+        config.execution.dust_limits[0].asset = "DOT".into();
+        let err = config.normalize().unwrap_err();
+        assert!(err.to_string().contains("invalid multilocation `DOT`"));
+    }
+
+    #[test]
+    fn loads_from_explicit_path() {
+        let path = std::env::temp_dir().join(format!("xcm-lite-{}.toml", std::process::id()));
+        std::fs::write(&path, "[server]\nport = 9191\n").expect("write config");
+        let config = AppConfig::load_from(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(config.expect("config").server.port, 9191);
+        assert!(AppConfig::load_from("does/not/exist.toml").is_err());
+    }
+
+    #[test]
+    fn rejects_overrides_for_unknown_parachains() {
+        let mut config = AppConfig::default();
+        config.parachains.overrides = vec![ParachainOverride {
+            para_id: 4000,
+            ..ParachainOverride::default()
+        }];
+        let err = config.normalize().unwrap_err();
+        assert!(err.to_string().contains("unknown parachain 4000"));
+    }
+
+    fn key(para_id: u32) -> ParachainKeyConfig {
+        ParachainKeyConfig {
+            para_id,
+            seed_phrase: Some(format!("seed {para_id}")),
+            secret_key: None,
+            public_key: None,
+        }
+    }
+
+    #[test]
+    fn explicit_ids_define_topology() {
+        let mut config = AppConfig::default();
+        config.parachains.explicit_ids = vec![2000, 2004];
+        config.parachains.keys = vec![key(2004)];
+        config.normalize().expect("valid");
+
+        assert_eq!(config.parachains.parachain_ids(), vec![2000, 2004]);
+        assert_eq!(config.parachains.count, 2);
+    }
+
+    #[test]
+    fn rejects_duplicate_explicit_ids() {
+        let mut config = AppConfig::default();
+        config.parachains.explicit_ids = vec![2000, 2000];
+        let err = config.normalize().unwrap_err();
+        assert!(err.to_string().contains("duplicate parachain id 2000"));
+    }
+
+    #[test]
+    fn rejects_keys_outside_explicit_ids() {
+        let mut config = AppConfig::default();
+        config.parachains.explicit_ids = vec![2000];
+        config.parachains.keys = vec![key(3000)];
+        let err = config.normalize().unwrap_err();
+        assert!(err.to_string().contains("parachain 3000"));
+    }
+
+    #[test]
+    fn default_configuration_normalizes() {
+        let mut config = AppConfig::default();
+        assert!(config.normalize().is_ok());
+        assert_eq!(config.parachains.parachain_ids(), vec![1000, 1001, 1002]);
+    }
 }
@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::VerifyingKey;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::config::ParachainConfig;
+
+/// Per-request timeout for key set fetches.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Remote JSON Web Key Set publishing parachain verifying keys.
+pub struct JwksSource {
+    client: reqwest::Client,
+    url: String,
+    refresh: Duration,
+}
+
+#[derive(Deserialize)]
+struct KeySet {
+    keys: Vec<Jwk>,
+}
+
+/// The subset of RFC 8037 `OKP` key fields needed to verify ed25519.
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    #[serde(default)]
+    crv: Option<String>,
+    #[serde(default)]
+    x: Option<String>,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+impl JwksSource {
+    pub fn new(url: impl Into<String>, refresh: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            url: url.into(),
+            refresh,
+        }
+    }
+
+    /// Build a source when `parachains.jwks_url` is configured.
+    pub fn from_config(config: &ParachainConfig) -> Option<Self> {
+        config
+            .jwks_url
+            .as_ref()
+            .map(|url| Self::new(url.clone(), Duration::from_millis(config.jwks_refresh_ms)))
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Delay between background refreshes, or `None` to fetch only once.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        (!self.refresh.is_zero()).then_some(self.refresh)
+    }
+
+    /// Download the key set and return its ed25519 keys by parachain id.
+    pub async fn fetch(&self) -> Result<Vec<(u32, VerifyingKey)>, JwksError> {
+        let body = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(JwksError::Fetch)?
+            .bytes()
+            .await
+            .map_err(JwksError::Fetch)?;
+        parse_key_set(&body)
+    }
+}
+
+/// Parse a key set, keeping ed25519 keys whose `kid` is a parachain id.
+///
+/// Other key types and ids are skipped so a shared key set can also carry
+/// keys meant for other services; a malformed ed25519 key is an error.
+pub fn parse_key_set(body: &[u8]) -> Result<Vec<(u32, VerifyingKey)>, JwksError> {
+    let set: KeySet = serde_json::from_slice(body).map_err(JwksError::Parse)?;
+    let mut keys = Vec::new();
+    for jwk in set.keys {
+        if jwk.kty != "OKP" || jwk.crv.as_deref() != Some("Ed25519") {
+            continue;
+        }
+        let Some(para_id) = jwk.kid.as_deref().and_then(|kid| kid.parse().ok()) else {
+            continue;
+        };
+        let invalid = |reason: String| JwksError::InvalidKey { para_id, reason };
+        let x = jwk.x.ok_or_else(|| invalid("missing x".into()))?;
+        let bytes: [u8; 32] = URL_SAFE_NO_PAD
+            .decode(x)
+            .map_err(|err| invalid(err.to_string()))?
+            .try_into()
+            .map_err(|bytes: Vec<u8>| {
+                invalid(format!("x is {} bytes, expected 32", bytes.len()))
+            })?;
+        let key = VerifyingKey::from_bytes(&bytes).map_err(|err| invalid(err.to_string()))?;
+        keys.push((para_id, key));
+    }
+    Ok(keys)
+}
+
+/// Failures while loading a remote key set.
+#[derive(Debug, Error)]
+pub enum JwksError {
+    #[error("failed to fetch key set: {0}")]
+    Fetch(#[source] reqwest::Error),
+    #[error("key set is not valid JSON: {0}")]
+    Parse(#[source] serde_json::Error),
+    #[error("published key for parachain {para_id} is invalid: {reason}")]
+    InvalidKey { para_id: u32, reason: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::get, Json, Router};
+    use ed25519_dalek::{Signer, SigningKey};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::{config::ParachainKeyConfig, crypto::KeyRegistry};
+
+    fn jwk(kid: &str, key: &VerifyingKey) -> serde_json::Value {
+        serde_json::json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "kid": kid,
+            "x": URL_SAFE_NO_PAD.encode(key.as_bytes()),
+        })
+    }
+
+    #[test]
+    fn keeps_only_ed25519_keys_for_parachain_ids() {
+        let key = SigningKey::from_bytes(&[3u8; 32]).verifying_key();
+        let body = serde_json::json!({
+            "keys": [
+                jwk("1000", &key),
+                jwk("signing-service", &key),
+                { "kty": "RSA", "kid": "1001", "n": "AQAB", "e": "AQAB" },
+            ]
+        });
+        let keys = parse_key_set(body.to_string().as_bytes()).expect("key set");
+        assert_eq!(keys, vec![(1000, key)]);
+    }
+
+    #[tokio::test]
+    async fn installs_published_keys_and_falls_back_when_unreachable() {
+        let remote = SigningKey::from_bytes(&[9u8; 32]);
+        let published = remote.verifying_key();
+        let body = serde_json::json!({
+            "keys": [jwk("1000", &published), jwk("1001", &published), jwk("1002", &published)]
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let app = Router::new().route("/jwks", get(move || async move { Json(body) }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let stale = SigningKey::from_bytes(&[4u8; 32]).verifying_key();
+        let config = ParachainConfig {
+            keys: vec![
+                ParachainKeyConfig {
+                    para_id: 1000,
+                    seed_phrase: None,
+                    secret_key: None,
+                    public_key: Some(hex::encode(stale.as_bytes())),
+                },
+                ParachainKeyConfig {
+                    para_id: 1001,
+                    seed_phrase: Some("local seed".into()),
+                    secret_key: None,
+                    public_key: None,
+                },
+            ],
+            // 1002 has no configured key, so it starts with a generated one.
+            explicit_ids: vec![1000, 1001, 1002],
+            jwks_url: Some(format!("http://{addr}/jwks")),
+            jwks_refresh_ms: 0,
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config_with_jwks(&config)
+            .await
+            .expect("registry");
+        let signature = remote.sign(b"payload");
+        for para_id in [1000, 1002] {
+            assert!(registry
+                .verify_signature(para_id, b"payload", &signature.to_bytes())
+                .is_ok());
+            assert!(registry.get(para_id).expect("key").is_verify_only());
+        }
+        assert!(!registry.get(1001).expect("key").is_verify_only());
+
+        let unreachable = ParachainConfig {
+            jwks_url: Some("http://127.0.0.1:1/jwks".into()),
+            ..config
+        };
+        let registry = KeyRegistry::from_config_with_jwks(&unreachable)
+            .await
+            .expect("registry");
+        assert_eq!(*registry.get(1000).expect("key").verifying_key(), stale);
+    }
+}
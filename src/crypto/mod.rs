@@ -0,0 +1,1023 @@
+pub mod jwks;
+pub mod signer;
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    sync::{Arc, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
+
+use ed25519_dalek::{Signature, SigningKey, Verifier, VerifyingKey};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use thiserror::Error;
+
+pub use self::{
+    jwks::{JwksError, JwksSource},
+    signer::{KeypairSigner, RemoteSigner, Signer},
+};
+use crate::{
+    clock::{self, SharedClock},
+    config::{ParachainConfig, ParachainKeyConfig},
+    domain::{MessageEnvelope, SignatureScheme},
+};
+
+/// An envelope paired with its decoded signature bytes.
+pub type SignedEnvelope = (MessageEnvelope, Vec<u8>);
+
+/// Message signed by [`KeyRegistry::self_test`], under its own domain so
+/// it can never pass as an envelope.
+const SELF_TEST_PROBE: &[u8] = b"xcm-lite/self-test/v1";
+
+/// Errors produced by the cryptography subsystem.
+#[derive(Debug, Error)]
+pub enum CryptoError {
+    #[error("parachain {para_id} is not registered")]
+    UnknownParachain { para_id: u32 },
+    #[error("parachain {para_id} signs with {expected}, but the envelope declares {declared}")]
+    SchemeMismatch {
+        para_id: u32,
+        declared: SignatureScheme,
+        expected: SignatureScheme,
+    },
+    #[error("parachain {para_id} has a verify-only key and cannot sign")]
+    VerifyOnly { para_id: u32 },
+    #[error("invalid signature bytes: {0}")]
+    InvalidSignature(String),
+    #[error("remote signer failed: {0}")]
+    RemoteSigner(String),
+    #[error("key self-test failed for parachain {para_id}: {reason}")]
+    SelfTest { para_id: u32, reason: String },
+    #[error("failed to construct keypair for parachain {para_id}: {source}")]
+    InvalidKey {
+        para_id: u32,
+        #[source]
+        source: KeypairBuildError,
+    },
+}
+
+/// Holder for generated or configured keypairs keyed by parachain id.
+///
+/// Clones share the same keys, so a rotation is visible to every holder.
+#[derive(Clone)]
+pub struct KeyRegistry {
+    /// Keypairs are shared rather than copied out, so looking one up for
+    /// verification never duplicates secret key material.
+    inner: Arc<RwLock<HashMap<u32, Arc<ParachainKeypair>>>>,
+    /// Keys rotated out within the grace window, still accepted for
+    /// verification until they expire.
+    retired: Arc<RwLock<HashMap<u32, Vec<RetiredKey>>>>,
+    rotation_grace: Duration,
+    clock: SharedClock,
+    /// Produces every signature; the in-memory keys unless replaced with
+    /// [`KeyRegistry::with_signer`].
+    signer: Arc<dyn Signer>,
+}
+
+/// A previous verifying key and the moment it stops being accepted.
+#[derive(Clone)]
+struct RetiredKey {
+    verifying_key: VerifyingKey,
+    expires_at: Instant,
+}
+
+impl KeyRegistry {
+    /// Build the registry from configuration, generating keys where none are provided.
+    pub fn from_config(config: &ParachainConfig) -> Result<Self, CryptoError> {
+        let mut map = HashMap::new();
+        let mut rng = OsRng;
+
+        for para_id in config.parachain_ids() {
+            let key_config = config.keys.iter().find(|entry| entry.para_id == para_id);
+            let pair = match key_config {
+                Some(entry) => ParachainKeypair::from_config_entry(para_id, entry)
+                    .map_err(|source| CryptoError::InvalidKey { para_id, source })?,
+                None => ParachainKeypair {
+                    generated: true,
+                    ..ParachainKeypair::generate(para_id, &mut rng)
+                },
+            };
+            map.insert(para_id, Arc::new(pair));
+        }
+
+        let inner = Arc::new(RwLock::new(map));
+        Ok(Self {
+            signer: Arc::new(KeypairSigner::new(inner.clone())),
+            inner,
+            retired: Arc::default(),
+            rotation_grace: Duration::from_millis(config.rotation_grace_ms),
+            clock: clock::system(),
+        })
+    }
+
+    /// Sign through `signer`, such as a [`RemoteSigner`], instead of the
+    /// in-memory keys. Verification keeps using the registered keys.
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = signer;
+        self
+    }
+
+    /// Expire rotated-out keys by `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Retrieve the current keypair for the given parachain id.
+    pub fn get(&self, para_id: u32) -> Option<Arc<ParachainKeypair>> {
+        // Entries are replaced whole, so a poisoned map is still consistent.
+        let keys = self.inner.read().unwrap_or_else(PoisonError::into_inner);
+        keys.get(&para_id).cloned()
+    }
+
+    /// Replace a parachain's keypair, generating one unless a hex secret is
+    /// given, and return the new public key as hex.
+    ///
+    /// Signatures made with the previous key keep verifying for
+    /// `parachains.rotation_grace_ms`, and stop immediately when that is 0.
+    pub fn rotate(&self, para_id: u32, secret_key: Option<&str>) -> Result<String, CryptoError> {
+        let pair = match secret_key {
+            Some(secret) => ParachainKeypair::new(
+                para_id,
+                signing_from_secret(secret)
+                    .map_err(|source| CryptoError::InvalidKey { para_id, source })?,
+            ),
+            None => ParachainKeypair::generate(para_id, &mut OsRng),
+        };
+        let public_key = pair.public_key_hex();
+        self.replace(pair)?;
+        Ok(public_key)
+    }
+
+    /// Build the registry from configuration, then replace verifying keys
+    /// with those published at `parachains.jwks_url`, if set.
+    pub async fn from_config_with_jwks(config: &ParachainConfig) -> Result<Self, CryptoError> {
+        Ok(Self::from_config(config)?.with_jwks(config).await)
+    }
+
+    /// Replace verifying keys with those published at `parachains.jwks_url`,
+    /// if set.
+    ///
+    /// When the key set cannot be fetched the current keys are kept. The set
+    /// is refreshed every `parachains.jwks_refresh_ms` in the background.
+    pub async fn with_jwks(self, config: &ParachainConfig) -> Self {
+        if let Some(source) = JwksSource::from_config(config) {
+            self.refresh_logged(&source).await;
+            if let Some(interval) = source.refresh_interval() {
+                let refreshing = self.clone();
+                tokio::spawn(async move {
+                    loop {
+                        tokio::time::sleep(interval).await;
+                        refreshing.refresh_logged(&source).await;
+                    }
+                });
+            }
+        }
+        self
+    }
+
+    /// Install every published key that names a registered parachain,
+    /// returning how many keys changed.
+    ///
+    /// Remote keys are verify-only. Keys that did not change are left alone,
+    /// so repeated refreshes do not restart the rotation grace window.
+    /// Parachains with a configured or rotated signing key keep it; keys
+    /// generated only because none was configured are replaced.
+    pub async fn refresh_from_jwks(&self, source: &JwksSource) -> Result<usize, JwksError> {
+        Ok(self.install_published(source.fetch().await?))
+    }
+
+    fn install_published(&self, published: Vec<(u32, VerifyingKey)>) -> usize {
+        let mut changed = 0;
+        for (para_id, verifying_key) in published {
+            let Some(current) = self.get(para_id) else {
+                tracing::debug!(
+                    target: "xcm_lite::crypto",
+                    para_id,
+                    "ignoring published key for unregistered parachain"
+                );
+                continue;
+            };
+            if *current.verifying_key() == verifying_key {
+                continue;
+            }
+            if !current.is_verify_only() && !current.generated {
+                tracing::warn!(
+                    target: "xcm_lite::crypto",
+                    para_id,
+                    "ignoring published key for a parachain with a local signing key"
+                );
+                continue;
+            }
+            if self
+                .replace(ParachainKeypair::verify_only(para_id, verifying_key))
+                .is_ok()
+            {
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    async fn refresh_logged(&self, source: &JwksSource) {
+        match self.refresh_from_jwks(source).await {
+            Ok(changed) => tracing::info!(
+                target: "xcm_lite::crypto",
+                url = %source.url(),
+                changed,
+                "refreshed verifying keys from jwks"
+            ),
+            Err(err) => tracing::warn!(
+                target: "xcm_lite::crypto",
+                url = %source.url(),
+                error = %err,
+                "failed to fetch jwks; keeping current keys"
+            ),
+        }
+    }
+
+    /// Swap in `pair`, retiring the previous key for the grace window.
+    fn replace(&self, pair: ParachainKeypair) -> Result<(), CryptoError> {
+        let para_id = pair.para_id;
+        let mut keys = self.inner.write().unwrap_or_else(PoisonError::into_inner);
+        let slot = keys
+            .get_mut(&para_id)
+            .ok_or(CryptoError::UnknownParachain { para_id })?;
+        let previous = std::mem::replace(slot, Arc::new(pair));
+        if !self.rotation_grace.is_zero() {
+            let now = self.clock.now();
+            let mut retired = self.retired.write().unwrap_or_else(PoisonError::into_inner);
+            let fallbacks = retired.entry(para_id).or_default();
+            fallbacks.retain(|key| key.expires_at > now);
+            fallbacks.push(RetiredKey {
+                verifying_key: *previous.verifying_key(),
+                expires_at: now + self.rotation_grace,
+            });
+        }
+        Ok(())
+    }
+
+    /// Verify a signature for a message emitted by a parachain.
+    pub fn verify_signature(
+        &self,
+        para_id: u32,
+        message: &[u8],
+        signature_bytes: &[u8],
+    ) -> Result<(), CryptoError> {
+        self.verify_with(para_id, message, signature_bytes, false)
+    }
+
+    /// Verify with ed25519ph over the SHA-512 digest of `message` when
+    /// `prehashed` is set, and plain ed25519 otherwise.
+    fn verify_with(
+        &self,
+        para_id: u32,
+        message: &[u8],
+        signature_bytes: &[u8],
+        prehashed: bool,
+    ) -> Result<(), CryptoError> {
+        let pair = self
+            .get(para_id)
+            .ok_or(CryptoError::UnknownParachain { para_id })?;
+
+        let signature = signature_from_bytes(signature_bytes)
+            .map_err(|err| CryptoError::InvalidSignature(err.to_string()))?;
+
+        let Err(err) = verify_key(pair.verifying_key(), message, &signature, prehashed) else {
+            return Ok(());
+        };
+        if self.verify_with_retired(para_id, message, &signature, prehashed) {
+            return Ok(());
+        }
+        Err(CryptoError::InvalidSignature(format!(
+            "signature verification failed: {err}"
+        )))
+    }
+
+    /// Whether any unexpired key rotated out of `para_id` accepts the
+    /// signature.
+    fn verify_with_retired(
+        &self,
+        para_id: u32,
+        message: &[u8],
+        signature: &Signature,
+        prehashed: bool,
+    ) -> bool {
+        let retired = self.retired.read().unwrap_or_else(PoisonError::into_inner);
+        let now = self.clock.now();
+        retired.get(&para_id).is_some_and(|keys| {
+            keys.iter().any(|key| {
+                key.expires_at > now
+                    && verify_key(&key.verifying_key, message, signature, prehashed).is_ok()
+            })
+        })
+    }
+
+    /// Verify an envelope signature over its canonical signing bytes.
+    ///
+    /// The envelope's declared `signature_scheme` must match the scheme of
+    /// the sender's registered key.
+    pub fn verify_envelope(
+        &self,
+        envelope: &MessageEnvelope,
+        signature_bytes: &[u8],
+    ) -> Result<(), CryptoError> {
+        let para_id = envelope.sender_para;
+        let pair = self
+            .get(para_id)
+            .ok_or(CryptoError::UnknownParachain { para_id })?;
+        if envelope.signature_scheme != pair.scheme() {
+            return Err(CryptoError::SchemeMismatch {
+                para_id,
+                declared: envelope.signature_scheme,
+                expected: pair.scheme(),
+            });
+        }
+
+        self.verify_with(
+            envelope.sender_para,
+            &envelope.signing_bytes(),
+            signature_bytes,
+            envelope.prehashed,
+        )
+    }
+
+    /// Verify a batch of envelopes against their decoded signatures,
+    /// returning one result per envelope in input order.
+    ///
+    /// Verification is CPU-bound, so the batch is split across up to
+    /// `parallelism` blocking tasks rather than run on the async workers.
+    /// A `parallelism` of 0 uses one task per available CPU.
+    pub async fn verify_envelopes(
+        &self,
+        batch: Arc<Vec<SignedEnvelope>>,
+        parallelism: usize,
+    ) -> Vec<Result<(), CryptoError>> {
+        let parallelism = match parallelism {
+            0 => std::thread::available_parallelism().map_or(1, |cpus| cpus.get()),
+            n => n,
+        };
+        let chunk = batch.len().div_ceil(parallelism).max(1);
+        let tasks: Vec<_> = (0..batch.len())
+            .step_by(chunk)
+            .map(|start| {
+                let end = (start + chunk).min(batch.len());
+                let (keys, batch) = (self.clone(), batch.clone());
+                let task = tokio::task::spawn_blocking(move || {
+                    batch[start..end]
+                        .iter()
+                        .map(|(envelope, signature)| keys.verify_envelope(envelope, signature))
+                        .collect::<Vec<_>>()
+                });
+                (task, end - start)
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(batch.len());
+        for (task, len) in tasks {
+            match task.await {
+                Ok(chunk_results) => results.extend(chunk_results),
+                Err(err) => results.extend((0..len).map(|_| {
+                    Err(CryptoError::InvalidSignature(format!(
+                        "verification task failed: {err}"
+                    )))
+                })),
+            }
+        }
+        results
+    }
+
+    /// Sign an envelope's canonical signing bytes with its sender's key,
+    /// using ed25519ph when the envelope is marked `prehashed`.
+    pub fn sign_envelope(&self, envelope: &MessageEnvelope) -> Result<Signature, CryptoError> {
+        let bytes = envelope.signing_bytes();
+        if envelope.prehashed {
+            self.signer.sign_prehashed(envelope.sender_para, &bytes)
+        } else {
+            self.signer.sign(envelope.sender_para, &bytes)
+        }
+    }
+
+    /// Sign a message for the parachain through the configured [`Signer`].
+    /// Intended for tests and the `dev-mode` benchmark.
+    pub fn sign_message(&self, para_id: u32, message: &[u8]) -> Result<Signature, CryptoError> {
+        self.signer.sign(para_id, message)
+    }
+
+    /// Sign a probe message with every key the [`Signer`] holds and verify
+    /// it against the registered key, returning how many were checked or
+    /// the first key that fails.
+    pub fn self_test(&self) -> Result<usize, CryptoError> {
+        let mut para_ids: Vec<u32> = self
+            .inner
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(_, pair)| self.signer.holds_key_for(pair))
+            .map(|(para_id, _)| *para_id)
+            .collect();
+        para_ids.sort_unstable();
+        for &para_id in &para_ids {
+            let probe = [SELF_TEST_PROBE, &para_id.to_le_bytes()].concat();
+            let signature = self.sign_message(para_id, &probe)?;
+            self.verify_signature(para_id, &probe, &signature.to_bytes())
+                .map_err(|err| CryptoError::SelfTest {
+                    para_id,
+                    reason: err.to_string(),
+                })?;
+        }
+        Ok(para_ids.len())
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Signing/verifying keypair for a parachain.
+///
+/// The verifying key is derived once at construction, so verification does
+/// not repeat the scalar multiplication on every call. Verify-only entries,
+/// configured from a public key, have no signing half.
+#[derive(Clone)]
+pub struct ParachainKeypair {
+    pub para_id: u32,
+    signing_key: Option<SigningKey>,
+    verifying_key: VerifyingKey,
+    /// Generated at startup because no key was configured, so a published
+    /// key may replace it.
+    generated: bool,
+}
+
+impl ParachainKeypair {
+    fn new(para_id: u32, signing_key: SigningKey) -> Self {
+        let verifying_key = signing_key.verifying_key();
+        Self {
+            para_id,
+            signing_key: Some(signing_key),
+            verifying_key,
+            generated: false,
+        }
+    }
+
+    fn verify_only(para_id: u32, verifying_key: VerifyingKey) -> Self {
+        Self {
+            para_id,
+            signing_key: None,
+            verifying_key,
+            generated: false,
+        }
+    }
+
+    fn from_config_entry(
+        para_id: u32,
+        entry: &ParachainKeyConfig,
+    ) -> Result<Self, KeypairBuildError> {
+        let sources = [
+            entry.secret_key.is_some(),
+            entry.seed_phrase.is_some(),
+            entry.public_key.is_some(),
+        ];
+        if sources.into_iter().filter(|set| *set).count() > 1 {
+            return Err(KeypairBuildError::ConflictingSources);
+        }
+        if let Some(public_key) = &entry.public_key {
+            return Ok(Self::verify_only(
+                para_id,
+                verifying_from_public(public_key)?,
+            ));
+        }
+
+        let signing_key = if let Some(secret) = &entry.secret_key {
+            signing_from_secret(secret)?
+        } else if let Some(seed) = &entry.seed_phrase {
+            signing_from_seed_phrase(seed)?
+        } else {
+            return Err(KeypairBuildError::MissingSource);
+        };
+
+        Ok(Self::new(para_id, signing_key))
+    }
+
+    fn generate<R>(para_id: u32, rng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut secret = [0u8; 32];
+        rng.fill_bytes(&mut secret);
+        Self::new(para_id, SigningKey::from_bytes(&secret))
+    }
+
+    /// Signature scheme of this keypair; only ed25519 keys are supported today.
+    pub fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Ed25519
+    }
+
+    pub fn verifying_key(&self) -> &VerifyingKey {
+        &self.verifying_key
+    }
+
+    /// Whether this entry can only verify signatures.
+    pub fn is_verify_only(&self) -> bool {
+        self.signing_key.is_none()
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.verifying_key().as_bytes())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum KeypairBuildError {
+    #[error("secret key, seed phrase or public key must be provided")]
+    MissingSource,
+    #[error(
+        "more than one of secret key, seed phrase and public key is provided; pick one source"
+    )]
+    ConflictingSources,
+    #[error("failed to parse public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("failed to parse secret key: {0}")]
+    InvalidSecretKey(String),
+    #[error("failed to derive key from seed phrase: {0}")]
+    SeedPhrase(String),
+    #[error("failed to construct signing key: {0}")]
+    Signature(String),
+}
+
+fn signing_from_secret(secret: &str) -> Result<SigningKey, KeypairBuildError> {
+    let decoded = decode_hex(secret).map_err(KeypairBuildError::InvalidSecretKey)?;
+    match decoded.len() {
+        32 => {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(&decoded);
+            Ok(SigningKey::from_bytes(&bytes))
+        }
+        64 => {
+            let mut bytes = [0u8; 64];
+            bytes.copy_from_slice(&decoded);
+            SigningKey::from_keypair_bytes(&bytes)
+                .map_err(|err| KeypairBuildError::Signature(err.to_string()))
+        }
+        other => Err(KeypairBuildError::InvalidSecretKey(format!(
+            "expected 32 or 64 bytes, got {other}"
+        ))),
+    }
+}
+
+/// Parse a hex ed25519 secret (32 bytes, or 64-byte keypair) into a
+/// signing key.
+pub fn signing_key_from_hex(secret: &str) -> Result<SigningKey, KeypairBuildError> {
+    signing_from_secret(secret)
+}
+
+fn verifying_from_public(public_key: &str) -> Result<VerifyingKey, KeypairBuildError> {
+    let decoded = decode_hex(public_key).map_err(KeypairBuildError::InvalidPublicKey)?;
+    let bytes: [u8; 32] = decoded.as_slice().try_into().map_err(|_| {
+        KeypairBuildError::InvalidPublicKey(format!("expected 32 bytes, got {}", decoded.len()))
+    })?;
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|err| KeypairBuildError::InvalidPublicKey(err.to_string()))
+}
+
+fn signing_from_seed_phrase(seed: &str) -> Result<SigningKey, KeypairBuildError> {
+    if seed.trim().is_empty() {
+        return Err(KeypairBuildError::SeedPhrase(
+            "seed phrase cannot be empty".into(),
+        ));
+    }
+    let digest = Sha512::digest(seed.as_bytes());
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&digest[..32]);
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+fn decode_hex(input: &str) -> Result<Vec<u8>, String> {
+    let normalized = input.trim().trim_start_matches("0x");
+    hex::decode(normalized).map_err(|err| err.to_string())
+}
+
+fn signature_from_bytes(bytes: &[u8]) -> Result<Signature, String> {
+    if bytes.len() != 64 {
+        return Err(format!(
+            "expected 64-byte signature, received {} bytes",
+            bytes.len()
+        ));
+    }
+    let arr: [u8; 64] = bytes
+        .try_into()
+        .map_err(|_| "invalid signature length".to_string())?;
+    Ok(Signature::from_bytes(&arr))
+}
+
+fn verify_key(
+    key: &VerifyingKey,
+    message: &[u8],
+    signature: &Signature,
+    prehashed: bool,
+) -> Result<(), ed25519_dalek::SignatureError> {
+    if prehashed {
+        key.verify_prehashed(Sha512::new().chain_update(message), None, signature)
+    } else {
+        key.verify(message, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::Signer as _;
+
+    use super::*;
+    use crate::domain::{Instruction, SigningEncoding, TransferReserveAsset, XcmVersion};
+
+    fn sample_config_with_secret(secret: &str) -> ParachainConfig {
+        ParachainConfig {
+            count: 1,
+            xcm_version: "V3".into(),
+            keys: vec![ParachainKeyConfig {
+                para_id: 1000,
+                seed_phrase: None,
+                secret_key: Some(secret.to_string()),
+                public_key: None,
+            }],
+            ..ParachainConfig::default()
+        }
+    }
+
+    #[test]
+    fn generates_keys_when_not_provided() {
+        let config = ParachainConfig {
+            count: 2,
+            xcm_version: "V3".into(),
+            keys: Vec::new(),
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn loads_secret_key_from_hex() {
+        let config = sample_config_with_secret(
+            "d1a8f40f4f54a97756f0a3cbb8113de2a8e2b3ef85da24e9f6d6c9cbe6a3b0ab",
+        );
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        let key = registry.get(1000).expect("key");
+        assert_eq!(key.para_id, 1000);
+    }
+
+    #[test]
+    fn derives_key_from_seed_phrase() {
+        let config = ParachainConfig {
+            count: 1,
+            xcm_version: "V3".into(),
+            keys: vec![ParachainKeyConfig {
+                para_id: 1000,
+                seed_phrase: Some("test seed phrase".into()),
+                secret_key: None,
+                public_key: None,
+            }],
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        assert!(registry.get(1000).is_some());
+    }
+
+    #[test]
+    fn lookups_share_the_stored_keypair() {
+        let registry = KeyRegistry::from_config(&ParachainConfig::default()).expect("registry");
+        let first = registry.get(1000).expect("key");
+        assert!(Arc::ptr_eq(&first, &registry.get(1000).expect("key")));
+
+        registry.rotate(1000, None).expect("rotate");
+        assert!(!Arc::ptr_eq(&first, &registry.get(1000).expect("key")));
+    }
+
+    #[test]
+    fn self_test_catches_mismatched_keypairs() {
+        let config = ParachainConfig {
+            count: 2,
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        assert_eq!(registry.self_test().expect("healthy keys"), 2);
+
+        let broken = ParachainKeypair {
+            para_id: 1001,
+            signing_key: Some(SigningKey::from_bytes(&[1u8; 32])),
+            verifying_key: SigningKey::from_bytes(&[2u8; 32]).verifying_key(),
+            generated: false,
+        };
+        registry.replace(broken).expect("replace");
+        assert!(matches!(
+            registry.self_test(),
+            Err(CryptoError::SelfTest { para_id: 1001, .. })
+        ));
+    }
+
+    #[test]
+    fn published_keys_do_not_replace_local_signing_keys() {
+        let remote = SigningKey::from_bytes(&[9u8; 32]).verifying_key();
+        let published = SigningKey::from_bytes(&[7u8; 32]).verifying_key();
+        let config = ParachainConfig {
+            keys: vec![
+                ParachainKeyConfig {
+                    para_id: 1000,
+                    seed_phrase: Some("local seed".into()),
+                    secret_key: None,
+                    public_key: None,
+                },
+                ParachainKeyConfig {
+                    para_id: 1001,
+                    seed_phrase: None,
+                    secret_key: None,
+                    public_key: Some(hex::encode(remote.as_bytes())),
+                },
+            ],
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        let local = *registry.get(1000).expect("key").verifying_key();
+
+        let changed = registry.install_published(vec![(1000, published), (1001, published)]);
+        assert_eq!(changed, 1);
+        let kept = registry.get(1000).expect("key");
+        assert!(!kept.is_verify_only());
+        assert_eq!(*kept.verifying_key(), local);
+        assert_eq!(*registry.get(1001).expect("key").verifying_key(), published);
+    }
+
+    #[test]
+    fn public_key_entries_verify_but_cannot_sign() {
+        let signer = SigningKey::from_bytes(&[9u8; 32]);
+        let config = ParachainConfig {
+            count: 1,
+            keys: vec![ParachainKeyConfig {
+                para_id: 1000,
+                seed_phrase: None,
+                secret_key: None,
+                public_key: Some(hex::encode(signer.verifying_key().as_bytes())),
+            }],
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        assert!(registry.get(1000).expect("key").is_verify_only());
+
+        let message = b"hello world";
+        let signature = signer.sign(message).to_bytes();
+        assert!(registry.verify_signature(1000, message, &signature).is_ok());
+        assert!(matches!(
+            registry.sign_message(1000, message),
+            Err(CryptoError::VerifyOnly { para_id: 1000 })
+        ));
+    }
+
+    #[test]
+    fn sign_and_verify_roundtrip() {
+        let config = ParachainConfig {
+            count: 1,
+            xcm_version: "V3".into(),
+            keys: Vec::new(),
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        let message = b"hello world";
+        let signature = registry.sign_message(1000, message).expect("signature");
+        let signature_bytes = signature.to_bytes();
+
+        assert!(registry
+            .verify_signature(1000, message, &signature_bytes)
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_signature_reused_for_different_sender() {
+        let shared_seed = |para_id| ParachainKeyConfig {
+            para_id,
+            seed_phrase: Some("shared seed".into()),
+            secret_key: None,
+            public_key: None,
+        };
+        let config = ParachainConfig {
+            count: 2,
+            xcm_version: "V3".into(),
+            keys: vec![shared_seed(1000), shared_seed(1001)],
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+
+        let envelope = MessageEnvelope {
+            message_id: None,
+            sender_para: 1000,
+            dest_para: 2000,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount: 10,
+                beneficiary: "acct-123".into(),
+            })],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        };
+        let signature = registry.sign_envelope(&envelope).expect("signature");
+        assert!(registry
+            .verify_envelope(&envelope, &signature.to_bytes())
+            .is_ok());
+
+        let mut spoofed = envelope.clone();
+        spoofed.sender_para = 1001;
+        assert!(registry
+            .verify_envelope(&spoofed, &signature.to_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn prehashed_signatures_roundtrip() {
+        let config = ParachainConfig {
+            count: 2,
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        let mut envelope = MessageEnvelope {
+            message_id: None,
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount: 10,
+                beneficiary: "acct-123".into(),
+            })],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: true,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        };
+        let signature = registry.sign_envelope(&envelope).expect("signature");
+        assert!(registry
+            .verify_envelope(&envelope, &signature.to_bytes())
+            .is_ok());
+
+        envelope.prehashed = false;
+        assert!(registry
+            .verify_envelope(&envelope, &signature.to_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_message_id_swapped_after_signing() {
+        let config = ParachainConfig {
+            count: 2,
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        let envelope = MessageEnvelope {
+            message_id: Some("msg-1".into()),
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount: 10,
+                beneficiary: "acct-123".into(),
+            })],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        };
+        let signature = registry.sign_envelope(&envelope).expect("signature");
+
+        let mut swapped = envelope.clone();
+        swapped.message_id = Some("msg-2".into());
+        assert!(registry
+            .verify_envelope(&swapped, &signature.to_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_mismatched_signature_scheme() {
+        let config = ParachainConfig {
+            count: 2,
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        let mut envelope = MessageEnvelope {
+            message_id: None,
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount: 10,
+                beneficiary: "acct-123".into(),
+            })],
+            signature_scheme: SignatureScheme::Sr25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        };
+        let signature = registry.sign_envelope(&envelope).expect("signature");
+
+        let err = registry
+            .verify_envelope(&envelope, &signature.to_bytes())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CryptoError::SchemeMismatch {
+                declared: SignatureScheme::Sr25519,
+                expected: SignatureScheme::Ed25519,
+                ..
+            }
+        ));
+
+        envelope.signature_scheme = SignatureScheme::Ed25519;
+        assert!(registry
+            .verify_envelope(&envelope, &signature.to_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn rotation_invalidates_old_signatures() {
+        let config = ParachainConfig {
+            count: 1,
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        let before = registry.get(1000).expect("key").public_key_hex();
+        let old_signature = registry.sign_message(1000, b"payload").expect("signature");
+
+        let after = registry.rotate(1000, None).expect("rotate");
+        assert_ne!(before, after);
+        assert!(registry
+            .verify_signature(1000, b"payload", &old_signature.to_bytes())
+            .is_err());
+
+        let new_signature = registry.sign_message(1000, b"payload").expect("signature");
+        assert!(registry
+            .verify_signature(1000, b"payload", &new_signature.to_bytes())
+            .is_ok());
+        assert!(matches!(
+            registry.rotate(4000, None),
+            Err(CryptoError::UnknownParachain { para_id: 4000 })
+        ));
+    }
+
+    #[test]
+    fn rotated_out_keys_verify_during_the_grace_window() {
+        let config = ParachainConfig {
+            count: 1,
+            rotation_grace_ms: 60_000,
+            ..ParachainConfig::default()
+        };
+        let registry = KeyRegistry::from_config(&config).expect("registry");
+        let old_signature = registry.sign_message(1000, b"payload").expect("signature");
+
+        registry.rotate(1000, None).expect("rotate");
+        let new_signature = registry.sign_message(1000, b"payload").expect("signature");
+        for signature in [&old_signature, &new_signature] {
+            assert!(registry
+                .verify_signature(1000, b"payload", &signature.to_bytes())
+                .is_ok());
+        }
+
+        // A second rotation keeps both earlier keys until they expire.
+        registry.rotate(1000, None).expect("rotate");
+        assert!(registry
+            .verify_signature(1000, b"payload", &old_signature.to_bytes())
+            .is_ok());
+
+        let expired = KeyRegistry {
+            rotation_grace: Duration::from_millis(1),
+            ..KeyRegistry::from_config(&config).expect("registry")
+        };
+        let old_signature = expired.sign_message(1000, b"payload").expect("signature");
+        expired.rotate(1000, None).expect("rotate");
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(expired
+            .verify_signature(1000, b"payload", &old_signature.to_bytes())
+            .is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::HashMap,
+    sync::{mpsc as std_mpsc, Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+use ed25519_dalek::{Signature, Signer as _, SigningKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use tokio::sync::mpsc;
+
+use super::{signature_from_bytes, CryptoError, ParachainKeypair};
+use crate::config::ParachainConfig;
+
+/// Per-request timeout for remote signing calls.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Produces signatures on behalf of parachains, so signing can live
+/// outside the process while verification stays local.
+pub trait Signer: Send + Sync {
+    fn sign(&self, para_id: u32, message: &[u8]) -> Result<Signature, CryptoError>;
+
+    /// Sign `message` with ed25519ph, which signs its SHA-512 digest.
+    fn sign_prehashed(&self, para_id: u32, message: &[u8]) -> Result<Signature, CryptoError>;
+
+    /// Whether this signer holds the signing half of `pair`, so
+    /// [`KeyRegistry::self_test`](super::KeyRegistry::self_test) probes it.
+    fn holds_key_for(&self, pair: &ParachainKeypair) -> bool;
+}
+
+/// Signs with the keypairs held in memory by a [`KeyRegistry`](super::KeyRegistry).
+pub struct KeypairSigner {
+    keys: Arc<RwLock<HashMap<u32, Arc<ParachainKeypair>>>>,
+}
+
+impl KeypairSigner {
+    pub(super) fn new(keys: Arc<RwLock<HashMap<u32, Arc<ParachainKeypair>>>>) -> Self {
+        Self { keys }
+    }
+
+    fn signing_key(&self, para_id: u32) -> Result<SigningKey, CryptoError> {
+        // Entries are replaced whole, so a poisoned map is still consistent.
+        let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+        let pair = keys
+            .get(&para_id)
+            .ok_or(CryptoError::UnknownParachain { para_id })?;
+        pair.signing_key
+            .clone()
+            .ok_or(CryptoError::VerifyOnly { para_id })
+    }
+}
+
+impl Signer for KeypairSigner {
+    fn sign(&self, para_id: u32, message: &[u8]) -> Result<Signature, CryptoError> {
+        Ok(self.signing_key(para_id)?.sign(message))
+    }
+
+    fn sign_prehashed(&self, para_id: u32, message: &[u8]) -> Result<Signature, CryptoError> {
+        self.signing_key(para_id)?
+            .sign_prehashed(Sha512::new().chain_update(message), None)
+            .map_err(|err| CryptoError::InvalidSignature(err.to_string()))
+    }
+
+    fn holds_key_for(&self, pair: &ParachainKeypair) -> bool {
+        !pair.is_verify_only()
+    }
+}
+
+/// Asks an HTTP signing service, such as an HSM or KMS front end, for
+/// each signature.
+///
+/// Posts `{ "paraId": .., "message": "<hex>" }` to the configured URL and
+/// expects `{ "signature": "<hex>" }` back; ed25519ph requests also carry
+/// `"prehashed": true`. The service holds the keys registered without a
+/// signing half. Requests share one client on a worker thread, and each
+/// call blocks its caller until the service answers, so async callers
+/// should sign from `spawn_blocking`.
+pub struct RemoteSigner {
+    requests: mpsc::UnboundedSender<PendingSignature>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignRequest {
+    para_id: u32,
+    message: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    prehashed: bool,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+/// A request queued for the worker and where to send its answer.
+struct PendingSignature {
+    request: SignRequest,
+    reply: std_mpsc::Sender<Result<Signature, CryptoError>>,
+}
+
+impl RemoteSigner {
+    /// Start the worker that sends every request to `url`.
+    pub fn new(url: impl Into<String>) -> Result<Self, CryptoError> {
+        let failed = |err: &dyn std::fmt::Display| CryptoError::RemoteSigner(err.to_string());
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|err| failed(&err))?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| failed(&err))?;
+        let url: Arc<str> = url.into().into();
+        let (requests, mut pending) = mpsc::unbounded_channel::<PendingSignature>();
+        std::thread::Builder::new()
+            .name("remote-signer".into())
+            .spawn(move || {
+                // Runs until the signer, and with it the sending half, is dropped.
+                runtime.block_on(async move {
+                    while let Some(PendingSignature { request, reply }) = pending.recv().await {
+                        let (client, url) = (client.clone(), url.clone());
+                        tokio::spawn(async move {
+                            // The caller only stops waiting if it is gone.
+                            let _ = reply.send(Self::request(&client, &url, &request).await);
+                        });
+                    }
+                });
+            })
+            .map_err(|err| failed(&err))?;
+        Ok(Self { requests })
+    }
+
+    /// Build a signer when `parachains.remote_signer_url` is configured.
+    pub fn from_config(config: &ParachainConfig) -> Result<Option<Self>, CryptoError> {
+        config.remote_signer_url.as_ref().map(Self::new).transpose()
+    }
+
+    fn call(
+        &self,
+        para_id: u32,
+        message: &[u8],
+        prehashed: bool,
+    ) -> Result<Signature, CryptoError> {
+        let stopped = || CryptoError::RemoteSigner("signing worker stopped".into());
+        let (reply, answer) = std_mpsc::channel();
+        let request = SignRequest {
+            para_id,
+            message: hex::encode(message),
+            prehashed,
+        };
+        self.requests
+            .send(PendingSignature { request, reply })
+            .map_err(|_| stopped())?;
+        answer.recv().map_err(|_| stopped())?
+    }
+
+    async fn request(
+        client: &reqwest::Client,
+        url: &str,
+        request: &SignRequest,
+    ) -> Result<Signature, CryptoError> {
+        let failed = |err: reqwest::Error| CryptoError::RemoteSigner(err.to_string());
+        let response: SignResponse = client
+            .post(url)
+            .json(request)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(failed)?
+            .json()
+            .await
+            .map_err(failed)?;
+        let bytes = hex::decode(response.signature.trim_start_matches("0x"))
+            .map_err(|err| CryptoError::RemoteSigner(format!("signature is not hex: {err}")))?;
+        signature_from_bytes(&bytes).map_err(|err| CryptoError::RemoteSigner(err.to_string()))
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn sign(&self, para_id: u32, message: &[u8]) -> Result<Signature, CryptoError> {
+        self.call(para_id, message, false)
+    }
+
+    fn sign_prehashed(&self, para_id: u32, message: &[u8]) -> Result<Signature, CryptoError> {
+        self.call(para_id, message, true)
+    }
+
+    fn holds_key_for(&self, pair: &ParachainKeypair) -> bool {
+        pair.is_verify_only()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Json, Router};
+    use ed25519_dalek::Verifier;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::{
+        config::ParachainKeyConfig,
+        crypto::KeyRegistry,
+        domain::{MessageEnvelope, SignatureScheme, SigningEncoding, XcmVersion},
+    };
+
+    const REMOTE_SEED: [u8; 32] = [7u8; 32];
+
+    async fn sign_remotely(Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+        let message = hex::decode(request["message"].as_str().expect("message")).expect("hex");
+        let key = SigningKey::from_bytes(&REMOTE_SEED);
+        let signature = if request["prehashed"] == true {
+            key.sign_prehashed(Sha512::new().chain_update(&message), None)
+                .expect("ed25519ph")
+        } else {
+            key.sign(&message)
+        };
+        Json(serde_json::json!({ "signature": hex::encode(signature.to_bytes()) }))
+    }
+
+    async fn signing_service() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let app = Router::new().route("/sign", post(sign_remotely));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn remote_signer_returns_the_service_signature() {
+        let service = signing_service().await;
+        let signer = RemoteSigner::new(format!("{service}/sign")).expect("signer");
+        let signature = signer.sign(1000, b"payload").expect("signature");
+        let key = SigningKey::from_bytes(&REMOTE_SEED).verifying_key();
+        assert!(key.verify(b"payload", &signature).is_ok());
+
+        let unreachable = RemoteSigner::new(format!("{service}/missing")).expect("signer");
+        assert!(matches!(
+            unreachable.sign(1000, b"payload"),
+            Err(CryptoError::RemoteSigner(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn registry_signs_and_self_tests_through_the_remote_signer() {
+        let service = signing_service().await;
+        let remote_key = SigningKey::from_bytes(&REMOTE_SEED).verifying_key();
+        let config = ParachainConfig {
+            count: 2,
+            keys: vec![ParachainKeyConfig {
+                para_id: 1000,
+                seed_phrase: None,
+                secret_key: None,
+                public_key: Some(hex::encode(remote_key.as_bytes())),
+            }],
+            ..ParachainConfig::default()
+        };
+        let signer = RemoteSigner::new(format!("{service}/sign")).expect("signer");
+        let registry = KeyRegistry::from_config(&config)
+            .expect("registry")
+            .with_signer(Arc::new(signer));
+
+        // Only the service's key is probed, not the generated one for 1001.
+        assert_eq!(registry.self_test().expect("remote keys match"), 1);
+
+        let envelope = MessageEnvelope {
+            message_id: None,
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: Vec::new(),
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: true,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        };
+        let signature = registry.sign_envelope(&envelope).expect("signature");
+        assert!(registry
+            .verify_envelope(&envelope, &signature.to_bytes())
+            .is_ok());
+    }
+}
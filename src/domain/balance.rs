@@ -0,0 +1,92 @@
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// Amount of a single asset held by an account.
+///
+/// All balance arithmetic goes through the checked helpers so overflow and
+/// underflow surface as errors instead of wrapping or saturating.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(transparent)]
+pub struct Balance(pub u128);
+
+impl Balance {
+    pub const ZERO: Balance = Balance(0);
+
+    pub fn get(self) -> u128 {
+        self.0
+    }
+
+    pub fn checked_add(self, amount: u128) -> Result<Self, BalanceError> {
+        self.0
+            .checked_add(amount)
+            .map(Balance)
+            .ok_or(BalanceError::Overflow {
+                balance: self.0,
+                amount,
+            })
+    }
+
+    pub fn checked_sub(self, amount: u128) -> Result<Self, BalanceError> {
+        self.0
+            .checked_sub(amount)
+            .map(Balance)
+            .ok_or(BalanceError::Underflow {
+                balance: self.0,
+                amount,
+            })
+    }
+}
+
+impl Display for Balance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Arithmetic failures on a [`Balance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum BalanceError {
+    #[error("adding {amount} to {balance} overflows")]
+    Overflow { balance: u128, amount: u128 },
+    #[error("cannot take {amount} from {balance}")]
+    Underflow { balance: u128, amount: u128 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_succeeds_up_to_the_maximum() {
+        let balance = Balance(u128::MAX - 1);
+        assert_eq!(balance.checked_add(1), Ok(Balance(u128::MAX)));
+        assert_eq!(
+            balance.checked_add(2),
+            Err(BalanceError::Overflow {
+                balance: u128::MAX - 1,
+                amount: 2
+            })
+        );
+    }
+
+    #[test]
+    fn sub_succeeds_down_to_zero() {
+        let balance = Balance(5);
+        assert_eq!(balance.checked_sub(5), Ok(Balance::ZERO));
+        assert_eq!(
+            balance.checked_sub(6),
+            Err(BalanceError::Underflow {
+                balance: 5,
+                amount: 6
+            })
+        );
+    }
+
+    #[test]
+    fn serializes_as_a_bare_number() {
+        let json = serde_json::to_string(&Balance(42)).expect("serialize");
+        assert_eq!(json, "42");
+    }
+}
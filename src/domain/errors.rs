@@ -11,6 +11,7 @@ pub enum XcmErrorCode {
     InvalidSignature,
     VersionMismatch,
     UnsupportedInstruction,
+    RouteNotAllowed,
 }
 
 impl Display for XcmErrorCode {
@@ -20,6 +21,7 @@ impl Display for XcmErrorCode {
             XcmErrorCode::InvalidSignature => "InvalidSignature",
             XcmErrorCode::VersionMismatch => "VersionMismatch",
             XcmErrorCode::UnsupportedInstruction => "UnsupportedInstruction",
+            XcmErrorCode::RouteNotAllowed => "RouteNotAllowed",
         })
     }
 }
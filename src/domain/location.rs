@@ -0,0 +1,163 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Simplified XCM MultiLocation identifying an asset relative to the
+/// chain that holds it.
+///
+/// Accepted either as JSON, `{"parents":1,"interior":[{"parachain":1000}]}`,
+/// or in the canonical string form produced by `Display`, which is the
+/// parent count followed by each junction: `1/Parachain(1000)`. A bare
+/// parent count has an empty interior, so the relay chain's native token
+/// seen from a parachain is `1`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MultiLocation {
+    pub parents: u8,
+    #[serde(default)]
+    pub interior: Vec<Junction>,
+}
+
+/// One step down from a location.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Junction {
+    Parachain(u32),
+    PalletInstance(u8),
+    GeneralIndex(u128),
+    GeneralKey(String),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid multilocation `{input}`: {reason}")]
+pub struct MultiLocationError {
+    pub input: String,
+    pub reason: String,
+}
+
+impl MultiLocationError {
+    fn new(input: &str, reason: impl Into<String>) -> Self {
+        Self {
+            input: input.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl FromStr for MultiLocation {
+    type Err = MultiLocationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let trimmed = input.trim();
+        if trimmed.starts_with('{') {
+            return serde_json::from_str(trimmed)
+                .map_err(|err| MultiLocationError::new(input, err.to_string()));
+        }
+
+        let mut parts = trimmed.split('/');
+        let parents = parts
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .map_err(|_| MultiLocationError::new(input, "expected a parent count"))?;
+        let interior = parts
+            .map(|part| {
+                Junction::from_str(part).map_err(|reason| MultiLocationError::new(input, reason))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { parents, interior })
+    }
+}
+
+impl Display for MultiLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.parents)?;
+        for junction in &self.interior {
+            write!(f, "/{junction}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Junction {
+    type Err = String;
+
+    fn from_str(part: &str) -> Result<Self, Self::Err> {
+        let (name, value) = part
+            .strip_suffix(')')
+            .and_then(|rest| rest.split_once('('))
+            .ok_or_else(|| format!("junction `{part}` is not of the form Name(value)"))?;
+        let invalid = |_| format!("junction `{part}` has an invalid value");
+        match name {
+            "Parachain" => value.parse().map(Junction::Parachain).map_err(invalid),
+            "PalletInstance" => value.parse().map(Junction::PalletInstance).map_err(invalid),
+            "GeneralIndex" => value.parse().map(Junction::GeneralIndex).map_err(invalid),
+            "GeneralKey" if !value.is_empty() && !value.contains(['/', '(', ')']) => {
+                Ok(Junction::GeneralKey(value.to_string()))
+            }
+            "GeneralKey" => Err(format!("junction `{part}` has an invalid value")),
+            _ => Err(format!("unknown junction `{name}`")),
+        }
+    }
+}
+
+impl Display for Junction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Junction::Parachain(id) => write!(f, "Parachain({id})"),
+            Junction::PalletInstance(index) => write!(f, "PalletInstance({index})"),
+            Junction::GeneralIndex(index) => write!(f, "GeneralIndex({index})"),
+            Junction::GeneralKey(key) => write!(f, "GeneralKey({key})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_and_canonical_forms_normalize_alike() {
+        let from_json: MultiLocation = r#"{"parents":1,"interior":[{"parachain":1000},{"palletInstance":50},{"generalIndex":1984}]}"#
+            .parse()
+            .expect("json form");
+        let from_string: MultiLocation = "1/Parachain(1000)/PalletInstance(50)/GeneralIndex(1984)"
+            .parse()
+            .expect("canonical form");
+
+        assert_eq!(from_json, from_string);
+        assert_eq!(
+            from_json.to_string(),
+            "1/Parachain(1000)/PalletInstance(50)/GeneralIndex(1984)"
+        );
+        assert_eq!(
+            "1".parse::<MultiLocation>().expect("relay token").interior,
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_locations() {
+        for input in [
+            "",
+            "DOT",
+            "-1/Parachain(1000)",
+            "1/Parachain(abc)",
+            "1/Parachain 1000",
+            "1/Account(alice)",
+            "1//Parachain(1000)",
+            "1/GeneralKey()",
+            r#"{"parents":1,"interior":[{"account":"alice"}]}"#,
+            r#"{"parents":1,"extra":true}"#,
+        ] {
+            assert!(
+                input.parse::<MultiLocation>().is_err(),
+                "`{input}` should not parse"
+            );
+        }
+    }
+}
@@ -1,18 +1,69 @@
-use std::{fmt::Display, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+};
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use super::errors::{MessageValidationError, XcmErrorCode};
+use super::{
+    errors::{MessageValidationError, XcmErrorCode},
+    location::MultiLocation,
+};
+use crate::config::{DestInViaPolicy, ValidationConfig};
 
-/// Supported XCM versions for the simulation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Supported XCM versions for the simulation, oldest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
 #[serde(rename_all = "UPPERCASE")]
 pub enum XcmVersion {
     V3,
     V4,
 }
 
+/// Instruction kinds accepted under XCM V3.
+const V3_INSTRUCTIONS: &[&str] = &[
+    "transferReserveAsset",
+    "withdrawAsset",
+    "depositAsset",
+    "transact",
+    "setBalance",
+    "conditionalTransfer",
+];
+
+/// Instruction kinds accepted under XCM V4, which adds `queryResponse`.
+const V4_INSTRUCTIONS: &[&str] = &[
+    "transferReserveAsset",
+    "withdrawAsset",
+    "depositAsset",
+    "transact",
+    "queryResponse",
+    "setBalance",
+    "conditionalTransfer",
+];
+
 impl XcmVersion {
+    /// Every version, oldest first.
+    const ALL: [XcmVersion; 2] = [XcmVersion::V3, XcmVersion::V4];
+
+    /// Instruction kinds (as named on the wire) valid for this version.
+    /// `validation.instruction_versions` can move a kind to another version.
+    pub fn supported_instructions(&self) -> &'static [&'static str] {
+        match self {
+            XcmVersion::V3 => V3_INSTRUCTIONS,
+            XcmVersion::V4 => V4_INSTRUCTIONS,
+        }
+    }
+
+    /// Earliest version `kind` is available in by default, or `None` for
+    /// an unknown instruction kind.
+    pub fn introducing(kind: &str) -> Option<XcmVersion> {
+        Self::ALL
+            .into_iter()
+            .find(|version| version.supported_instructions().contains(&kind))
+    }
+
     pub fn is_supported(self, configured: &str) -> bool {
         let normalized = configured.trim().to_uppercase();
         match self {
@@ -45,42 +96,273 @@ impl Display for XcmVersion {
     }
 }
 
+/// Signature algorithm an envelope declares it was signed with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureScheme {
+    #[default]
+    Ed25519,
+    Sr25519,
+}
+
+impl Display for SignatureScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureScheme::Ed25519 => write!(f, "ed25519"),
+            SignatureScheme::Sr25519 => write!(f, "sr25519"),
+        }
+    }
+}
+
+/// Encoding of the envelope body within its signing bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningEncoding {
+    #[default]
+    Json,
+    /// SCALE, as produced by substrate tooling. Only available in builds
+    /// with the `scale` feature.
+    #[cfg(feature = "scale")]
+    Scale,
+}
+
+impl SigningEncoding {
+    fn is_json(&self) -> bool {
+        *self == SigningEncoding::Json
+    }
+}
+
+/// Domain-separation tag prepended to every signed envelope.
+const SIGNING_DOMAIN: &[u8] = b"xcm-lite/envelope/v1";
+
 /// Envelope representing an incoming message submission.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
 #[serde(rename_all = "camelCase")]
 pub struct MessageEnvelope {
     pub message_id: Option<String>,
     pub sender_para: u32,
     pub dest_para: u32,
+    /// Intermediate parachains the message is relayed through, in order.
+    /// Omitted from JSON signing bytes when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub via: Vec<u32>,
     pub xcm_version: XcmVersion,
     pub instructions: Vec<Instruction>,
+    /// Scheme used to verify `signature`; defaults to `ed25519` when omitted.
     #[serde(default)]
+    pub signature_scheme: SignatureScheme,
+    /// Whether `signature` is an ed25519ph signature over the SHA-512
+    /// digest of the signing bytes. Omitted from JSON signing bytes when
+    /// false, so existing signatures stay valid.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub prehashed: bool,
+    /// Stop at `relayed` until `POST /status/:id/execute`, as if
+    /// `relay.defer_execution` were set for this message alone.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub defer_execution: bool,
+    /// Per-sender sequence number, required when `processor.enforce_nonces`
+    /// is set. Omitted from JSON signing bytes when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// How the body of the signing bytes is encoded. Omitted from JSON
+    /// signing bytes and never part of SCALE ones.
+    #[serde(default, skip_serializing_if = "SigningEncoding::is_json")]
+    #[cfg_attr(feature = "scale", codec(skip))]
+    pub signing_encoding: SigningEncoding,
+    #[serde(default)]
+    #[cfg_attr(feature = "scale", codec(skip))]
     pub signature: Option<String>,
 }
 
 impl MessageEnvelope {
-    /// Validate structural correctness and supported features.
-    pub fn validate(&self, configured_version: &str) -> Result<(), MessageValidationError> {
+    /// Parse an envelope from JSON.
+    ///
+    /// In strict mode any field the schema does not recognise is rejected
+    /// with `InvalidPayload` naming the offending path, so client typos are
+    /// not silently dropped.
+    pub fn from_json(bytes: &[u8], strict: bool) -> Result<Self, MessageValidationError> {
+        let raw: serde_json::Value = serde_json::from_slice(bytes).map_err(|err| {
+            MessageValidationError::invalid_payload(format!("malformed message body: {err}"))
+        })?;
+        Self::from_value(raw, strict)
+    }
+
+    /// Parse an envelope from MessagePack, with the same strict-mode rules
+    /// as [`MessageEnvelope::from_json`].
+    pub fn from_msgpack(bytes: &[u8], strict: bool) -> Result<Self, MessageValidationError> {
+        let raw: serde_json::Value = rmp_serde::from_slice(bytes).map_err(|err| {
+            MessageValidationError::invalid_payload(format!("malformed message body: {err}"))
+        })?;
+        Self::from_value(raw, strict)
+    }
+
+    /// Parse an envelope from an already decoded JSON value, with the same
+    /// strict-mode rules as [`MessageEnvelope::from_json`].
+    pub fn from_value(
+        raw: serde_json::Value,
+        strict: bool,
+    ) -> Result<Self, MessageValidationError> {
+        let envelope: MessageEnvelope = serde_json::from_value(raw.clone()).map_err(|err| {
+            MessageValidationError::invalid_payload(format!("malformed message body: {err}"))
+        })?;
+
+        if strict {
+            let mut known =
+                serde_json::to_value(&envelope).expect("envelope serialization is infallible");
+            // Explicit `false` flags are skipped on the way out but are
+            // still known fields.
+            known["prehashed"] = envelope.prehashed.into();
+            known["deferExecution"] = envelope.defer_execution.into();
+            known["signingEncoding"] = serde_json::to_value(envelope.signing_encoding)
+                .expect("encoding serialization is infallible");
+            if envelope.via.is_empty() {
+                known["via"] = serde_json::json!([]);
+            }
+            // So are unset optional fields, which an explicit `null` also
+            // leaves unset.
+            if let Some(fields) = known.as_object_mut() {
+                fields.entry("nonce").or_insert(serde_json::Value::Null);
+            }
+            let known_instructions = known["instructions"].as_array_mut();
+            for (instruction, known) in envelope
+                .instructions
+                .iter()
+                .zip(known_instructions.into_iter().flatten())
+            {
+                if let Some(fields) = known.as_object_mut() {
+                    for field in instruction.optional_fields() {
+                        fields.entry(*field).or_insert(serde_json::Value::Null);
+                    }
+                }
+            }
+            if let Some(path) = first_unknown_field(&raw, &known, "") {
+                return Err(MessageValidationError::invalid_payload(format!(
+                    "unknown field `{path}`"
+                )));
+            }
+        }
+
+        Ok(envelope)
+    }
+
+    /// Canonical bytes signed by the sender parachain.
+    ///
+    /// The domain tag and little-endian `sender_para` come first so a
+    /// signature is only ever valid for the sender it was produced for,
+    /// followed by the envelope without its signature, encoded as chosen by
+    /// `signingEncoding`. The body includes `messageId`, so a client-supplied
+    /// id cannot be swapped without invalidating the signature.
+    ///
+    /// The SCALE body encodes the fields in declaration order: `messageId`
+    /// (`Option<String>`), `senderPara` and `destPara` (`u32`), `via`
+    /// (`Vec<u32>`), `xcmVersion` (variant index, V3 = 0), `instructions`
+    /// (`Vec` of variant index then fields in declaration order, amounts as
+    /// `u128`), `signatureScheme` (variant index), `prehashed` and
+    /// `deferExecution` (`bool`), and `nonce` (`Option<u64>`).
+    /// `signingEncoding` and `signature` are left out. Unlike the JSON body,
+    /// which drops empty `via`, false flags and unset optional fields, the
+    /// SCALE body always encodes them, unset options as a `0x00` byte.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let body = match self.signing_encoding {
+            SigningEncoding::Json => {
+                let unsigned = MessageEnvelope {
+                    signature: None,
+                    ..self.clone()
+                };
+                serde_json::to_vec(&unsigned).expect("envelope serialization is infallible")
+            }
+            #[cfg(feature = "scale")]
+            SigningEncoding::Scale => parity_scale_codec::Encode::encode(self),
+        };
+
+        let mut bytes = Vec::with_capacity(SIGNING_DOMAIN.len() + 4 + body.len());
+        bytes.extend_from_slice(SIGNING_DOMAIN);
+        bytes.extend_from_slice(&self.sender_para.to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    /// Rewrite every asset identifier that parses as a MultiLocation into
+    /// its canonical string form, so equivalent spellings share a balance
+    /// key. Identifiers that do not parse are left alone.
+    pub fn normalize_assets(&mut self) {
+        for instruction in &mut self.instructions {
+            for asset in instruction.assets_mut() {
+                if let Ok(location) = MultiLocation::from_str(asset) {
+                    *asset = location.to_string();
+                }
+            }
+        }
+    }
+
+    /// Parachains the message travels through, from sender to destination.
+    ///
+    /// When the destination also appears in `via`, the message is
+    /// delivered at its first occurrence and later hops are never taken.
+    pub fn hop_path(&self) -> Vec<u32> {
+        let intermediate = self
+            .via
+            .iter()
+            .position(|para_id| *para_id == self.dest_para)
+            .map_or(&self.via[..], |first| &self.via[..first]);
+        let mut path = Vec::with_capacity(intermediate.len() + 2);
+        path.push(self.sender_para);
+        path.extend(intermediate);
+        path.push(self.dest_para);
+        path
+    }
+
+    /// Validate structural correctness and supported features, stopping at
+    /// the first problem.
+    ///
+    /// Transfers above `rules.max_transfer_amount` are rejected regardless of
+    /// the balances available to cover them.
+    pub fn validate(
+        &self,
+        configured_version: &str,
+        rules: &ValidationConfig,
+    ) -> Result<(), MessageValidationError> {
+        self.validate_all(configured_version, rules)
+            .map_err(|mut errors| errors.swap_remove(0))
+    }
+
+    /// Run every validation check and return all failures, in the order
+    /// [`MessageEnvelope::validate`] would report them.
+    ///
+    /// Each instruction contributes at most one error.
+    pub fn validate_all(
+        &self,
+        configured_version: &str,
+        rules: &ValidationConfig,
+    ) -> Result<(), Vec<MessageValidationError>> {
+        let mut errors = Vec::new();
+
         if self.sender_para == 0 || self.dest_para == 0 {
-            return Err(MessageValidationError::invalid_payload(
+            errors.push(MessageValidationError::invalid_payload(
                 "sender and destination parachain IDs must be non-zero",
             ));
         }
 
-        if self.sender_para == self.dest_para {
-            return Err(MessageValidationError::invalid_payload(
+        if self.sender_para == self.dest_para && !rules.allow_loopback {
+            errors.push(MessageValidationError::invalid_payload(
                 "sender and destination parachain IDs must differ",
             ));
         }
 
+        if let Err(err) = self.check_route(rules) {
+            errors.push(err);
+        }
+
         if self.instructions.is_empty() {
-            return Err(MessageValidationError::invalid_payload(
+            errors.push(MessageValidationError::invalid_payload(
                 "at least one instruction is required",
             ));
         }
 
         if !self.xcm_version.is_supported(configured_version) {
-            return Err(MessageValidationError {
+            errors.push(MessageValidationError {
                 code: XcmErrorCode::VersionMismatch,
                 detail: format!(
                     "message version {0} mismatches configured version {configured_version}",
@@ -90,93 +372,560 @@ impl MessageEnvelope {
         }
 
         for (idx, instruction) in self.instructions.iter().enumerate() {
-            instruction.validate().map_err(|err| {
-                MessageValidationError::invalid_payload(format!(
-                    "instruction {idx} invalid: {}",
-                    err.detail
-                ))
-            })?;
+            if let Err(err) = self.validate_instruction(idx, instruction, rules) {
+                errors.push(err);
+            }
+        }
+
+        if rules.reject_duplicate_instructions {
+            let mut seen = HashSet::new();
+            for (idx, instruction) in self.instructions.iter().enumerate() {
+                if !seen.insert(instruction) {
+                    errors.push(MessageValidationError::invalid_payload(format!(
+                        "instruction {idx} ({}) duplicates an earlier instruction",
+                        instruction.kind()
+                    )));
+                }
+            }
         }
 
+        if let Err(err) = self.check_transfer_totals(rules.max_message_transfer_total) {
+            errors.push(err);
+        }
+
+        if let Err(err) = self.check_holding_order() {
+            errors.push(err);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Reject routes longer than `max_hops` parachains, endpoints
+    /// included, and routes through `via` that visit a parachain twice.
+    /// A destination listed in `via` is rejected unless
+    /// `validation.dest_in_via` delivers at its first occurrence.
+    fn check_route(&self, rules: &ValidationConfig) -> Result<(), MessageValidationError> {
+        if rules.dest_in_via == DestInViaPolicy::Reject {
+            if let Some(index) = self.via.iter().position(|id| *id == self.dest_para) {
+                return Err(MessageValidationError::invalid_payload(format!(
+                    "destination parachain {} also appears in via at index {index}",
+                    self.dest_para
+                )));
+            }
+        }
+        let max_hops = rules.max_hops;
+        let path = self.hop_path();
+        if path.len() > max_hops {
+            return Err(MessageValidationError::invalid_payload(format!(
+                "route visits {} parachains, exceeding the maximum of {max_hops}",
+                path.len()
+            )));
+        }
+        if self.via.is_empty() {
+            return Ok(());
+        }
+        let mut seen = HashSet::new();
+        match path.iter().find(|para_id| !seen.insert(**para_id)) {
+            Some(para_id) => Err(MessageValidationError::invalid_payload(format!(
+                "route visits parachain {para_id} more than once"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_instruction(
+        &self,
+        idx: usize,
+        instruction: &Instruction,
+        rules: &ValidationConfig,
+    ) -> Result<(), MessageValidationError> {
+        let required = rules
+            .instruction_versions
+            .iter()
+            .find(|entry| entry.instruction == instruction.kind())
+            .map(|entry| entry.min_version)
+            .or_else(|| XcmVersion::introducing(instruction.kind()));
+        if let Some(required) = required.filter(|required| self.xcm_version < *required) {
+            return Err(MessageValidationError::unsupported_instruction(format!(
+                "instruction {idx} ({}) requires XCM {required} or later; the message uses XCM {}",
+                instruction.kind(),
+                self.xcm_version
+            )));
+        }
+        if rules
+            .disabled_instructions
+            .iter()
+            .any(|kind| kind == instruction.kind())
+        {
+            return Err(MessageValidationError::unsupported_instruction(format!(
+                "instruction {idx} ({}) is disabled on this service",
+                instruction.kind()
+            )));
+        }
+        if let Instruction::Transact(transact) = instruction {
+            let origin_kind = transact.origin_kind();
+            if !rules.allowed_origin_kinds.contains(&origin_kind) {
+                return Err(MessageValidationError::unsupported_instruction(format!(
+                    "instruction {idx} (transact) uses origin kind {origin_kind}, which is not allowed on this service"
+                )));
+            }
+        }
+        if instruction.is_privileged() && !rules.privileged_senders.contains(&self.sender_para) {
+            return Err(MessageValidationError::unsupported_instruction(format!(
+                "instruction {idx} ({}) requires a privileged sender; parachain {} is not privileged",
+                instruction.kind(),
+                self.sender_para
+            )));
+        }
+        instruction.validate(rules).map_err(|err| {
+            MessageValidationError::invalid_payload(format!(
+                "instruction {idx} invalid: {}",
+                err.detail
+            ))
+        })
+    }
+
+    /// Reject messages whose transfers of any one asset add up to more
+    /// than `cap`.
+    fn check_transfer_totals(&self, cap: u128) -> Result<(), MessageValidationError> {
+        let mut totals: HashMap<&str, u128> = HashMap::new();
+        for instruction in &self.instructions {
+            let (asset, amount) = match instruction {
+                Instruction::TransferReserveAsset(data) => (&data.asset, data.amount),
+                // Counted as if the condition holds, bounding the worst case.
+                Instruction::ConditionalTransfer(data) => (&data.asset, data.amount),
+                _ => continue,
+            };
+            let total = totals.entry(asset.as_str()).or_default();
+            *total = total.saturating_add(amount);
+            if *total > cap {
+                return Err(MessageValidationError::invalid_payload(format!(
+                    "transfers of {asset} total more than the per-message maximum of {cap}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Statically check that deposits are funded by earlier withdrawals.
+    ///
+    /// Instructions run in array order against a per-asset holding register:
+    /// `withdrawAsset` adds to the register and `depositAsset` takes from it.
+    /// A deposit may never take more of an asset than the withdrawals before
+    /// it have placed in the register. Other instructions do not touch it.
+    fn check_holding_order(&self) -> Result<(), MessageValidationError> {
+        let mut holding: HashMap<&str, u128> = HashMap::new();
+        for (idx, instruction) in self.instructions.iter().enumerate() {
+            match instruction {
+                Instruction::WithdrawAsset(withdraw) => {
+                    let held = holding.entry(withdraw.asset.as_str()).or_default();
+                    *held = held.checked_add(withdraw.amount).ok_or_else(|| {
+                        MessageValidationError::invalid_payload(format!(
+                            "instruction {idx} (withdrawAsset) overflows the holding register for {}",
+                            withdraw.asset
+                        ))
+                    })?;
+                }
+                Instruction::DepositAsset(deposit) => {
+                    let held = holding.entry(deposit.asset.as_str()).or_default();
+                    if deposit.amount > *held {
+                        return Err(MessageValidationError::invalid_payload(format!(
+                            "instruction {idx} (depositAsset) deposits {} {} but prior withdrawals only provide {held}",
+                            deposit.amount, deposit.asset
+                        )));
+                    }
+                    *held -= deposit.amount;
+                }
+                _ => {}
+            }
+        }
         Ok(())
     }
 }
 
 /// Supported instruction set for the MVP.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum Instruction {
     TransferReserveAsset(TransferReserveAsset),
+    WithdrawAsset(WithdrawAsset),
+    DepositAsset(DepositAsset),
     Transact(Transact),
     QueryResponse(QueryResponse),
+    SetBalance(SetBalance),
+    ConditionalTransfer(ConditionalTransfer),
 }
 
 impl Instruction {
-    pub fn validate(&self) -> Result<(), MessageValidationError> {
+    /// Wire name of the instruction, matching its serde `type` tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Instruction::TransferReserveAsset(_) => "transferReserveAsset",
+            Instruction::WithdrawAsset(_) => "withdrawAsset",
+            Instruction::DepositAsset(_) => "depositAsset",
+            Instruction::Transact(_) => "transact",
+            Instruction::QueryResponse(_) => "queryResponse",
+            Instruction::SetBalance(_) => "setBalance",
+            Instruction::ConditionalTransfer(_) => "conditionalTransfer",
+        }
+    }
+
+    /// Wire names of the optional fields left out of this instruction's JSON
+    /// while unset.
+    fn optional_fields(&self) -> &'static [&'static str] {
         match self {
-            Instruction::TransferReserveAsset(data) => data.validate(),
-            Instruction::Transact(data) => data.validate(),
+            Instruction::Transact(_) => &["originKind"],
+            Instruction::QueryResponse(_) => &["responseLen", "responseHash"],
+            _ => &[],
+        }
+    }
+
+    /// Whether only `validation.privileged_senders` may send this instruction.
+    pub fn is_privileged(&self) -> bool {
+        matches!(self, Instruction::SetBalance(_))
+    }
+
+    pub fn validate(&self, rules: &ValidationConfig) -> Result<(), MessageValidationError> {
+        let max_transfer_amount = rules.max_transfer_amount;
+        match self {
+            Instruction::TransferReserveAsset(data) => data.validate(max_transfer_amount),
+            Instruction::WithdrawAsset(data) => {
+                validate_asset_amount(&data.asset, data.amount, max_transfer_amount)
+            }
+            Instruction::DepositAsset(data) => {
+                validate_asset_amount(&data.asset, data.amount, max_transfer_amount)?;
+                validate_beneficiary(&data.beneficiary)
+            }
+            Instruction::Transact(data) => data.validate(rules.max_call_data_bytes),
             Instruction::QueryResponse(data) => data.validate(),
+            Instruction::SetBalance(data) => data.validate(),
+            Instruction::ConditionalTransfer(data) => data.validate(max_transfer_amount),
+        }?;
+        if rules.multilocation_assets {
+            for asset in self.assets() {
+                MultiLocation::from_str(asset)
+                    .map_err(|err| MessageValidationError::invalid_payload(err.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Asset identifiers the instruction refers to.
+    fn assets(&self) -> Vec<&str> {
+        match self {
+            Instruction::TransferReserveAsset(data) => vec![&data.asset],
+            Instruction::WithdrawAsset(data) => vec![&data.asset],
+            Instruction::DepositAsset(data) => vec![&data.asset],
+            Instruction::SetBalance(data) => vec![&data.asset],
+            Instruction::ConditionalTransfer(data) => vec![&data.asset, &data.condition.asset],
+            Instruction::Transact(_) | Instruction::QueryResponse(_) => Vec::new(),
+        }
+    }
+
+    fn assets_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Instruction::TransferReserveAsset(data) => vec![&mut data.asset],
+            Instruction::WithdrawAsset(data) => vec![&mut data.asset],
+            Instruction::DepositAsset(data) => vec![&mut data.asset],
+            Instruction::SetBalance(data) => vec![&mut data.asset],
+            Instruction::ConditionalTransfer(data) => {
+                vec![&mut data.asset, &mut data.condition.asset]
+            }
+            Instruction::Transact(_) | Instruction::QueryResponse(_) => Vec::new(),
         }
     }
 }
 
 /// Representation of a `TransferReserveAsset` instruction.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
 #[serde(rename_all = "camelCase")]
 pub struct TransferReserveAsset {
     pub asset: String,
+    #[serde(with = "amount_format")]
     pub amount: u128,
     pub beneficiary: String,
 }
 
 impl TransferReserveAsset {
+    fn validate(&self, max_amount: u128) -> Result<(), MessageValidationError> {
+        validate_asset_amount(&self.asset, self.amount, max_amount)?;
+        validate_beneficiary(&self.beneficiary)
+    }
+}
+
+/// Representation of a `WithdrawAsset` instruction, moving funds from the
+/// sender's sovereign account into the holding register.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawAsset {
+    pub asset: String,
+    #[serde(with = "amount_format")]
+    pub amount: u128,
+}
+
+/// Representation of a `DepositAsset` instruction, moving funds from the
+/// holding register to a beneficiary.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
+#[serde(rename_all = "camelCase")]
+pub struct DepositAsset {
+    pub asset: String,
+    #[serde(with = "amount_format")]
+    pub amount: u128,
+    pub beneficiary: String,
+}
+
+/// Representation of a privileged `SetBalance` instruction, overwriting an
+/// account's balance on the destination. Intended for test setup.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
+#[serde(rename_all = "camelCase")]
+pub struct SetBalance {
+    pub account: String,
+    pub asset: String,
+    #[serde(with = "amount_format")]
+    pub amount: u128,
+}
+
+impl SetBalance {
     fn validate(&self) -> Result<(), MessageValidationError> {
+        if self.account.trim().is_empty() {
+            return Err(MessageValidationError::invalid_payload(
+                "account must be provided",
+            ));
+        }
         if self.asset.trim().is_empty() {
             return Err(MessageValidationError::invalid_payload(
                 "asset identifier must be provided",
             ));
         }
-        if self.amount == 0 {
+        Ok(())
+    }
+}
+
+/// Representation of a `ConditionalTransfer` instruction: a reserve
+/// transfer applied only when `condition` holds on the destination.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionalTransfer {
+    pub asset: String,
+    #[serde(with = "amount_format")]
+    pub amount: u128,
+    pub beneficiary: String,
+    pub condition: BalanceCondition,
+}
+
+impl ConditionalTransfer {
+    /// The transfer applied when the condition holds.
+    pub fn transfer(&self) -> TransferReserveAsset {
+        TransferReserveAsset {
+            asset: self.asset.clone(),
+            amount: self.amount,
+            beneficiary: self.beneficiary.clone(),
+        }
+    }
+
+    fn validate(&self, max_amount: u128) -> Result<(), MessageValidationError> {
+        validate_asset_amount(&self.asset, self.amount, max_amount)?;
+        validate_beneficiary(&self.beneficiary)?;
+        self.condition.validate()
+    }
+}
+
+/// Predicate comparing an account's destination balance of `asset` with a
+/// threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceCondition {
+    pub account: String,
+    pub asset: String,
+    pub comparison: Comparison,
+    #[serde(with = "amount_format")]
+    pub threshold: u128,
+}
+
+impl BalanceCondition {
+    /// Whether `balance` satisfies the condition.
+    pub fn holds(&self, balance: u128) -> bool {
+        self.comparison.compare(balance, self.threshold)
+    }
+
+    fn validate(&self) -> Result<(), MessageValidationError> {
+        if self.account.trim().is_empty() {
             return Err(MessageValidationError::invalid_payload(
-                "transfer amount must be greater than zero",
+                "condition account must be provided",
             ));
         }
-        if self.beneficiary.trim().is_empty() {
+        if self.asset.trim().is_empty() {
             return Err(MessageValidationError::invalid_payload(
-                "beneficiary must be provided",
+                "condition asset identifier must be provided",
             ));
         }
         Ok(())
     }
 }
 
+/// Comparison operators accepted by a [`BalanceCondition`], written `lt`,
+/// `lte`, `gt`, `gte`, `eq`, or `ne`. Anything else fails to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
+#[serde(rename_all = "lowercase")]
+pub enum Comparison {
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    pub fn compare(self, lhs: u128, rhs: u128) -> bool {
+        match self {
+            Comparison::Lt => lhs < rhs,
+            Comparison::Lte => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Gte => lhs >= rhs,
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Comparison::Lt => "lt",
+            Comparison::Lte => "lte",
+            Comparison::Gt => "gt",
+            Comparison::Gte => "gte",
+            Comparison::Eq => "eq",
+            Comparison::Ne => "ne",
+        };
+        f.write_str(name)
+    }
+}
+
+fn validate_asset_amount(
+    asset: &str,
+    amount: u128,
+    max_amount: u128,
+) -> Result<(), MessageValidationError> {
+    if asset.trim().is_empty() {
+        return Err(MessageValidationError::invalid_payload(
+            "asset identifier must be provided",
+        ));
+    }
+    if amount == 0 {
+        return Err(MessageValidationError::invalid_payload(
+            "transfer amount must be greater than zero",
+        ));
+    }
+    if amount > max_amount {
+        return Err(MessageValidationError::invalid_payload(format!(
+            "transfer amount {amount} exceeds the maximum of {max_amount}"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_beneficiary(beneficiary: &str) -> Result<(), MessageValidationError> {
+    if beneficiary.trim().is_empty() {
+        return Err(MessageValidationError::invalid_payload(
+            "beneficiary must be provided",
+        ));
+    }
+    Ok(())
+}
+
 /// Representation of a `Transact` instruction.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
 #[serde(rename_all = "camelCase")]
 pub struct Transact {
     pub call_data: String,
     #[serde(default)]
     pub weight: Option<u64>,
+    /// Authority the call is dispatched with; unset means
+    /// `sovereignAccount`. Omitted from JSON signing bytes when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_kind: Option<OriginKind>,
+}
+
+/// Origin a `Transact` call is dispatched as on the destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
+#[serde(rename_all = "camelCase")]
+pub enum OriginKind {
+    /// The sender chain's own native origin.
+    Native,
+    /// The sender's sovereign account on the destination.
+    #[default]
+    SovereignAccount,
+    /// Root authority; only privileged senders may use it.
+    Superuser,
+}
+
+impl Display for OriginKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OriginKind::Native => "native",
+            OriginKind::SovereignAccount => "sovereignAccount",
+            OriginKind::Superuser => "superuser",
+        };
+        f.write_str(name)
+    }
 }
 
 impl Transact {
-    fn validate(&self) -> Result<(), MessageValidationError> {
-        if self.call_data.trim().is_empty() {
+    pub fn origin_kind(&self) -> OriginKind {
+        self.origin_kind.unwrap_or_default()
+    }
+
+    fn validate(&self, max_call_data_bytes: usize) -> Result<(), MessageValidationError> {
+        let encoded = self.call_data.trim();
+        if encoded.is_empty() {
             return Err(MessageValidationError::invalid_payload(
                 "call_data must be provided",
             ));
         }
+        let decoded = hex::decode(encoded.trim_start_matches("0x")).map_err(|err| {
+            MessageValidationError::invalid_payload(format!("call_data is not valid hex: {err}"))
+        })?;
+        if decoded.len() > max_call_data_bytes {
+            return Err(MessageValidationError::invalid_payload(format!(
+                "call_data is {} bytes, exceeding the maximum of {max_call_data_bytes}",
+                decoded.len()
+            )));
+        }
         Ok(())
     }
 }
 
 /// Representation of a `QueryResponse` instruction.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode))]
 #[serde(rename_all = "camelCase")]
 pub struct QueryResponse {
     pub query_id: String,
     pub response: String,
+    /// Declared byte length of `response`, checked during validation.
+    /// Omitted from JSON signing bytes when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_len: Option<u64>,
+    /// Declared hex SHA-256 of `response`, checked during validation.
+    /// Omitted from JSON signing bytes when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_hash: Option<String>,
 }
 
 impl QueryResponse {
@@ -191,10 +940,115 @@ impl QueryResponse {
                 "response must be provided",
             ));
         }
+        let actual_len = self.response.len() as u64;
+        if let Some(declared) = self.response_len.filter(|len| *len != actual_len) {
+            return Err(MessageValidationError::invalid_payload(format!(
+                "response is {actual_len} bytes but responseLen declares {declared}"
+            )));
+        }
+        if let Some(declared) = &self.response_hash {
+            let actual = hex::encode(Sha256::digest(self.response.as_bytes()));
+            if !declared
+                .trim_start_matches("0x")
+                .eq_ignore_ascii_case(&actual)
+            {
+                return Err(MessageValidationError::invalid_payload(format!(
+                    "response hashes to {actual} but responseHash declares {declared}"
+                )));
+            }
+        }
         Ok(())
     }
 }
 
+/// Find the first key present in `raw` but absent from the re-serialized
+/// `known` value, returning its dotted path.
+fn first_unknown_field(
+    raw: &serde_json::Value,
+    known: &serde_json::Value,
+    path: &str,
+) -> Option<String> {
+    use serde_json::Value;
+
+    match (raw, known) {
+        (Value::Object(raw), Value::Object(known)) => raw.iter().find_map(|(key, value)| {
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            match known.get(key) {
+                Some(known_value) => first_unknown_field(value, known_value, &child),
+                None => Some(child),
+            }
+        }),
+        (Value::Array(raw), Value::Array(known)) => {
+            raw.iter()
+                .zip(known)
+                .enumerate()
+                .find_map(|(idx, (value, known_value))| {
+                    first_unknown_field(value, known_value, &format!("{path}[{idx}]"))
+                })
+        }
+        _ => None,
+    }
+}
+
+/// Serde adapter for `u128` amounts inside internally tagged instructions.
+///
+/// Serde buffers tagged enum content in a form that cannot hold `u128`, so
+/// amounts are read via `deserialize_any` and accepted either as JSON numbers
+/// or as decimal strings. Values beyond `u64::MAX` are written as strings so
+/// they round-trip without loss.
+pub(crate) mod amount_format {
+    use std::fmt;
+
+    use serde::{
+        de::{self, Visitor},
+        Deserializer, Serializer,
+    };
+
+    pub fn serialize<S: Serializer>(amount: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        match u64::try_from(*amount) {
+            Ok(small) => serializer.serialize_u64(small),
+            Err(_) => serializer.serialize_str(&amount.to_string()),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+
+    struct AmountVisitor;
+
+    impl Visitor<'_> for AmountVisitor {
+        type Value = u128;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a non-negative integer or decimal string")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<u128, E> {
+            Ok(value.into())
+        }
+
+        fn visit_u128<E: de::Error>(self, value: u128) -> Result<u128, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<u128, E> {
+            u128::try_from(value).map_err(|_| E::custom("amount must not be negative"))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<u128, E> {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| E::custom(format!("invalid amount: {value}")))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -204,12 +1058,18 @@ mod tests {
             message_id: Some("msg-1".to_string()),
             sender_para: 1000,
             dest_para: 2000,
+            via: Vec::new(),
             xcm_version: XcmVersion::V3,
             instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
                 asset: "DOT".into(),
                 amount: 10,
                 beneficiary: "acct-123".into(),
             })],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
             signature: Some("deadbeef".into()),
         }
     }
@@ -217,21 +1077,619 @@ mod tests {
     #[test]
     fn validates_correct_message() {
         let message = sample_message();
-        assert!(message.validate("V3").is_ok());
+        assert!(message.validate("V3", &ValidationConfig::default()).is_ok());
     }
 
     #[test]
     fn rejects_missing_instructions() {
         let mut message = sample_message();
         message.instructions.clear();
-        let err = message.validate("V3").unwrap_err();
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+    }
+
+    #[test]
+    fn rejects_transfers_above_the_cap() {
+        let message = sample_message();
+        let cap = |max_transfer_amount| ValidationConfig {
+            max_transfer_amount,
+            ..ValidationConfig::default()
+        };
+        assert!(message.validate("V3", &cap(10)).is_ok());
+        let err = message.validate("V3", &cap(9)).unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err.detail.contains("exceeds the maximum of 9"));
+    }
+
+    #[test]
+    fn caps_the_total_transferred_per_asset() {
+        let transfer = |asset: &str, amount| {
+            Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: asset.into(),
+                amount,
+                beneficiary: "acct-123".into(),
+            })
+        };
+        let rules = ValidationConfig {
+            max_message_transfer_total: 10,
+            ..ValidationConfig::default()
+        };
+        let mut message = sample_message();
+
+        message.instructions = vec![transfer("DOT", 6), transfer("KSM", 6)];
+        assert!(message.validate("V3", &rules).is_ok());
+
+        message.instructions = vec![transfer("DOT", 6), transfer("KSM", 6), transfer("DOT", 5)];
+        let err = message.validate("V3", &rules).unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err.detail.contains("transfers of DOT total more than"));
+    }
+
+    #[test]
+    fn rejects_unrecognised_condition_comparisons() {
+        let mut message = sample_message();
+        message.instructions = vec![Instruction::ConditionalTransfer(ConditionalTransfer {
+            asset: "DOT".into(),
+            amount: 5,
+            beneficiary: "acct-456".into(),
+            condition: BalanceCondition {
+                account: "acct-123".into(),
+                asset: "DOT".into(),
+                comparison: Comparison::Gte,
+                threshold: 10,
+            },
+        })];
+        assert!(message.validate("V3", &ValidationConfig::default()).is_ok());
+
+        let mut raw = serde_json::to_value(&message).expect("json");
+        assert_eq!(raw["instructions"][0]["condition"]["comparison"], "gte");
+        raw["instructions"][0]["condition"]["comparison"] = "<".into();
+        let err = MessageEnvelope::from_value(raw, false).unwrap_err();
         assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err.detail.contains("unknown variant `<`"), "{}", err.detail);
+    }
+
+    #[test]
+    fn rejects_disabled_instruction_kinds() {
+        let rules = ValidationConfig {
+            disabled_instructions: vec!["transferReserveAsset".into()],
+            ..ValidationConfig::default()
+        };
+        let err = sample_message().validate("V3", &rules).unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::UnsupportedInstruction);
+        assert!(err.detail.contains("(transferReserveAsset) is disabled"));
+    }
+
+    #[test]
+    fn loopback_messages_need_allow_loopback() {
+        let mut message = sample_message();
+        message.dest_para = message.sender_para;
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert!(err.detail.contains("must differ"));
+
+        let rules = ValidationConfig {
+            allow_loopback: true,
+            ..ValidationConfig::default()
+        };
+        assert!(message.validate("V3", &rules).is_ok());
+    }
+
+    #[test]
+    fn deposits_must_follow_matching_withdrawals() {
+        let withdraw = |amount| {
+            Instruction::WithdrawAsset(WithdrawAsset {
+                asset: "DOT".into(),
+                amount,
+            })
+        };
+        let deposit = |amount| {
+            Instruction::DepositAsset(DepositAsset {
+                asset: "DOT".into(),
+                amount,
+                beneficiary: "acct-123".into(),
+            })
+        };
+        let mut message = sample_message();
+
+        message.instructions = vec![withdraw(10), deposit(4), deposit(6)];
+        assert!(message.validate("V3", &ValidationConfig::default()).is_ok());
+
+        message.instructions = vec![deposit(4), withdraw(10)];
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err.detail.starts_with("instruction 0 (depositAsset)"));
+
+        message.instructions = vec![withdraw(10), deposit(11)];
+        assert!(message
+            .validate("V3", &ValidationConfig::default())
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_instructions_when_enabled() {
+        let mut message = sample_message();
+        message.instructions.push(message.instructions[0].clone());
+        assert!(message.validate("V3", &ValidationConfig::default()).is_ok());
+
+        let rules = ValidationConfig {
+            reject_duplicate_instructions: true,
+            ..ValidationConfig::default()
+        };
+        let err = message.validate("V3", &rules).unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err
+            .detail
+            .contains("instruction 1 (transferReserveAsset) duplicates"));
+    }
+
+    #[test]
+    fn set_balance_requires_a_privileged_sender() {
+        let mut message = sample_message();
+        message.instructions = vec![Instruction::SetBalance(SetBalance {
+            account: "acct-123".into(),
+            asset: "DOT".into(),
+            amount: 0,
+        })];
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::UnsupportedInstruction);
+
+        let rules = ValidationConfig {
+            privileged_senders: vec![1000],
+            ..ValidationConfig::default()
+        };
+        assert!(message.validate("V3", &rules).is_ok());
+    }
+
+    #[test]
+    fn multilocation_mode_requires_and_normalizes_locations() {
+        let rules = ValidationConfig {
+            multilocation_assets: true,
+            ..ValidationConfig::default()
+        };
+        let mut message = sample_message();
+        let err = message.validate("V3", &rules).unwrap_err();
+        assert!(err.detail.contains("invalid multilocation `DOT`"));
+
+        message.instructions = vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+            asset: r#"{"parents":1}"#.into(),
+            amount: 10,
+            beneficiary: "acct-123".into(),
+        })];
+        message.validate("V3", &rules).expect("json multilocation");
+        message.normalize_assets();
+        assert!(matches!(
+            &message.instructions[0],
+            Instruction::TransferReserveAsset(data) if data.asset == "1"
+        ));
+    }
+
+    #[test]
+    fn validate_all_reports_every_problem() {
+        let mut message = sample_message();
+        message.dest_para = message.sender_para;
+        message
+            .instructions
+            .push(Instruction::WithdrawAsset(WithdrawAsset {
+                asset: String::new(),
+                amount: 1,
+            }));
+
+        let errors = message
+            .validate_all("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].detail.contains("must differ"));
+        assert!(errors[1].detail.starts_with("instruction 1 invalid"));
+
+        let first = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(first.detail, errors[0].detail);
+    }
+
+    #[test]
+    fn rejects_routes_over_max_hops() {
+        let mut message = sample_message();
+        message.via = vec![1001];
+        assert!(message.validate("V3", &ValidationConfig::default()).is_ok());
+        assert_eq!(message.hop_path(), [1000, 1001, 2000]);
+
+        message.via = vec![1001, 1002];
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err
+            .detail
+            .contains("4 parachains, exceeding the maximum of 3"));
+
+        let rules = ValidationConfig {
+            max_hops: 4,
+            ..ValidationConfig::default()
+        };
+        assert!(message.validate("V3", &rules).is_ok());
+    }
+
+    #[test]
+    fn rejects_routes_that_loop() {
+        let mut message = sample_message();
+        message.via = vec![1000];
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err.detail.contains("parachain 1000 more than once"));
+
+        let rules = ValidationConfig {
+            allow_loopback: true,
+            ..ValidationConfig::default()
+        };
+        message.dest_para = 1000;
+        message.via = vec![1001];
+        let err = message.validate("V3", &rules).unwrap_err();
+        assert!(err.detail.contains("parachain 1000 more than once"));
+        message.via.clear();
+        assert!(message.validate("V3", &rules).is_ok());
+    }
+
+    #[test]
+    fn destination_in_via_is_rejected_unless_delivered_at_first_hop() {
+        let mut message = sample_message();
+        message.via = vec![1500, 2000, 1600];
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err
+            .detail
+            .contains("destination parachain 2000 also appears in via"));
+
+        let rules = ValidationConfig {
+            dest_in_via: DestInViaPolicy::DeliverAtFirst,
+            ..ValidationConfig::default()
+        };
+        assert!(message.validate("V3", &rules).is_ok());
+        assert_eq!(message.hop_path(), [1000, 1500, 2000]);
+    }
+
+    #[test]
+    fn v4_only_instructions_are_rejected_under_v3() {
+        let v4_only: Vec<_> = XcmVersion::V4
+            .supported_instructions()
+            .iter()
+            .filter(|kind| !XcmVersion::V3.supported_instructions().contains(kind))
+            .collect();
+        assert_eq!(v4_only, [&"queryResponse"]);
+        assert!(XcmVersion::V3
+            .supported_instructions()
+            .iter()
+            .all(|kind| XcmVersion::V4.supported_instructions().contains(kind)));
+        assert_eq!(
+            XcmVersion::introducing("queryResponse"),
+            Some(XcmVersion::V4)
+        );
+        assert_eq!(XcmVersion::introducing("transact"), Some(XcmVersion::V3));
+        assert_eq!(XcmVersion::introducing("teleportAsset"), None);
+    }
+
+    #[test]
+    fn query_response_requires_v4_unless_overridden() {
+        let mut message = sample_message();
+        message.instructions = vec![Instruction::QueryResponse(QueryResponse {
+            query_id: "q-1".into(),
+            response: "ok".into(),
+            response_len: None,
+            response_hash: None,
+        })];
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::UnsupportedInstruction);
+        assert!(err.detail.contains("requires XCM V4 or later"));
+
+        let rules = ValidationConfig {
+            instruction_versions: vec![crate::config::InstructionVersionConfig {
+                instruction: "queryResponse".into(),
+                min_version: XcmVersion::V3,
+            }],
+            ..ValidationConfig::default()
+        };
+        assert!(message.validate("V3", &rules).is_ok());
+
+        message.xcm_version = XcmVersion::V4;
+        assert!(message.validate("V4", &ValidationConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn query_response_must_match_its_declared_size_and_hash() {
+        let mut message = sample_message();
+        message.xcm_version = XcmVersion::V4;
+        let response = QueryResponse {
+            query_id: "q-1".into(),
+            response: "ok".into(),
+            response_len: Some(2),
+            response_hash: Some(hex::encode(Sha256::digest(b"ok"))),
+        };
+        message.instructions = vec![Instruction::QueryResponse(response.clone())];
+        assert!(message.validate("V4", &ValidationConfig::default()).is_ok());
+
+        message.instructions = vec![Instruction::QueryResponse(QueryResponse {
+            response_len: Some(3),
+            ..response.clone()
+        })];
+        let err = message
+            .validate("V4", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err.detail.contains("responseLen declares 3"));
+
+        message.instructions = vec![Instruction::QueryResponse(QueryResponse {
+            response: "no".into(),
+            ..response
+        })];
+        let err = message
+            .validate("V4", &ValidationConfig::default())
+            .unwrap_err();
+        assert!(err.detail.contains("responseHash declares"));
+    }
+
+    #[test]
+    fn origin_kinds_must_be_allowed() {
+        let mut message = sample_message();
+        message.instructions = vec![Instruction::Transact(Transact {
+            call_data: "0x00".into(),
+            weight: None,
+            origin_kind: Some(OriginKind::Native),
+        })];
+        let rules = ValidationConfig {
+            allowed_origin_kinds: vec![OriginKind::SovereignAccount],
+            ..ValidationConfig::default()
+        };
+        let err = message.validate("V3", &rules).unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::UnsupportedInstruction);
+        assert!(err.detail.contains("origin kind native"));
+
+        message.instructions = vec![Instruction::Transact(Transact {
+            call_data: "0x00".into(),
+            weight: None,
+            origin_kind: None,
+        })];
+        assert!(message.validate("V3", &rules).is_ok());
+        let parsed: Transact =
+            serde_json::from_str(r#"{"callData":"0x00","originKind":"superuser"}"#).expect("json");
+        assert_eq!(parsed.origin_kind(), OriginKind::Superuser);
+    }
+
+    #[test]
+    fn bounds_decoded_call_data_size() {
+        let mut message = sample_message();
+        message.instructions = vec![Instruction::Transact(Transact {
+            call_data: format!("0x{}", "ab".repeat(4)),
+            weight: None,
+            origin_kind: None,
+        })];
+        let rules = ValidationConfig {
+            max_call_data_bytes: 4,
+            ..ValidationConfig::default()
+        };
+        assert!(message.validate("V3", &rules).is_ok());
+
+        let rules = ValidationConfig {
+            max_call_data_bytes: 3,
+            ..rules
+        };
+        let err = message.validate("V3", &rules).unwrap_err();
+        assert!(err.detail.contains("4 bytes, exceeding the maximum of 3"));
+
+        message.instructions = vec![Instruction::Transact(Transact {
+            call_data: "not hex".into(),
+            weight: None,
+            origin_kind: None,
+        })];
+        let err = message
+            .validate("V3", &ValidationConfig::default())
+            .unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+    }
+
+    #[test]
+    fn signing_bytes_bind_sender_and_ignore_signature() {
+        let message = sample_message();
+        let mut other_sender = message.clone();
+        other_sender.sender_para = 1001;
+        let mut resigned = message.clone();
+        resigned.signature = Some("cafebabe".into());
+
+        assert_ne!(message.signing_bytes(), other_sender.signing_bytes());
+        assert_eq!(message.signing_bytes(), resigned.signing_bytes());
+        assert!(message.signing_bytes().starts_with(SIGNING_DOMAIN));
+    }
+
+    #[test]
+    fn signing_bytes_bind_message_id() {
+        let mut message = sample_message();
+        message.message_id = Some("msg-1".into());
+        let mut swapped = message.clone();
+        swapped.message_id = Some("msg-2".into());
+        let mut dropped = message.clone();
+        dropped.message_id = None;
+
+        assert_ne!(message.signing_bytes(), swapped.signing_bytes());
+        assert_ne!(message.signing_bytes(), dropped.signing_bytes());
+    }
+
+    #[cfg(feature = "scale")]
+    #[test]
+    fn scale_signing_bytes_follow_the_documented_layout() {
+        let mut message = sample_message();
+        message.signing_encoding = SigningEncoding::Scale;
+        message.instructions.extend([
+            Instruction::Transact(Transact {
+                call_data: "0x00".into(),
+                weight: None,
+                origin_kind: None,
+            }),
+            Instruction::QueryResponse(QueryResponse {
+                query_id: "q".into(),
+                response: "r".into(),
+                response_len: None,
+                response_hash: None,
+            }),
+        ]);
+
+        let mut expected = SIGNING_DOMAIN.to_vec();
+        expected.extend_from_slice(&1000u32.to_le_bytes());
+        expected.extend_from_slice(b"\x01\x14msg-1");
+        expected.extend_from_slice(&1000u32.to_le_bytes());
+        expected.extend_from_slice(&2000u32.to_le_bytes());
+        // Empty `via`, XCM V3, three instructions: transferReserveAsset,
+        expected.extend_from_slice(&[0x00, 0x00, 0x0c, 0x00]);
+        expected.extend_from_slice(b"\x0cDOT");
+        expected.extend_from_slice(&10u128.to_le_bytes());
+        expected.extend_from_slice(b"\x20acct-123");
+        // transact with no weight or origin kind,
+        expected.extend_from_slice(b"\x03\x100x00\x00\x00");
+        // and queryResponse with no declared length or hash.
+        expected.extend_from_slice(b"\x04\x04q\x04r\x00\x00");
+        // ed25519, not prehashed, not deferred, no nonce.
+        expected.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(message.signing_bytes(), expected);
+
+        let json = serde_json::to_vec(&message).expect("serialize");
+        let decoded = MessageEnvelope::from_json(&json, true).expect("strict parse");
+        assert_eq!(decoded.signing_encoding, SigningEncoding::Scale);
+    }
+
+    #[test]
+    fn transfer_amounts_roundtrip_through_json() {
+        let mut message = sample_message();
+        message
+            .instructions
+            .push(Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount: u128::MAX,
+                beneficiary: "acct-456".into(),
+            }));
+
+        let json = serde_json::to_string(&message).expect("serialize");
+        let decoded: MessageEnvelope = serde_json::from_str(&json).expect("deserialize");
+        let amounts: Vec<u128> = decoded
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::TransferReserveAsset(data) => Some(data.amount),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(amounts, vec![10, u128::MAX]);
+    }
+
+    #[test]
+    fn instruction_kind_matches_serde_tag() {
+        let message = sample_message();
+        let instruction = &message.instructions[0];
+        let json = serde_json::to_value(instruction).expect("serialize");
+        assert_eq!(json["type"], instruction.kind());
+    }
+
+    #[test]
+    fn strict_parsing_names_unknown_fields() {
+        let mut json = serde_json::to_value(sample_message()).expect("serialize");
+        json["senderParaId"] = serde_json::json!(1000);
+        let bytes = serde_json::to_vec(&json).expect("encode");
+
+        assert!(MessageEnvelope::from_json(&bytes, false).is_ok());
+        let err = MessageEnvelope::from_json(&bytes, true).unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::InvalidPayload);
+        assert!(err.detail.contains("`senderParaId`"));
+    }
+
+    #[test]
+    fn strict_parsing_treats_null_as_an_unset_field() {
+        let mut message = sample_message();
+        message.instructions = vec![
+            Instruction::Transact(Transact {
+                call_data: "0x00".into(),
+                weight: None,
+                origin_kind: None,
+            }),
+            Instruction::QueryResponse(QueryResponse {
+                query_id: "query-1".into(),
+                response: "ok".into(),
+                response_len: None,
+                response_hash: None,
+            }),
+        ];
+        let mut json = serde_json::to_value(&message).expect("serialize");
+        json["nonce"] = serde_json::Value::Null;
+        json["instructions"][0]["originKind"] = serde_json::Value::Null;
+        json["instructions"][1]["responseLen"] = serde_json::Value::Null;
+        json["instructions"][1]["responseHash"] = serde_json::Value::Null;
+        let bytes = serde_json::to_vec(&json).expect("encode");
+
+        let decoded = MessageEnvelope::from_json(&bytes, true).expect("strict parse");
+        assert_eq!(decoded.instructions, message.instructions);
+    }
+
+    #[test]
+    fn strict_parsing_rejects_unknown_fields_set_to_null() {
+        let mut message = sample_message();
+        message.instructions = vec![
+            Instruction::Transact(Transact {
+                call_data: "0x00".into(),
+                weight: None,
+                origin_kind: None,
+            }),
+            Instruction::QueryResponse(QueryResponse {
+                query_id: "query-1".into(),
+                response: "ok".into(),
+                response_len: None,
+                response_hash: None,
+            }),
+        ];
+        let json = serde_json::to_value(&message).expect("serialize");
+
+        for (instruction, typo) in [
+            (None, "senderParaId"),
+            (Some(0), "originKnd"),
+            (Some(1), "responseLength"),
+        ] {
+            let mut json = json.clone();
+            let target = match instruction {
+                Some(idx) => &mut json["instructions"][idx],
+                None => &mut json,
+            };
+            target[typo] = serde_json::Value::Null;
+            let bytes = serde_json::to_vec(&json).expect("encode");
+            let err = MessageEnvelope::from_json(&bytes, true).unwrap_err();
+            assert!(err.detail.contains(typo), "{typo}: {}", err.detail);
+        }
+    }
+
+    #[test]
+    fn strict_parsing_checks_instruction_fields() {
+        let mut json = serde_json::to_value(sample_message()).expect("serialize");
+        json["instructions"][0]["amout"] = serde_json::json!(5);
+        let bytes = serde_json::to_vec(&json).expect("encode");
+
+        let err = MessageEnvelope::from_json(&bytes, true).unwrap_err();
+        assert!(err.detail.contains("`instructions[0].amout`"));
     }
 
     #[test]
     fn rejects_version_mismatch() {
         let message = sample_message();
-        let err = message.validate("V4").unwrap_err();
+        let err = message
+            .validate("V4", &ValidationConfig::default())
+            .unwrap_err();
         assert_eq!(err.code, XcmErrorCode::VersionMismatch);
     }
 }
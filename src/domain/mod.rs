@@ -1,7 +1,13 @@
+pub mod balance;
 pub mod errors;
+pub mod location;
 pub mod message;
 
+pub use balance::{Balance, BalanceError};
 pub use errors::{MessageValidationError, XcmErrorCode};
+pub use location::{Junction, MultiLocation, MultiLocationError};
 pub use message::{
-    Instruction, MessageEnvelope, QueryResponse, Transact, TransferReserveAsset, XcmVersion,
+    BalanceCondition, Comparison, ConditionalTransfer, DepositAsset, Instruction, MessageEnvelope,
+    OriginKind, QueryResponse, SetBalance, SignatureScheme, SigningEncoding, Transact,
+    TransferReserveAsset, WithdrawAsset, XcmVersion,
 };
@@ -1,104 +1,705 @@
+pub mod plan;
+pub mod simulation;
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
+
+use serde::Serialize;
+
 use crate::{
-    domain::{Instruction, MessageEnvelope, QueryResponse, Transact, TransferReserveAsset},
+    config::{EngineKind, ExecutionConfig, FeeRecipientConfig, UnderflowPolicy},
+    domain::{
+        Balance, DepositAsset, Instruction, MessageEnvelope, OriginKind, QueryResponse, SetBalance,
+        Transact, TransferReserveAsset, WithdrawAsset,
+    },
+    metrics::Metrics,
     state::{ParachainState, ServiceState},
 };
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
 
 /// Trait describing message execution behaviour for simulated parachains.
 pub trait ExecutionEngine: Send + Sync {
-    fn execute(&self, message: &MessageEnvelope) -> Result<ExecutionOutcome, ExecutionError>;
+    fn execute(&self, message: &MessageEnvelope) -> Result<ExecutionOutcome, ExecutionFailure>;
+
+    /// Execute unless `cancel` fires first, failing with
+    /// [`ExecutionError::Cancelled`] and no effects. Engines that cannot
+    /// stop part-way only check before starting.
+    fn execute_until_cancelled(
+        &self,
+        message: &MessageEnvelope,
+        cancel: &CancellationToken,
+    ) -> Result<ExecutionOutcome, ExecutionFailure> {
+        if cancel.is_cancelled() {
+            return Err(ExecutionError::Cancelled.into());
+        }
+        self.execute(message)
+    }
+
+    /// Short name recorded on each message this engine executes.
+    fn engine_name(&self) -> &str;
+}
+
+/// Engine that accepts every message without applying its effects,
+/// modelling a parachain that ignores what it is sent.
+pub struct NoopExecutionEngine;
+
+impl ExecutionEngine for NoopExecutionEngine {
+    fn execute(&self, message: &MessageEnvelope) -> Result<ExecutionOutcome, ExecutionFailure> {
+        Ok(ExecutionOutcome {
+            logs: Vec::new(),
+            instructions_executed: message.instructions.len(),
+            timings: None,
+        })
+    }
+
+    fn engine_name(&self) -> &str {
+        "noop"
+    }
+}
+
+/// Engines assigned to particular destinations by `execution.engines`.
+/// Destinations assigned the default kind share `default`.
+pub fn engines_by_destination(
+    config: &ExecutionConfig,
+    default: &Arc<dyn ExecutionEngine>,
+) -> HashMap<u32, Arc<dyn ExecutionEngine>> {
+    let noop: Arc<dyn ExecutionEngine> = Arc::new(NoopExecutionEngine);
+    config
+        .engines
+        .iter()
+        .map(|entry| {
+            let engine = match entry.engine {
+                EngineKind::Default => default.clone(),
+                EngineKind::Noop => noop.clone(),
+            };
+            (entry.para_id, engine)
+        })
+        .collect()
 }
 
 /// Outcome details produced by the execution engine.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ExecutionOutcome {
     pub logs: Vec<String>,
+    /// Instructions that ran, which is what weight is charged for.
+    pub instructions_executed: usize,
+    /// Present when `execution.record_timings` is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<ExecutionTimings>,
 }
 
-impl ExecutionOutcome {
-    pub fn summary(&self) -> Option<String> {
-        if self.logs.is_empty() {
-            None
-        } else {
-            Some(format!("{} instructions applied", self.logs.len()))
+/// Wall-clock time spent applying a message, in microseconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionTimings {
+    pub total_micros: u64,
+    pub instructions: Vec<InstructionTiming>,
+}
+
+/// Time spent applying one instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionTiming {
+    pub index: usize,
+    pub kind: &'static str,
+    pub micros: u64,
+}
+
+/// Weight charged for every instruction, on top of a `Transact`'s
+/// declared weight.
+pub const BASE_INSTRUCTION_WEIGHT: u64 = 1_000;
+
+/// Result of executing a message, mirroring XCM's `Outcome`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum XcmOutcome {
+    /// Every instruction ran.
+    Complete { weight_used: u64 },
+    /// Some instructions ran before `error` stopped execution. Their
+    /// effects are discarded.
+    Incomplete { weight_used: u64, error: String },
+    /// Execution failed before any instruction ran.
+    Error { error: String },
+}
+
+impl XcmOutcome {
+    /// Outcome of running `message` with `result`, charging the weight of
+    /// each instruction the engine reports as executed.
+    pub fn of(
+        message: &MessageEnvelope,
+        result: &Result<ExecutionOutcome, ExecutionFailure>,
+    ) -> Self {
+        let weight_of = |ran: usize| -> u64 {
+            message
+                .instructions
+                .iter()
+                .take(ran)
+                .map(instruction_weight)
+                .fold(0, u64::saturating_add)
+        };
+        match result {
+            Ok(outcome) => XcmOutcome::Complete {
+                weight_used: weight_of(outcome.instructions_executed),
+            },
+            Err(failure) if failure.instructions_executed == 0 => XcmOutcome::Error {
+                error: failure.error.to_string(),
+            },
+            Err(failure) => XcmOutcome::Incomplete {
+                weight_used: weight_of(failure.instructions_executed),
+                error: failure.error.to_string(),
+            },
         }
     }
 }
 
+/// Weight charged for one instruction: the base weight, plus the declared
+/// weight of a `Transact`.
+pub fn instruction_weight(instruction: &Instruction) -> u64 {
+    match instruction {
+        Instruction::Transact(data) => {
+            BASE_INSTRUCTION_WEIGHT.saturating_add(data.weight.unwrap_or_default())
+        }
+        _ => BASE_INSTRUCTION_WEIGHT,
+    }
+}
+
 /// Default implementation applying mock effects to in-memory state.
 pub struct DefaultExecutionEngine {
     state: ServiceState,
+    /// Asset to the parachains trusted as its reserve; empty trusts everyone.
+    reserves: HashMap<String, HashSet<u32>>,
+    underflow_policy: UnderflowPolicy,
+    record_timings: bool,
+    /// Asset to the share of each reserve transfer taken as a fee, in basis
+    /// points.
+    transfer_fees: HashMap<String, u32>,
+    fee_recipient: Option<FeeRecipientConfig>,
+    /// Destination to the senders it accepts; absent destinations accept
+    /// everyone.
+    accepted_senders: HashMap<u32, HashSet<u32>>,
+    /// Asset to its existential deposit.
+    dust_limits: HashMap<String, u128>,
+    /// Senders allowed to dispatch `superuser` Transacts.
+    privileged_senders: HashSet<u32>,
+    /// Counts applied instructions when set; simulations leave it unset.
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl DefaultExecutionEngine {
-    pub fn new(state: ServiceState) -> Self {
-        Self { state }
+    pub fn new(state: ServiceState, config: &ExecutionConfig) -> Self {
+        let mut reserves: HashMap<String, HashSet<u32>> = HashMap::new();
+        for reserve in &config.reserves {
+            reserves
+                .entry(reserve.asset.clone())
+                .or_default()
+                .extend(&reserve.para_ids);
+        }
+        Self {
+            state,
+            reserves,
+            underflow_policy: config.underflow_policy,
+            record_timings: config.record_timings,
+            transfer_fees: config
+                .transfer_fees
+                .iter()
+                .map(|fee| (fee.asset.clone(), fee.basis_points))
+                .collect(),
+            fee_recipient: config.fee_recipient.clone(),
+            accepted_senders: config
+                .accepted_senders
+                .iter()
+                .map(|entry| (entry.para_id, entry.senders.iter().copied().collect()))
+                .collect(),
+            dust_limits: config
+                .dust_limits
+                .iter()
+                .map(|dust| (dust.asset.clone(), dust.limit))
+                .collect(),
+            privileged_senders: HashSet::new(),
+            metrics: None,
+        }
+    }
+
+    /// Reject a balance change that left `account` holding dust: more than
+    /// zero but less than `asset`'s existential deposit.
+    fn check_dust(
+        &self,
+        staged: &ParachainState,
+        account: &str,
+        asset: &str,
+    ) -> Result<(), ExecutionError> {
+        let Some(&limit) = self.dust_limits.get(asset) else {
+            return Ok(());
+        };
+        let balance = staged.balance(account, asset).get();
+        if balance > 0 && balance < limit {
+            return Err(ExecutionError::BelowExistentialDeposit {
+                account: account.to_string(),
+                asset: asset.to_string(),
+                balance,
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    /// Count each instruction of every committed execution in `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Let `senders` dispatch `superuser` Transacts, normally
+    /// `validation.privileged_senders`.
+    pub fn with_privileged_senders(mut self, senders: &[u32]) -> Self {
+        self.privileged_senders = senders.iter().copied().collect();
+        self
+    }
+
+    fn check_sender(&self, message: &MessageEnvelope) -> Result<(), ExecutionError> {
+        match self.accepted_senders.get(&message.dest_para) {
+            Some(senders) if !senders.contains(&message.sender_para) => {
+                Err(ExecutionError::SenderNotAccepted {
+                    sender: message.sender_para,
+                    dest: message.dest_para,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_reserve(&self, sender: u32, asset: &str) -> Result<(), ExecutionError> {
+        if self.reserves.is_empty()
+            || self
+                .reserves
+                .get(asset)
+                .is_some_and(|paras| paras.contains(&sender))
+        {
+            Ok(())
+        } else {
+            Err(ExecutionError::ReserveNotTrusted {
+                para_id: sender,
+                asset: asset.to_string(),
+            })
+        }
     }
 }
 
 impl ExecutionEngine for DefaultExecutionEngine {
-    fn execute(&self, message: &MessageEnvelope) -> Result<ExecutionOutcome, ExecutionError> {
-        let mut parachains = self
+    fn execute(&self, message: &MessageEnvelope) -> Result<ExecutionOutcome, ExecutionFailure> {
+        self.run(message, None)
+    }
+
+    /// Checks `cancel` before every instruction and again before committing.
+    fn execute_until_cancelled(
+        &self,
+        message: &MessageEnvelope,
+        cancel: &CancellationToken,
+    ) -> Result<ExecutionOutcome, ExecutionFailure> {
+        self.run(message, Some(cancel))
+    }
+
+    fn engine_name(&self) -> &str {
+        "default"
+    }
+}
+
+impl DefaultExecutionEngine {
+    fn run(
+        &self,
+        message: &MessageEnvelope,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<ExecutionOutcome, ExecutionFailure> {
+        self.check_sender(message)?;
+        if self.state.is_offline(message.dest_para) {
+            return Err(ExecutionError::DestinationOffline {
+                para_id: message.dest_para,
+            }
+            .into());
+        }
+        let mut dest_state = self
             .state
-            .parachains
+            .parachain(message.dest_para)
+            .ok_or(ExecutionError::UnknownParachain {
+                para_id: message.dest_para,
+            })?
             .write()
             .map_err(|_| ExecutionError::StatePoisoned)?;
 
-        let dest_state =
-            parachains
-                .get_mut(&message.dest_para)
-                .ok_or(ExecutionError::UnknownParachain {
-                    para_id: message.dest_para,
-                })?;
+        // Apply to a copy so a failing instruction leaves no partial effects.
+        let started = self.record_timings.then(Instant::now);
+        let mut staged = dest_state.clone();
+        let mut outcome = ExecutionOutcome {
+            logs: Vec::new(),
+            instructions_executed: 0,
+            timings: self.record_timings.then(ExecutionTimings::default),
+        };
+        let mut remote_fees = BTreeMap::new();
+        let applied =
+            self.apply_instructions(message, &mut staged, &mut outcome, &mut remote_fees, cancel);
+        if let Err(error) = applied.and_then(|()| match cancel {
+            Some(cancel) if cancel.is_cancelled() => Err(ExecutionError::Cancelled),
+            _ => Ok(()),
+        }) {
+            return Err(ExecutionFailure {
+                error,
+                logs: outcome.logs,
+                instructions_executed: outcome.instructions_executed,
+            });
+        }
 
-        let mut logs = Vec::new();
+        *dest_state = staged;
+        // Release the destination before touching the recipient's parachain
+        // so no two parachain locks are ever held at once.
+        drop(dest_state);
+        self.credit_remote_fees(&remote_fees);
+        if let Some(metrics) = &self.metrics {
+            for instruction in &message.instructions {
+                metrics.instructions_executed.increment(instruction.kind());
+            }
+        }
+        if let (Some(timings), Some(started)) = (&mut outcome.timings, started) {
+            timings.total_micros = elapsed_micros(started);
+        }
+        Ok(outcome)
+    }
 
-        for instruction in &message.instructions {
+    /// Apply every instruction in order, recording a log line for each one
+    /// that succeeds and counting it in `outcome`.
+    fn apply_instructions(
+        &self,
+        message: &MessageEnvelope,
+        staged: &mut ParachainState,
+        outcome: &mut ExecutionOutcome,
+        remote_fees: &mut BTreeMap<String, u128>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(), ExecutionError> {
+        let mut holding: HashMap<String, Balance> = HashMap::new();
+        for (index, instruction) in message.instructions.iter().enumerate() {
+            if cancel.is_some_and(CancellationToken::is_cancelled) {
+                return Err(ExecutionError::Cancelled);
+            }
+            let started = outcome.timings.is_some().then(Instant::now);
             match instruction {
                 Instruction::TransferReserveAsset(data) => {
-                    apply_transfer(dest_state, data);
-                    logs.push(format!(
-                        "TransferReserveAsset: {} {} to {}",
+                    self.check_reserve(message.sender_para, &data.asset)?;
+                    let fee =
+                        self.apply_transfer_with_fee(message.dest_para, staged, data, remote_fees)?;
+                    self.check_dust(staged, &data.beneficiary, &data.asset)?;
+                    outcome.logs.push(format!(
+                        "TransferReserveAsset: {} {} to {}{fee}",
+                        data.amount, data.asset, data.beneficiary
+                    ));
+                }
+                Instruction::WithdrawAsset(data) => {
+                    let account = sovereign_account(message.sender_para);
+                    let withdrawn = apply_withdraw(
+                        staged,
+                        &mut holding,
+                        &account,
+                        data,
+                        self.underflow_policy,
+                    )?;
+                    self.check_dust(staged, &account, &data.asset)?;
+                    outcome.logs.push(format!(
+                        "WithdrawAsset: {withdrawn} {} from {account}",
+                        data.asset
+                    ));
+                }
+                Instruction::DepositAsset(data) => {
+                    apply_deposit(staged, &mut holding, data)?;
+                    self.check_dust(staged, &data.beneficiary, &data.asset)?;
+                    outcome.logs.push(format!(
+                        "DepositAsset: {} {} to {}",
                         data.amount, data.asset, data.beneficiary
                     ));
                 }
                 Instruction::Transact(data) => {
-                    apply_transact(dest_state, data);
-                    logs.push(format!(
+                    if data.origin_kind() == OriginKind::Superuser
+                        && !self.privileged_senders.contains(&message.sender_para)
+                    {
+                        return Err(ExecutionError::UnsupportedInstruction(format!(
+                            "superuser transact requires a privileged sender; parachain {} is not privileged",
+                            message.sender_para
+                        )));
+                    }
+                    apply_transact(staged, data);
+                    outcome.logs.push(format!(
                         "Transact: call_data={} bytes, weight={}",
                         data.call_data.len(),
                         data.weight.unwrap_or_default()
                     ));
                 }
                 Instruction::QueryResponse(data) => {
-                    apply_query(dest_state, data);
-                    logs.push(format!(
+                    apply_query(staged, data);
+                    outcome.logs.push(format!(
                         "QueryResponse: id={}, response_length={}",
                         data.query_id,
                         data.response.len()
                     ));
                 }
+                Instruction::ConditionalTransfer(data) => {
+                    let condition = &data.condition;
+                    let balance = staged.balance(&condition.account, &condition.asset);
+                    if condition.holds(balance.get()) {
+                        self.check_reserve(message.sender_para, &data.asset)?;
+                        let fee = self.apply_transfer_with_fee(
+                            message.dest_para,
+                            staged,
+                            &data.transfer(),
+                            remote_fees,
+                        )?;
+                        self.check_dust(staged, &data.beneficiary, &data.asset)?;
+                        outcome.logs.push(format!(
+                            "ConditionalTransfer: {} {} to {}{fee}",
+                            data.amount, data.asset, data.beneficiary
+                        ));
+                    } else {
+                        outcome.logs.push(format!(
+                            "ConditionalTransfer: skipped, {} balance {balance} is not {} {}",
+                            condition.account, condition.comparison, condition.threshold
+                        ));
+                    }
+                }
+                Instruction::SetBalance(data) => {
+                    apply_set_balance(staged, data);
+                    outcome.logs.push(format!(
+                        "SetBalance: {} {} for {}",
+                        data.amount, data.asset, data.account
+                    ));
+                }
+            }
+            outcome.instructions_executed = index + 1;
+            if let (Some(timings), Some(started)) = (&mut outcome.timings, started) {
+                timings.instructions.push(InstructionTiming {
+                    index,
+                    kind: instruction.kind(),
+                    micros: elapsed_micros(started),
+                });
             }
         }
 
-        Ok(ExecutionOutcome { logs })
+        Ok(())
     }
+
+    /// Credit a reserve transfer net of its asset's fee and return a
+    /// description of the fee for the instruction log, empty when none was
+    /// taken.
+    ///
+    /// A recipient on the destination is credited in `staged`; one on another
+    /// parachain is added to its asset's total in `remote_fees` and credited
+    /// after commit.
+    fn apply_transfer_with_fee(
+        &self,
+        dest_para: u32,
+        staged: &mut ParachainState,
+        transfer: &TransferReserveAsset,
+        remote_fees: &mut BTreeMap<String, u128>,
+    ) -> Result<String, ExecutionError> {
+        let basis_points = self.transfer_fees.get(&transfer.asset).copied();
+        let fee = basis_points.map_or(0, |bps| fee_for(transfer.amount, bps));
+        if fee == 0 {
+            apply_transfer(staged, transfer)?;
+            return Ok(String::new());
+        }
+
+        apply_transfer(
+            staged,
+            &TransferReserveAsset {
+                amount: transfer.amount - fee,
+                ..transfer.clone()
+            },
+        )?;
+        let asset = &transfer.asset;
+        Ok(match &self.fee_recipient {
+            Some(recipient) if recipient.para_id == dest_para => {
+                apply_transfer(
+                    staged,
+                    &TransferReserveAsset {
+                        asset: asset.clone(),
+                        amount: fee,
+                        beneficiary: recipient.account.clone(),
+                    },
+                )?;
+                format!(" (fee {fee} {asset} to {})", recipient.account)
+            }
+            Some(recipient) => {
+                let total = remote_fees.entry(asset.clone()).or_default();
+                *total = total.saturating_add(fee);
+                format!(
+                    " (fee {fee} {asset} to {} on parachain {})",
+                    recipient.account, recipient.para_id
+                )
+            }
+            None => format!(" (fee {fee} {asset} burned)"),
+        })
+    }
+
+    /// Credit fees owed to a recipient on another parachain, each asset's
+    /// total separately.
+    ///
+    /// The message has already committed, so a failure here is logged and
+    /// the fee burned rather than failing the message.
+    fn credit_remote_fees(&self, fees: &BTreeMap<String, u128>) {
+        let Some(recipient) = &self.fee_recipient else {
+            return;
+        };
+        if fees.is_empty() {
+            return;
+        }
+        let warn = |asset: Option<&str>, err: &ExecutionError| {
+            tracing::warn!(
+                target: "xcm_lite::execution",
+                para_id = recipient.para_id,
+                account = %recipient.account,
+                asset,
+                error = %err,
+                "failed to credit transfer fees; burning them"
+            );
+        };
+        let state = self
+            .state
+            .parachain(recipient.para_id)
+            .ok_or(ExecutionError::UnknownParachain {
+                para_id: recipient.para_id,
+            })
+            .and_then(|lock| lock.write().map_err(|_| ExecutionError::StatePoisoned));
+        let mut state = match state {
+            Ok(state) => state,
+            Err(err) => return warn(None, &err),
+        };
+        for (asset, total) in fees {
+            let credit = TransferReserveAsset {
+                asset: asset.clone(),
+                amount: *total,
+                beneficiary: recipient.account.clone(),
+            };
+            if let Err(err) = apply_transfer(&mut state, &credit) {
+                warn(Some(asset), &err);
+            }
+        }
+    }
+}
+
+fn apply_transfer(
+    state: &mut ParachainState,
+    transfer: &TransferReserveAsset,
+) -> Result<(), ExecutionError> {
+    let entry = state.balance_mut(&transfer.beneficiary, &transfer.asset);
+    *entry = entry
+        .checked_add(transfer.amount)
+        .map_err(|_| ExecutionError::BalanceOverflow {
+            account: transfer.beneficiary.clone(),
+            asset: transfer.asset.clone(),
+        })?;
+    let updated = format!(
+        "Balance updated: {} {} => {}",
+        transfer.beneficiary, transfer.asset, *entry
+    );
+    state.push_log(updated);
+    Ok(())
+}
+
+/// `amount * basis_points / 10_000`, rounded down, without overflowing.
+fn fee_for(amount: u128, basis_points: u32) -> u128 {
+    let bps = u128::from(basis_points);
+    amount / 10_000 * bps + amount % 10_000 * bps / 10_000
+}
+
+fn elapsed_micros(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX)
+}
+
+/// Account on the destination that holds funds owned by parachain `para_id`.
+pub fn sovereign_account(para_id: u32) -> String {
+    format!("sibling:{para_id}")
+}
+
+/// Account name used in errors raised by the holding register.
+const HOLDING_ACCOUNT: &str = "holding";
+
+/// Debit `account` into the holding register, returning the amount actually
+/// withdrawn.
+fn apply_withdraw(
+    state: &mut ParachainState,
+    holding: &mut HashMap<String, Balance>,
+    account: &str,
+    withdraw: &WithdrawAsset,
+    policy: UnderflowPolicy,
+) -> Result<u128, ExecutionError> {
+    let balance = state.balance_mut(account, &withdraw.asset);
+    let amount = match policy {
+        UnderflowPolicy::Saturate if withdraw.amount > balance.get() => {
+            tracing::warn!(
+                target: "xcm_lite::execution",
+                account,
+                asset = %withdraw.asset,
+                requested = withdraw.amount,
+                available = balance.get(),
+                "withdrawal exceeds balance; saturating to zero"
+            );
+            balance.get()
+        }
+        _ => withdraw.amount,
+    };
+    *balance = balance
+        .checked_sub(amount)
+        .map_err(|_| ExecutionError::InsufficientBalance {
+            account: account.to_string(),
+            asset: withdraw.asset.clone(),
+        })?;
+    let updated = format!(
+        "Balance updated: {account} {} => {}",
+        withdraw.asset, *balance
+    );
+    state.push_log(updated);
+
+    let held = holding.entry(withdraw.asset.clone()).or_default();
+    *held = held
+        .checked_add(amount)
+        .map_err(|_| ExecutionError::BalanceOverflow {
+            account: HOLDING_ACCOUNT.to_string(),
+            asset: withdraw.asset.clone(),
+        })?;
+    Ok(amount)
+}
+
+fn apply_deposit(
+    state: &mut ParachainState,
+    holding: &mut HashMap<String, Balance>,
+    deposit: &DepositAsset,
+) -> Result<(), ExecutionError> {
+    let held = holding.entry(deposit.asset.clone()).or_default();
+    *held = held
+        .checked_sub(deposit.amount)
+        .map_err(|_| ExecutionError::InsufficientHolding {
+            asset: deposit.asset.clone(),
+        })?;
+    apply_transfer(
+        state,
+        &TransferReserveAsset {
+            asset: deposit.asset.clone(),
+            amount: deposit.amount,
+            beneficiary: deposit.beneficiary.clone(),
+        },
+    )
 }
 
-fn apply_transfer(state: &mut ParachainState, transfer: &TransferReserveAsset) {
-    let entry = state
-        .balances
-        .entry(transfer.beneficiary.clone())
-        .or_insert(0);
-    *entry = entry.saturating_add(transfer.amount);
-    state.logs.push(format!(
-        "Balance updated: {} => {}",
-        transfer.beneficiary, *entry
+fn apply_set_balance(state: &mut ParachainState, set: &SetBalance) {
+    *state.balance_mut(&set.account, &set.asset) = Balance(set.amount);
+    state.push_log(format!(
+        "Balance updated: {} {} => {}",
+        set.account, set.asset, set.amount
     ));
 }
 
 fn apply_transact(state: &mut ParachainState, transact: &Transact) {
-    state.logs.push(format!(
+    state.push_log(format!(
         "Transact executed: call_data_len={}, weight={}",
         transact.call_data.len(),
         transact.weight.unwrap_or_default()
@@ -106,7 +707,7 @@ fn apply_transact(state: &mut ParachainState, transact: &Transact) {
 }
 
 fn apply_query(state: &mut ParachainState, response: &QueryResponse) {
-    state.logs.push(format!(
+    state.push_log(format!(
         "QueryResponse stored: id={}, response={}",
         response.query_id, response.response
     ));
@@ -117,6 +718,641 @@ fn apply_query(state: &mut ParachainState, response: &QueryResponse) {
 pub enum ExecutionError {
     #[error("destination parachain {para_id} not registered")]
     UnknownParachain { para_id: u32 },
+    #[error("parachain {para_id} is not a trusted reserve for {asset}")]
+    ReserveNotTrusted { para_id: u32, asset: String },
+    #[error("parachain {dest} does not accept messages from parachain {sender}")]
+    SenderNotAccepted { sender: u32, dest: u32 },
+    #[error("{account} holds too little {asset} to withdraw")]
+    InsufficientBalance { account: String, asset: String },
+    #[error("holding register has too little {asset} to deposit")]
+    InsufficientHolding { asset: String },
+    #[error("{account} would hold {balance} {asset}, below the existential deposit of {limit}")]
+    BelowExistentialDeposit {
+        account: String,
+        asset: String,
+        balance: u128,
+        limit: u128,
+    },
+    #[error("crediting {asset} to {account} would overflow its balance")]
+    BalanceOverflow { account: String, asset: String },
+    #[error("unsupported instruction: {0}")]
+    UnsupportedInstruction(String),
+    #[error("execution cancelled")]
+    Cancelled,
     #[error("state lock poisoned")]
     StatePoisoned,
+    #[error("destination parachain {para_id} is offline")]
+    DestinationOffline { para_id: u32 },
+    #[error("destination parachain {para_id} unavailable after repeated failures")]
+    DestinationUnavailable { para_id: u32 },
+    /// Waited in the relay queue longer than `relay.max_queue_age_secs`.
+    #[error("expired in queue")]
+    ExpiredInQueue,
+}
+
+impl ExecutionError {
+    /// Whether the failure lies with the destination or the service rather
+    /// than the message, and so counts towards opening its circuit breaker.
+    pub fn is_destination_fault(&self) -> bool {
+        matches!(
+            self,
+            ExecutionError::UnknownParachain { .. }
+                | ExecutionError::StatePoisoned
+                | ExecutionError::DestinationOffline { .. }
+        )
+    }
+}
+
+/// A failed execution together with the logs of the instructions that
+/// completed before the failure. None of their effects are kept.
+#[derive(Debug, Error)]
+#[error("{error}")]
+pub struct ExecutionFailure {
+    #[source]
+    pub error: ExecutionError,
+    pub logs: Vec<String>,
+    /// Instructions that completed before the failure.
+    pub instructions_executed: usize,
+}
+
+impl From<ExecutionError> for ExecutionFailure {
+    fn from(error: ExecutionError) -> Self {
+        Self {
+            error,
+            logs: Vec::new(),
+            instructions_executed: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{
+            AcceptedSendersConfig, DustLimitConfig, ParachainConfig, ReserveConfig, StateConfig,
+            TransferFeeConfig,
+        },
+        domain::{
+            BalanceCondition, Comparison, ConditionalTransfer, SignatureScheme, SigningEncoding,
+            XcmVersion,
+        },
+    };
+
+    fn transfers(amounts: &[u128]) -> MessageEnvelope {
+        MessageEnvelope {
+            message_id: None,
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: amounts
+                .iter()
+                .map(|amount| {
+                    Instruction::TransferReserveAsset(TransferReserveAsset {
+                        asset: "DOT".into(),
+                        amount: *amount,
+                        beneficiary: "acct-123".into(),
+                    })
+                })
+                .collect(),
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn overflowing_credit_fails_without_partial_effects() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+        engine
+            .execute(&transfers(&[u128::MAX - 1]))
+            .expect("near-max credit");
+
+        let failure = engine.execute(&transfers(&[1, 1])).unwrap_err();
+        assert_eq!(failure.logs.len(), 1);
+        assert!(matches!(
+            failure.error,
+            ExecutionError::BalanceOverflow { ref account, ref asset }
+                if account == "acct-123" && asset == "DOT"
+        ));
+
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert_eq!(dest.balance("acct-123", "DOT"), Balance(u128::MAX - 1));
+    }
+
+    #[test]
+    fn outcomes_charge_the_instructions_that_ran() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let engine = DefaultExecutionEngine::new(state, &ExecutionConfig::default());
+        engine
+            .execute(&transfers(&[u128::MAX - 1]))
+            .expect("near-max credit");
+
+        let message = transfers(&[1, 1]);
+        let outcome = XcmOutcome::of(&message, &engine.execute(&message));
+        assert_eq!(
+            outcome,
+            XcmOutcome::Incomplete {
+                weight_used: BASE_INSTRUCTION_WEIGHT,
+                error: "crediting DOT to acct-123 would overflow its balance".into(),
+            }
+        );
+
+        // Engines that keep no logs still charge for what they ran.
+        let outcome = XcmOutcome::of(&message, &NoopExecutionEngine.execute(&message));
+        assert_eq!(
+            outcome,
+            XcmOutcome::Complete {
+                weight_used: 2 * BASE_INSTRUCTION_WEIGHT,
+            }
+        );
+
+        let mut offline = transfers(&[1]);
+        offline.dest_para = 4000;
+        let outcome = XcmOutcome::of(&offline, &engine.execute(&offline));
+        assert!(matches!(outcome, XcmOutcome::Error { .. }));
+        assert_eq!(
+            serde_json::to_value(XcmOutcome::Complete { weight_used: 7 }).expect("json"),
+            serde_json::json!({ "complete": { "weightUsed": 7 } })
+        );
+    }
+
+    #[test]
+    fn rejects_transfers_from_untrusted_reserves() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let config = ExecutionConfig {
+            reserves: vec![ReserveConfig {
+                asset: "DOT".into(),
+                para_ids: vec![1001],
+            }],
+            ..ExecutionConfig::default()
+        };
+        let engine = DefaultExecutionEngine::new(state, &config);
+
+        let err = engine.execute(&transfers(&[5])).unwrap_err();
+        assert!(matches!(
+            err.error,
+            ExecutionError::ReserveNotTrusted { para_id: 1000, ref asset } if asset == "DOT"
+        ));
+    }
+
+    #[test]
+    fn destinations_only_accept_listed_senders() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let config = ExecutionConfig {
+            accepted_senders: vec![AcceptedSendersConfig {
+                para_id: 1001,
+                senders: vec![1002],
+            }],
+            ..ExecutionConfig::default()
+        };
+        let engine = DefaultExecutionEngine::new(state.clone(), &config);
+
+        let err = engine.execute(&transfers(&[5])).unwrap_err();
+        assert!(matches!(
+            err.error,
+            ExecutionError::SenderNotAccepted {
+                sender: 1000,
+                dest: 1001
+            }
+        ));
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert!(!dest.balances.contains_key("acct-123"));
+        drop(dest);
+
+        let mut accepted = transfers(&[5]);
+        accepted.sender_para = 1002;
+        engine.execute(&accepted).expect("listed sender");
+
+        let mut unrestricted = transfers(&[5]);
+        unrestricted.dest_para = 1002;
+        engine.execute(&unrestricted).expect("no accept-list");
+    }
+
+    #[test]
+    fn cancelled_execution_leaves_no_effects() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+        let cancel = CancellationToken::new();
+        engine
+            .execute_until_cancelled(&transfers(&[1]), &cancel)
+            .expect("not cancelled");
+
+        cancel.cancel();
+        let err = engine
+            .execute_until_cancelled(&transfers(&[2]), &cancel)
+            .unwrap_err();
+        assert!(matches!(err.error, ExecutionError::Cancelled));
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert_eq!(dest.balance("acct-123", "DOT"), Balance(1));
+    }
+
+    #[test]
+    fn superuser_transacts_need_a_privileged_sender() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let transact = |origin_kind| {
+            let mut message = transfers(&[]);
+            message.instructions = vec![Instruction::Transact(Transact {
+                call_data: "0x00".into(),
+                weight: None,
+                origin_kind,
+            })];
+            message
+        };
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+        for origin_kind in [
+            None,
+            Some(OriginKind::Native),
+            Some(OriginKind::SovereignAccount),
+        ] {
+            engine
+                .execute(&transact(origin_kind))
+                .expect("unprivileged origin");
+        }
+        let err = engine
+            .execute(&transact(Some(OriginKind::Superuser)))
+            .unwrap_err();
+        assert!(matches!(
+            err.error,
+            ExecutionError::UnsupportedInstruction(ref reason) if reason.contains("parachain 1000")
+        ));
+
+        let privileged = DefaultExecutionEngine::new(state, &ExecutionConfig::default())
+            .with_privileged_senders(&[1000]);
+        privileged
+            .execute(&transact(Some(OriginKind::Superuser)))
+            .expect("privileged sender");
+    }
+
+    #[test]
+    fn rejects_transfers_that_create_or_leave_dust() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        *state
+            .parachain(1001)
+            .expect("dest")
+            .write()
+            .expect("lock")
+            .balance_mut(&sovereign_account(1000), "DOT") = Balance(20);
+        let config = ExecutionConfig {
+            dust_limits: vec![DustLimitConfig {
+                asset: "DOT".into(),
+                limit: 10,
+            }],
+            ..ExecutionConfig::default()
+        };
+        let engine = DefaultExecutionEngine::new(state.clone(), &config);
+
+        let err = engine.execute(&transfers(&[5])).unwrap_err();
+        assert!(matches!(
+            err.error,
+            ExecutionError::BelowExistentialDeposit { ref account, balance: 5, limit: 10, .. }
+                if account == "acct-123"
+        ));
+        engine.execute(&transfers(&[10])).expect("meets the limit");
+
+        let withdraw = |amount| {
+            let mut message = transfers(&[]);
+            message.instructions = vec![
+                Instruction::WithdrawAsset(WithdrawAsset {
+                    asset: "DOT".into(),
+                    amount,
+                }),
+                Instruction::DepositAsset(DepositAsset {
+                    asset: "DOT".into(),
+                    amount,
+                    beneficiary: "acct-123".into(),
+                }),
+            ];
+            message
+        };
+        let err = engine.execute(&withdraw(15)).unwrap_err();
+        assert!(matches!(
+            err.error,
+            ExecutionError::BelowExistentialDeposit { balance: 5, .. }
+        ));
+        engine.execute(&withdraw(20)).expect("emptying is allowed");
+    }
+
+    #[test]
+    fn dust_is_checked_against_the_asset_it_is_in() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        {
+            let mut dest = state.parachain(1001).expect("dest").write().expect("lock");
+            *dest.balance_mut(&sovereign_account(1000), "DOT") = Balance(20);
+            *dest.balance_mut("acct-123", "KSM") = Balance(1_000);
+        }
+        let config = ExecutionConfig {
+            dust_limits: vec![DustLimitConfig {
+                asset: "DOT".into(),
+                limit: 10,
+            }],
+            ..ExecutionConfig::default()
+        };
+        let engine = DefaultExecutionEngine::new(state.clone(), &config);
+
+        // The KSM held by the beneficiary does not lift its DOT over the limit.
+        let err = engine.execute(&transfers(&[5])).unwrap_err();
+        assert!(matches!(
+            err.error,
+            ExecutionError::BelowExistentialDeposit { ref asset, balance: 5, .. } if asset == "DOT"
+        ));
+    }
+
+    #[test]
+    fn withdraws_into_holding_then_deposits() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        *state
+            .parachain(1001)
+            .expect("dest")
+            .write()
+            .expect("lock")
+            .balance_mut(&sovereign_account(1000), "DOT") = Balance(10);
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+
+        let mut message = transfers(&[]);
+        message.instructions = vec![
+            Instruction::WithdrawAsset(WithdrawAsset {
+                asset: "DOT".into(),
+                amount: 7,
+            }),
+            Instruction::DepositAsset(DepositAsset {
+                asset: "DOT".into(),
+                amount: 7,
+                beneficiary: "acct-123".into(),
+            }),
+        ];
+        engine.execute(&message).expect("execute");
+        {
+            let dest = state.parachain(1001).expect("dest").read().expect("lock");
+            assert_eq!(dest.balance(&sovereign_account(1000), "DOT"), Balance(3));
+            assert_eq!(dest.balance("acct-123", "DOT"), Balance(7));
+        }
+
+        let err = engine.execute(&message).unwrap_err();
+        assert!(matches!(
+            err.error,
+            ExecutionError::InsufficientBalance { .. }
+        ));
+    }
+
+    #[test]
+    fn withdrawals_only_debit_their_own_asset() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        {
+            let mut dest = state.parachain(1001).expect("dest").write().expect("lock");
+            *dest.balance_mut(&sovereign_account(1000), "DOT") = Balance(10);
+            *dest.balance_mut(&sovereign_account(1000), "KSM") = Balance(10);
+        }
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+        let withdraw = |asset: &str, amount| {
+            let mut message = transfers(&[]);
+            message.instructions = vec![Instruction::WithdrawAsset(WithdrawAsset {
+                asset: asset.into(),
+                amount,
+            })];
+            message
+        };
+
+        engine.execute(&withdraw("DOT", 10)).expect("withdraw DOT");
+        let err = engine.execute(&withdraw("DOT", 1)).unwrap_err();
+        assert!(matches!(
+            err.error,
+            ExecutionError::InsufficientBalance { .. }
+        ));
+        engine.execute(&withdraw("KSM", 4)).expect("withdraw KSM");
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert_eq!(dest.balance(&sovereign_account(1000), "DOT"), Balance::ZERO);
+        assert_eq!(dest.balance(&sovereign_account(1000), "KSM"), Balance(6));
+    }
+
+    #[test]
+    fn counts_instructions_of_committed_executions() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let metrics = Arc::new(Metrics::default());
+        let engine = DefaultExecutionEngine::new(state, &ExecutionConfig::default())
+            .with_metrics(metrics.clone());
+
+        engine.execute(&transfers(&[1, 2])).expect("execute");
+        let mut failing = transfers(&[3]);
+        failing
+            .instructions
+            .push(Instruction::WithdrawAsset(WithdrawAsset {
+                asset: "DOT".into(),
+                amount: 1,
+            }));
+        engine.execute(&failing).unwrap_err();
+
+        let counted = &metrics.instructions_executed;
+        assert_eq!(counted.get("transferReserveAsset"), 2);
+        assert_eq!(counted.get("withdrawAsset"), 0);
+    }
+
+    #[test]
+    fn saturating_policy_clamps_withdrawals_to_zero() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        *state
+            .parachain(1001)
+            .expect("dest")
+            .write()
+            .expect("lock")
+            .balance_mut(&sovereign_account(1000), "DOT") = Balance(4);
+        let config = ExecutionConfig {
+            underflow_policy: UnderflowPolicy::Saturate,
+            ..ExecutionConfig::default()
+        };
+        let engine = DefaultExecutionEngine::new(state.clone(), &config);
+
+        let mut message = transfers(&[]);
+        message.instructions = vec![Instruction::WithdrawAsset(WithdrawAsset {
+            asset: "DOT".into(),
+            amount: 10,
+        })];
+        let outcome = engine.execute(&message).expect("execute");
+        assert_eq!(outcome.logs, vec!["WithdrawAsset: 4 DOT from sibling:1000"]);
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert_eq!(dest.balance(&sovereign_account(1000), "DOT"), Balance::ZERO);
+    }
+
+    #[test]
+    fn set_balance_overwrites_rather_than_credits() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+        engine.execute(&transfers(&[50])).expect("credit");
+
+        let mut message = transfers(&[]);
+        message.instructions = vec![Instruction::SetBalance(SetBalance {
+            account: "acct-123".into(),
+            asset: "DOT".into(),
+            amount: 7,
+        })];
+        engine.execute(&message).expect("set");
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert_eq!(dest.balance("acct-123", "DOT"), Balance(7));
+    }
+
+    #[test]
+    fn conditional_transfer_applies_only_while_the_condition_holds() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+        let mut message = transfers(&[]);
+        message.instructions = vec![Instruction::ConditionalTransfer(ConditionalTransfer {
+            asset: "DOT".into(),
+            amount: 8,
+            beneficiary: "acct-123".into(),
+            condition: BalanceCondition {
+                account: "acct-123".into(),
+                asset: "DOT".into(),
+                comparison: Comparison::Lt,
+                threshold: 5,
+            },
+        })];
+        // Only the condition's own asset is compared.
+        *state
+            .parachain(1001)
+            .expect("dest")
+            .write()
+            .expect("lock")
+            .balance_mut("acct-123", "KSM") = Balance(100);
+
+        let outcome = engine.execute(&message).expect("applied");
+        assert_eq!(outcome.logs, vec!["ConditionalTransfer: 8 DOT to acct-123"]);
+        let outcome = engine.execute(&message).expect("skipped");
+        assert_eq!(
+            outcome.logs,
+            vec!["ConditionalTransfer: skipped, acct-123 balance 8 is not lt 5"]
+        );
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert_eq!(dest.balance("acct-123", "DOT"), Balance(8));
+    }
+
+    #[test]
+    fn records_instruction_timings_only_when_enabled() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+        assert!(engine
+            .execute(&transfers(&[1]))
+            .expect("execute")
+            .timings
+            .is_none());
+
+        let config = ExecutionConfig {
+            record_timings: true,
+            ..ExecutionConfig::default()
+        };
+        let engine = DefaultExecutionEngine::new(state, &config);
+        let timings = engine
+            .execute(&transfers(&[1, 2]))
+            .expect("execute")
+            .timings
+            .expect("timings");
+        let kinds: Vec<_> = timings
+            .instructions
+            .iter()
+            .map(|t| (t.index, t.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![(0, "transferReserveAsset"), (1, "transferReserveAsset")]
+        );
+        let applied: u64 = timings.instructions.iter().map(|t| t.micros).sum();
+        assert!(timings.total_micros >= applied);
+    }
+
+    #[test]
+    fn transfer_fees_are_split_off_to_the_recipient() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let mut config = ExecutionConfig {
+            transfer_fees: vec![TransferFeeConfig {
+                asset: "DOT".into(),
+                basis_points: 250,
+            }],
+            fee_recipient: Some(FeeRecipientConfig {
+                para_id: 1001,
+                account: "treasury".into(),
+            }),
+            ..ExecutionConfig::default()
+        };
+        let engine = DefaultExecutionEngine::new(state.clone(), &config);
+        let outcome = engine.execute(&transfers(&[1000])).expect("execute");
+        assert_eq!(
+            outcome.logs,
+            vec!["TransferReserveAsset: 1000 DOT to acct-123 (fee 25 DOT to treasury)"]
+        );
+
+        config.fee_recipient = Some(FeeRecipientConfig {
+            para_id: 1002,
+            account: "treasury".into(),
+        });
+        let engine = DefaultExecutionEngine::new(state.clone(), &config);
+        engine.execute(&transfers(&[1000])).expect("execute");
+
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert_eq!(dest.balance("acct-123", "DOT"), Balance(1950));
+        assert_eq!(dest.balance("treasury", "DOT"), Balance(25));
+        let remote = state.parachain(1002).expect("para").read().expect("lock");
+        assert_eq!(remote.balance("treasury", "DOT"), Balance(25));
+        drop((dest, remote));
+
+        let mut mixed = transfers(&[1000, 1000]);
+        if let Instruction::TransferReserveAsset(data) = &mut mixed.instructions[1] {
+            data.asset = "KSM".into();
+        }
+        config.transfer_fees.push(TransferFeeConfig {
+            asset: "KSM".into(),
+            basis_points: 100,
+        });
+        let engine = DefaultExecutionEngine::new(state.clone(), &config);
+        engine.execute(&mixed).expect("execute");
+        let remote = state.parachain(1002).expect("para").read().expect("lock");
+        let credits: Vec<_> = remote.logs.iter().rev().take(2).rev().collect();
+        assert_eq!(
+            credits,
+            [
+                "Balance updated: treasury DOT => 50",
+                "Balance updated: treasury KSM => 10"
+            ]
+        );
+    }
+
+    #[test]
+    fn poisoned_parachain_does_not_block_others() {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let poisoned = state.parachain(1002).expect("para").clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoned.write().expect("lock");
+            panic!("poison parachain 1002");
+        })
+        .join();
+        let engine = DefaultExecutionEngine::new(state.clone(), &ExecutionConfig::default());
+
+        engine
+            .execute(&transfers(&[5]))
+            .expect("unaffected parachain");
+        let mut message = transfers(&[5]);
+        message.dest_para = 1002;
+        let err = engine.execute(&message).unwrap_err();
+        assert!(matches!(err.error, ExecutionError::StatePoisoned));
+    }
 }
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::sovereign_account;
+use crate::domain::{Instruction, MessageEnvelope};
+
+/// Balance requirement for one account and asset on the destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceRequirement {
+    /// Smallest starting balance for which no debit in the message fails.
+    #[serde(with = "crate::domain::message::amount_format")]
+    pub required: u128,
+    #[serde(with = "crate::domain::message::amount_format")]
+    pub credited: u128,
+    #[serde(with = "crate::domain::message::amount_format")]
+    pub debited: u128,
+    /// Balance the message has made available to later debits.
+    #[serde(skip)]
+    surplus: u128,
+    /// Set once a `setBalance` fixes the balance, after which the starting
+    /// balance no longer matters.
+    #[serde(skip)]
+    overwritten: bool,
+}
+
+impl BalanceRequirement {
+    fn credit(&mut self, amount: u128) {
+        self.credited = self.credited.saturating_add(amount);
+        self.surplus = self.surplus.saturating_add(amount);
+    }
+
+    fn debit(&mut self, amount: u128) {
+        self.debited = self.debited.saturating_add(amount);
+        match self.surplus.checked_sub(amount) {
+            Some(left) => self.surplus = left,
+            None => {
+                if !self.overwritten {
+                    self.required = self.required.saturating_add(amount - self.surplus);
+                }
+                self.surplus = 0;
+            }
+        }
+    }
+
+    fn overwrite(&mut self, amount: u128) {
+        self.surplus = amount;
+        self.overwritten = true;
+    }
+}
+
+/// Balances a message needs on its destination, keyed by account and asset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct ExecutionPlan(pub BTreeMap<String, BTreeMap<String, BalanceRequirement>>);
+
+impl ExecutionPlan {
+    fn entry(&mut self, account: &str, asset: &str) -> &mut BalanceRequirement {
+        self.0
+            .entry(account.to_string())
+            .or_default()
+            .entry(asset.to_string())
+            .or_default()
+    }
+}
+
+/// Walk a message's instructions and work out the balances it needs,
+/// without touching any state.
+///
+/// The holding register is internal to one execution, so it never appears
+/// in the plan; `withdrawAsset` shows up as a debit of the sender's
+/// sovereign account and `depositAsset` as a credit of its beneficiary.
+pub fn plan(message: &MessageEnvelope) -> ExecutionPlan {
+    let mut plan = ExecutionPlan::default();
+    for instruction in &message.instructions {
+        match instruction {
+            Instruction::TransferReserveAsset(data) => {
+                plan.entry(&data.beneficiary, &data.asset)
+                    .credit(data.amount);
+            }
+            Instruction::WithdrawAsset(data) => {
+                plan.entry(&sovereign_account(message.sender_para), &data.asset)
+                    .debit(data.amount);
+            }
+            Instruction::DepositAsset(data) => {
+                plan.entry(&data.beneficiary, &data.asset)
+                    .credit(data.amount);
+            }
+            Instruction::SetBalance(data) => {
+                plan.entry(&data.account, &data.asset)
+                    .overwrite(data.amount);
+            }
+            // A conditional credit may not happen, so later debits cannot
+            // rely on it.
+            Instruction::ConditionalTransfer(_)
+            | Instruction::Transact(_)
+            | Instruction::QueryResponse(_) => {}
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        DepositAsset, SignatureScheme, SigningEncoding, TransferReserveAsset, WithdrawAsset,
+        XcmVersion,
+    };
+
+    #[test]
+    fn requires_only_the_uncovered_part_of_each_debit() {
+        let withdraw = |amount| {
+            Instruction::WithdrawAsset(WithdrawAsset {
+                asset: "DOT".into(),
+                amount,
+            })
+        };
+        let message = MessageEnvelope {
+            message_id: None,
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: vec![
+                withdraw(10),
+                Instruction::DepositAsset(DepositAsset {
+                    asset: "DOT".into(),
+                    amount: 10,
+                    beneficiary: "sibling:1000".into(),
+                }),
+                withdraw(25),
+                Instruction::TransferReserveAsset(TransferReserveAsset {
+                    asset: "DOT".into(),
+                    amount: 5,
+                    beneficiary: "acct-123".into(),
+                }),
+            ],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        };
+
+        let plan = plan(&message);
+        let sovereign = plan.0["sibling:1000"]["DOT"];
+        assert_eq!(sovereign.required, 25);
+        assert_eq!(sovereign.credited, 10);
+        assert_eq!(sovereign.debited, 35);
+        assert_eq!(plan.0["acct-123"]["DOT"].required, 0);
+        assert_eq!(plan.0["acct-123"]["DOT"].credited, 5);
+    }
+}
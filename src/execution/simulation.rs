@@ -0,0 +1,236 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{atomic::Ordering, Arc, Mutex},
+};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::{DefaultExecutionEngine, ExecutionEngine, ExecutionError, ExecutionOutcome};
+use crate::{
+    config::ExecutionConfig, domain::MessageEnvelope, metrics::Metrics, state::ServiceState,
+};
+
+/// Result of simulating a message against a snapshot of current state.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    pub outcome: ExecutionOutcome,
+    /// Whether the outcome was served from the cache.
+    pub cached: bool,
+}
+
+/// Dry-run executor that never mutates shared state.
+///
+/// Each simulation runs the default engine over a private copy of the
+/// parachain states. Successful outcomes can be cached by payload hash; a
+/// cached outcome reflects the state at the time it was first computed.
+pub struct Simulator {
+    state: ServiceState,
+    config: ExecutionConfig,
+    cache: Option<OutcomeCache>,
+    metrics: Arc<Metrics>,
+    privileged_senders: Vec<u32>,
+}
+
+impl Simulator {
+    /// Create a simulator; a `simulation_cache_size` of zero disables caching.
+    pub fn new(state: ServiceState, config: &ExecutionConfig, metrics: Arc<Metrics>) -> Self {
+        let cache_size = config.simulation_cache_size;
+        Self {
+            state,
+            config: config.clone(),
+            cache: (cache_size > 0).then(|| OutcomeCache::new(cache_size)),
+            metrics,
+            privileged_senders: Vec::new(),
+        }
+    }
+
+    /// Simulate with the same `superuser` senders as the relay's engine.
+    pub fn with_privileged_senders(mut self, senders: &[u32]) -> Self {
+        self.privileged_senders = senders.to_vec();
+        self
+    }
+
+    pub fn simulate(&self, message: &MessageEnvelope) -> Result<SimulationResult, ExecutionError> {
+        let key = self.cache.as_ref().map(|_| payload_hash(message));
+
+        if let (Some(cache), Some(key)) = (&self.cache, key) {
+            if let Some(outcome) = cache.get(&key) {
+                self.metrics
+                    .simulation_cache_hits
+                    .fetch_add(1, Ordering::Relaxed);
+                return Ok(SimulationResult {
+                    outcome,
+                    cached: true,
+                });
+            }
+            self.metrics
+                .simulation_cache_misses
+                .fetch_add(1, Ordering::Relaxed);
+        }
+
+        if self
+            .state
+            .parachain(message.dest_para)
+            .is_some_and(|lock| lock.is_poisoned())
+        {
+            return Err(ExecutionError::StatePoisoned);
+        }
+        let outcome = DefaultExecutionEngine::new(self.state.snapshot(), &self.config)
+            .with_privileged_senders(&self.privileged_senders)
+            .execute(message)
+            .map_err(|failure| failure.error)?;
+
+        if let (Some(cache), Some(key)) = (&self.cache, key) {
+            cache.insert(key, outcome.clone());
+        }
+
+        Ok(SimulationResult {
+            outcome,
+            cached: false,
+        })
+    }
+}
+
+fn payload_hash(message: &MessageEnvelope) -> [u8; 32] {
+    Sha256::digest(message.signing_bytes()).into()
+}
+
+/// Fixed-size least-recently-used cache of execution outcomes.
+struct OutcomeCache {
+    capacity: usize,
+    inner: Mutex<CacheEntries>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    outcomes: HashMap<[u8; 32], ExecutionOutcome>,
+    recency: VecDeque<[u8; 32]>,
+}
+
+impl OutcomeCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(CacheEntries::default()),
+        }
+    }
+
+    fn get(&self, key: &[u8; 32]) -> Option<ExecutionOutcome> {
+        let mut entries = self.inner.lock().ok()?;
+        let outcome = entries.outcomes.get(key).cloned()?;
+        entries.touch(key);
+        Some(outcome)
+    }
+
+    fn insert(&self, key: [u8; 32], outcome: ExecutionOutcome) {
+        let Ok(mut entries) = self.inner.lock() else {
+            return;
+        };
+        if entries.outcomes.insert(key, outcome).is_some() {
+            entries.touch(&key);
+            return;
+        }
+        entries.recency.push_back(key);
+        while entries.outcomes.len() > self.capacity {
+            match entries.recency.pop_front() {
+                Some(oldest) => {
+                    entries.outcomes.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl CacheEntries {
+    fn touch(&mut self, key: &[u8; 32]) {
+        if let Some(position) = self.recency.iter().position(|entry| entry == key) {
+            if let Some(entry) = self.recency.remove(position) {
+                self.recency.push_back(entry);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ParachainConfig, StateConfig},
+        domain::{Instruction, SignatureScheme, SigningEncoding, TransferReserveAsset, XcmVersion},
+    };
+
+    fn transfer(amount: u128) -> MessageEnvelope {
+        MessageEnvelope {
+            message_id: None,
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: vec![Instruction::TransferReserveAsset(TransferReserveAsset {
+                asset: "DOT".into(),
+                amount,
+                beneficiary: "acct-123".into(),
+            })],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        }
+    }
+
+    fn simulator(cache_size: usize) -> (Simulator, ServiceState, Arc<Metrics>) {
+        let state = ServiceState::initialize(&ParachainConfig::default(), &StateConfig::default())
+            .expect("state");
+        let metrics = Arc::new(Metrics::default());
+        (
+            Simulator::new(
+                state.clone(),
+                &ExecutionConfig {
+                    simulation_cache_size: cache_size,
+                    ..ExecutionConfig::default()
+                },
+                metrics.clone(),
+            ),
+            state,
+            metrics,
+        )
+    }
+
+    #[test]
+    fn simulation_does_not_mutate_state() {
+        let (simulator, state, _) = simulator(0);
+        simulator.simulate(&transfer(10)).expect("simulate");
+
+        let dest = state.parachain(1001).expect("dest").read().expect("lock");
+        assert!(dest.balances.is_empty());
+    }
+
+    #[test]
+    fn repeated_payloads_hit_the_cache() {
+        let (simulator, _, metrics) = simulator(4);
+
+        assert!(!simulator.simulate(&transfer(10)).expect("first").cached);
+        assert!(simulator.simulate(&transfer(10)).expect("second").cached);
+        assert!(!simulator.simulate(&transfer(11)).expect("third").cached);
+
+        assert_eq!(metrics.simulation_cache_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(metrics.simulation_cache_misses.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_outcome() {
+        let (simulator, _, _) = simulator(2);
+        simulator.simulate(&transfer(1)).expect("1");
+        simulator.simulate(&transfer(2)).expect("2");
+        simulator.simulate(&transfer(1)).expect("1 again");
+        simulator.simulate(&transfer(3)).expect("3");
+
+        assert!(simulator.simulate(&transfer(1)).expect("1").cached);
+        assert!(!simulator.simulate(&transfer(2)).expect("2").cached);
+    }
+}
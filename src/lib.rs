@@ -1,18 +1,31 @@
+pub mod api;
+pub mod audit;
+pub mod clock;
 pub mod config;
 pub mod crypto;
 pub mod domain;
 pub mod execution;
+pub mod metrics;
 pub mod processor;
+pub mod reload;
 pub mod state;
 
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
+use api::ApiContext;
+use audit::AuditLog;
 use config::AppConfig;
-use crypto::KeyRegistry;
-use execution::DefaultExecutionEngine;
-use processor::{run_relay_loop, MessageProcessor};
+use crypto::{KeyRegistry, RemoteSigner};
+use execution::{simulation::Simulator, DefaultExecutionEngine, ExecutionEngine};
+use metrics::Metrics;
+use processor::{run_relay_loop, MessageProcessor, PauseSwitch, RelayOptions};
+use reload::ConfigReloader;
 use state::ServiceState;
 use thiserror::Error;
+use tokio::net::TcpListener;
 
 #[derive(Debug, Error)]
 pub enum ServiceError {
@@ -22,18 +35,46 @@ pub enum ServiceError {
     State(#[from] state::StateInitError),
     #[error(transparent)]
     Crypto(#[from] crypto::CryptoError),
+    #[error("invalid audit signing key: {0}")]
+    AuditKey(#[source] crypto::KeypairBuildError),
+    #[error("server I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
-pub async fn run() -> Result<(), ServiceError> {
-    let config = AppConfig::load()?;
-    let state = ServiceState::initialize(&config.parachains)?;
-    let key_registry = KeyRegistry::from_config(&config.parachains)?;
-    let (_processor, relay_rx) = MessageProcessor::new(
-        state.clone(),
-        key_registry.clone(),
-        &config.parachains.xcm_version,
+/// Start the relay and HTTP server with an already-loaded configuration,
+/// applying reloads of its hot-reloadable settings through `reloader`.
+pub async fn run(config: AppConfig, reloader: ConfigReloader) -> Result<(), ServiceError> {
+    let clock = clock::from_config(&config);
+    let audit = AuditLog::from_config(&config.audit).map_err(ServiceError::AuditKey)?;
+    let state = ServiceState::initialize(&config.parachains, &config.state)?
+        .with_audit(audit)
+        .with_clock(clock.clone());
+    let mut key_registry = KeyRegistry::from_config(&config.parachains)?
+        .with_clock(clock.clone())
+        .with_jwks(&config.parachains)
+        .await;
+    if let Some(remote) = RemoteSigner::from_config(&config.parachains)? {
+        key_registry = key_registry.with_signer(Arc::new(remote));
+    }
+    if config.server.startup_selftest {
+        // A remote signer blocks while it waits for each signature.
+        let registry = key_registry.clone();
+        let checked = tokio::task::spawn_blocking(move || registry.self_test())
+            .await
+            .expect("the key self-test does not panic")?;
+        tracing::info!(target: "xcm_lite", keys = checked, "key self-test passed");
+    }
+    let (processor, relay_rx) = MessageProcessor::new(state.clone(), key_registry.clone(), &config);
+    let metrics = Arc::new(Metrics::default());
+    let execution_engine: Arc<dyn ExecutionEngine> = Arc::new(
+        DefaultExecutionEngine::new(state.clone(), &config.execution)
+            .with_privileged_senders(&config.validation.privileged_senders)
+            .with_metrics(metrics.clone()),
+    );
+    let simulator = Arc::new(
+        Simulator::new(state.clone(), &config.execution, metrics.clone())
+            .with_privileged_senders(&config.validation.privileged_senders),
     );
-    let execution_engine = Arc::new(DefaultExecutionEngine::new(state.clone()));
 
     tracing::info!(
         target: "xcm_lite",
@@ -45,13 +86,44 @@ pub async fn run() -> Result<(), ServiceError> {
         "configuration and state initialised"
     );
 
-    tokio::spawn(run_relay_loop(
-        state.clone(),
-        execution_engine.clone(),
-        relay_rx,
-    ));
+    let ready = Arc::new(AtomicBool::new(false));
+    let pause = PauseSwitch::default();
+    if config.server.read_only {
+        tracing::info!(target: "xcm_lite", "read-only mode: relay loop disabled");
+        ready.store(true, Ordering::Release);
+    } else {
+        let relay_ready = ready.clone();
+        let relay_state = state.clone();
+        let relay_options = RelayOptions {
+            webhook: reloader.webhook(),
+            engines: execution::engines_by_destination(&config.execution, &execution_engine),
+            ..RelayOptions::from_config(&config, metrics.clone(), pause.clone(), clock)
+        };
+        tokio::spawn(async move {
+            relay_ready.store(true, Ordering::Release);
+            run_relay_loop(relay_state, execution_engine, relay_rx, relay_options).await;
+        });
+    }
+
+    let listener = TcpListener::bind((config.server.host.as_str(), config.server.port)).await?;
+    tracing::info!(
+        target: "xcm_lite",
+        addr = %listener.local_addr()?,
+        "http server listening"
+    );
 
-    // TODO: continue wiring subsystems before starting HTTP server
+    let context = ApiContext {
+        state,
+        keys: key_registry,
+        processor: Arc::new(processor),
+        simulator,
+        metrics,
+        config: Arc::new(config),
+        ready,
+        pause,
+        reloader,
+    };
+    axum::serve(listener, api::router(context)).await?;
 
     Ok(())
 }
@@ -1,10 +1,54 @@
-use xcm_lite::ServiceError;
+use std::path::PathBuf;
+
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter};
+use xcm_lite::{
+    config::{AppConfig, ConfigError, ConfigSource},
+    reload::ConfigReloader,
+    ServiceError,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), ServiceError> {
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+    let (filter, log_filter) = reload::Layer::new(EnvFilter::from_default_env());
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
         .init();
 
-    xcm_lite::run().await
+    let mut dump_config = false;
+    let mut config_path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dump-config" => dump_config = true,
+            "--config" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| ConfigError::Invalid("--config requires a file path".into()))?;
+                config_path = Some(PathBuf::from(path));
+            }
+            other => match other.strip_prefix("--config=") {
+                Some(path) => config_path = Some(PathBuf::from(path)),
+                None => {
+                    return Err(
+                        ConfigError::Invalid(format!("unrecognised argument {other}")).into(),
+                    )
+                }
+            },
+        }
+    }
+
+    let source = config_path.map_or(ConfigSource::Default, ConfigSource::File);
+    let config = AppConfig::load_source(&source)?;
+
+    if dump_config {
+        // Redacted like `/config`, so pasted output never carries keys.
+        let rendered = serde_json::to_string_pretty(&config.redacted())
+            .expect("config serialization is infallible");
+        println!("{rendered}");
+        return Ok(());
+    }
+
+    let reloader = ConfigReloader::new(source, &config).with_log_filter(log_filter);
+    xcm_lite::run(config, reloader).await
 }
@@ -0,0 +1,187 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+/// Upper bounds, in seconds, of the relay latency histogram buckets.
+const RELAY_DURATION_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Process-wide counters rendered in the Prometheus text format at `/metrics`.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub simulation_cache_hits: AtomicU64,
+    pub simulation_cache_misses: AtomicU64,
+    /// Submit-to-terminal-status latency of relayed messages.
+    pub relay_duration: Histogram,
+    /// Instructions applied by committed executions, by instruction kind.
+    pub instructions_executed: LabeledCounter,
+}
+
+impl Metrics {
+    /// Render every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        write_counter(
+            &mut out,
+            "xcm_lite_simulation_cache_hits_total",
+            "Simulations answered from the outcome cache.",
+            self.simulation_cache_hits.load(Ordering::Relaxed),
+        );
+        write_counter(
+            &mut out,
+            "xcm_lite_simulation_cache_misses_total",
+            "Simulations that required executing the message.",
+            self.simulation_cache_misses.load(Ordering::Relaxed),
+        );
+        self.relay_duration.render(
+            &mut out,
+            "xcm_lite_relay_duration_seconds",
+            "Time from submission to a terminal message status.",
+        );
+        self.instructions_executed.render(
+            &mut out,
+            "xcm_lite_instructions_executed_total",
+            "Instructions applied by the execution engine.",
+            "type",
+        );
+        out
+    }
+}
+
+/// Fixed-bucket histogram of durations.
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Per-bucket (non-cumulative) counts; the last slot is `+Inf`.
+    buckets: Vec<AtomicU64>,
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new(RELAY_DURATION_BUCKETS)
+    }
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::default()).collect(),
+            sum_micros: AtomicU64::default(),
+            count: AtomicU64::default(),
+        }
+    }
+
+    pub fn observe(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let index = self
+            .bounds
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        let mut cumulative = 0;
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        cumulative += self.buckets[self.bounds.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {cumulative}");
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{name}_sum {sum}");
+        let _ = writeln!(out, "{name}_count {}", self.count());
+    }
+}
+
+/// Counter partitioned by a single label.
+#[derive(Debug, Default)]
+pub struct LabeledCounter {
+    values: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl LabeledCounter {
+    pub fn increment(&self, label: &'static str) {
+        *self
+            .values
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(label)
+            .or_default() += 1;
+    }
+
+    pub fn get(&self, label: &str) -> u64 {
+        self.values
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(label)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str, label: &str) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} counter");
+        let values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+        for (value, count) in values.iter() {
+            let _ = writeln!(out, "{name}{{{label}=\"{value}\"}} {count}");
+        }
+    }
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} counter");
+    let _ = writeln!(out, "{name} {value}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_renders_cumulative_buckets() {
+        let metrics = Metrics::default();
+        metrics.relay_duration.observe(Duration::from_millis(3));
+        metrics.relay_duration.observe(Duration::from_millis(70));
+        metrics.relay_duration.observe(Duration::from_secs(9));
+
+        let text = metrics.render();
+        assert!(text.contains("xcm_lite_relay_duration_seconds_bucket{le=\"0.001\"} 0"));
+        assert!(text.contains("xcm_lite_relay_duration_seconds_bucket{le=\"0.005\"} 1"));
+        assert!(text.contains("xcm_lite_relay_duration_seconds_bucket{le=\"0.1\"} 2"));
+        assert!(text.contains("xcm_lite_relay_duration_seconds_bucket{le=\"5\"} 2"));
+        assert!(text.contains("xcm_lite_relay_duration_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(text.contains("xcm_lite_relay_duration_seconds_count 3"));
+    }
+
+    #[test]
+    fn labeled_counter_renders_one_series_per_label() {
+        let metrics = Metrics::default();
+        metrics.instructions_executed.increment("withdrawAsset");
+        metrics.instructions_executed.increment("depositAsset");
+        metrics.instructions_executed.increment("withdrawAsset");
+
+        let text = metrics.render();
+        assert!(text.contains("xcm_lite_instructions_executed_total{type=\"withdrawAsset\"} 2"));
+        assert!(text.contains("xcm_lite_instructions_executed_total{type=\"depositAsset\"} 1"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{clock::SharedClock, config::RelayConfig};
+
+/// Stops executing messages for a destination after repeated failures.
+///
+/// Once `threshold` consecutive executions to a destination fail through
+/// its own fault, such as being offline, the breaker opens and messages to
+/// it fail without being executed until `cooldown` has passed. The next
+/// message after that is attempted; a success closes the breaker and
+/// another failure reopens it.
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    destinations: Mutex<HashMap<u32, DestinationHealth>>,
+    clock: SharedClock,
+}
+
+#[derive(Default)]
+struct DestinationHealth {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration, clock: SharedClock) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            destinations: Mutex::default(),
+            clock,
+        }
+    }
+
+    /// Build a breaker when `relay.breaker_threshold` is configured.
+    pub fn from_config(config: &RelayConfig, clock: SharedClock) -> Option<Self> {
+        config.breaker_threshold.map(|threshold| {
+            Self::new(
+                threshold,
+                Duration::from_millis(config.breaker_cooldown_ms),
+                clock,
+            )
+        })
+    }
+
+    /// Whether messages to `dest_para` may be executed right now.
+    pub fn allows(&self, dest_para: u32) -> bool {
+        self.allows_at(dest_para, self.clock.now())
+    }
+
+    /// Record the outcome of an execution to `dest_para`.
+    pub fn record(&self, dest_para: u32, succeeded: bool) {
+        self.record_at(dest_para, succeeded, self.clock.now());
+    }
+
+    fn allows_at(&self, dest_para: u32, now: Instant) -> bool {
+        // Entries are updated whole, so a poisoned map is still consistent.
+        let destinations = self
+            .destinations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        destinations
+            .get(&dest_para)
+            .and_then(|health| health.open_until)
+            .is_none_or(|until| now >= until)
+    }
+
+    fn record_at(&self, dest_para: u32, succeeded: bool, now: Instant) {
+        let mut destinations = self
+            .destinations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let health = destinations.entry(dest_para).or_default();
+        if succeeded {
+            *health = DestinationHealth::default();
+            return;
+        }
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+        if health.consecutive_failures >= self.threshold {
+            health.open_until = Some(now + self.cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_consecutive_failures_until_the_cooldown_passes() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(30), crate::clock::system());
+        let start = Instant::now();
+
+        breaker.record_at(1001, false, start);
+        breaker.record_at(1001, true, start);
+        breaker.record_at(1001, false, start);
+        assert!(breaker.allows_at(1001, start));
+
+        breaker.record_at(1001, false, start);
+        assert!(!breaker.allows_at(1001, start));
+        assert!(breaker.allows_at(1002, start));
+
+        let later = start + Duration::from_secs(30);
+        assert!(breaker.allows_at(1001, later));
+        breaker.record_at(1001, false, later);
+        assert!(!breaker.allows_at(1001, later));
+        breaker.record_at(1001, true, later);
+        assert!(breaker.allows_at(1001, later));
+    }
+}
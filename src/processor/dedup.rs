@@ -0,0 +1,108 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{clock::SharedClock, config::ProcessorConfig, domain::MessageEnvelope};
+
+/// SHA-256 of an envelope's signing bytes with its `messageId` cleared, so
+/// a retry under a fresh id still hashes the same.
+pub fn content_hash(envelope: &MessageEnvelope) -> [u8; 32] {
+    let anonymous = MessageEnvelope {
+        message_id: None,
+        ..envelope.clone()
+    };
+    Sha256::digest(anonymous.signing_bytes()).into()
+}
+
+/// Id given to an envelope without one under `MessageIdMode::ContentHash`:
+/// the hex SHA-256 of the bytes its sender signed.
+pub fn content_id(raw_payload: &[u8]) -> String {
+    hex::encode(Sha256::digest(raw_payload))
+}
+
+/// Content hashes of recently accepted submissions.
+///
+/// A submission whose content matches one accepted less than `window` ago
+/// is refused, which catches clients that retry without reusing their id.
+pub struct RecentContent {
+    window: Duration,
+    seen: Mutex<SeenHashes>,
+    clock: SharedClock,
+}
+
+#[derive(Default)]
+struct SeenHashes {
+    ids: HashMap<[u8; 32], String>,
+    /// Claim times in acceptance order, for pruning from the front.
+    order: VecDeque<([u8; 32], Instant)>,
+}
+
+impl RecentContent {
+    pub fn new(window: Duration, clock: SharedClock) -> Self {
+        Self {
+            window,
+            seen: Mutex::default(),
+            clock,
+        }
+    }
+
+    /// Build the set when `processor.dedup_window_ms` is non-zero.
+    pub fn from_config(config: &ProcessorConfig, clock: SharedClock) -> Option<Self> {
+        (config.dedup_window_ms > 0)
+            .then(|| Self::new(Duration::from_millis(config.dedup_window_ms), clock))
+    }
+
+    /// Record `hash` for `message_id`, or return the id that already holds
+    /// it within the window.
+    pub fn claim(&self, hash: [u8; 32], message_id: &str) -> Result<(), String> {
+        let now = self.clock.now();
+        // Both collections are updated together, so a poisoned set is
+        // still consistent.
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        while let Some(&(oldest, claimed_at)) = seen.order.front() {
+            if now.duration_since(claimed_at) < self.window {
+                break;
+            }
+            seen.order.pop_front();
+            seen.ids.remove(&oldest);
+        }
+        if let Some(existing) = seen.ids.get(&hash) {
+            return Err(existing.clone());
+        }
+        seen.ids.insert(hash, message_id.to_string());
+        seen.order.push_back((hash, now));
+        Ok(())
+    }
+
+    /// Forget a claim whose submission was not accepted after all.
+    pub fn release(&self, hash: &[u8; 32]) {
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        if seen.ids.remove(hash).is_some() {
+            seen.order.retain(|(claimed, _)| claimed != hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::clock::TestClock;
+
+    #[test]
+    fn claims_expire_after_the_window() {
+        let clock = Arc::new(TestClock::new());
+        let recent = RecentContent::new(Duration::from_secs(10), clock.clone());
+
+        recent.claim([1; 32], "msg-1").expect("first claim");
+        assert_eq!(recent.claim([1; 32], "msg-2"), Err("msg-1".to_string()));
+
+        clock.advance(Duration::from_secs(10));
+        recent.claim([1; 32], "msg-2").expect("window elapsed");
+    }
+}
@@ -1,17 +1,43 @@
-use std::sync::Arc;
+pub mod breaker;
+pub mod dedup;
+pub mod nonce;
+pub mod routing;
+pub mod webhook;
 
-use tokio::sync::mpsc::{self, Receiver, Sender};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLockWriteGuard},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{
+    mpsc::{self, Receiver, Sender},
+    watch, Semaphore,
+};
 use uuid::Uuid;
 
+use self::{
+    breaker::CircuitBreaker,
+    dedup::{content_hash, content_id, RecentContent},
+    nonce::NonceTracker,
+    routing::{ChannelTopology, ConsensusBoundary},
+    webhook::SharedWebhook,
+};
 use crate::{
-    crypto::KeyRegistry,
+    clock::SharedClock,
+    config::{AppConfig, MessageIdMode, RelayOrdering, ValidationConfig},
+    crypto::{KeyRegistry, SignedEnvelope},
     domain::{MessageEnvelope, MessageValidationError},
-    execution::ExecutionEngine,
-    state::{MessageRecord, MessageStatus, ServiceState},
+    execution::{ExecutionEngine, ExecutionError, ExecutionFailure, XcmOutcome},
+    metrics::Metrics,
+    state::{
+        Hop, HopStatus, MessageRecord, MessageStatus, MessageStore, MessageStoreError, ServiceState,
+    },
 };
 
-/// Maximum number of hops supported by the relay.
-const MAX_HOPS: usize = 3;
+/// Messages buffered per destination worker under
+/// [`RelayOrdering::SequentialPerDestination`].
+const LANE_CAPACITY: usize = 128;
 
 /// Message stored in the processing queue.
 #[derive(Debug)]
@@ -19,6 +45,14 @@ pub struct QueuedMessage {
     pub message_id: String,
     pub envelope: MessageEnvelope,
     pub raw_payload: Vec<u8>,
+    /// When the message was accepted, for end-to-end latency.
+    pub submitted_at: Instant,
+    /// When the message entered the queue on the service clock, checked
+    /// against `relay.max_queue_age_secs` when it is taken off.
+    pub enqueued_at: Instant,
+    /// Execute even if deferral is configured, because
+    /// `POST /status/:id/execute` released it.
+    pub release_deferred: bool,
 }
 
 /// Coordinates message validation, signature checking, and routing through the simulated relay.
@@ -26,6 +60,22 @@ pub struct MessageProcessor {
     state: ServiceState,
     keys: KeyRegistry,
     configured_version: String,
+    /// Destinations whose `xcm_version` override differs from the default.
+    version_overrides: HashMap<u32, String>,
+    validation: ValidationConfig,
+    topology: ChannelTopology,
+    consensus: ConsensusBoundary,
+    message_ids: MessageIdMode,
+    /// Per-destination limit on in-flight messages.
+    capacity: HashMap<u32, u64>,
+    max_inflight_per_sender: Option<u64>,
+    verify_parallelism: usize,
+    /// Recently accepted content, when `processor.dedup_window_ms` is set.
+    recent_content: Option<RecentContent>,
+    /// Expected nonce per sender, when `processor.enforce_nonces` is set.
+    nonces: Option<NonceTracker>,
+    /// Set only in `dev-mode` builds with `processor.dev_skip_signature`.
+    skip_signatures: bool,
     sender: Sender<QueuedMessage>,
 }
 
@@ -33,59 +83,455 @@ impl MessageProcessor {
     pub fn new(
         state: ServiceState,
         keys: KeyRegistry,
-        configured_version: impl Into<String>,
+        config: &AppConfig,
     ) -> (Self, Receiver<QueuedMessage>) {
         let (sender, receiver) = mpsc::channel(128);
+        let skip_signatures = cfg!(feature = "dev-mode") && config.processor.dev_skip_signature;
+        if skip_signatures {
+            tracing::warn!(
+                "processor.dev_skip_signature is enabled: submissions are accepted WITHOUT \
+                 signature verification; never run this build in production"
+            );
+        }
+        let recent_content = RecentContent::from_config(&config.processor, state.clock.clone());
         (
             Self {
                 state,
                 keys,
-                configured_version: configured_version.into(),
+                configured_version: config.parachains.xcm_version.clone(),
+                version_overrides: config
+                    .parachains
+                    .overrides
+                    .iter()
+                    .filter_map(|entry| Some((entry.para_id, entry.xcm_version?.to_string())))
+                    .collect(),
+                validation: config.validation.clone(),
+                topology: ChannelTopology::from_config(&config.parachains),
+                consensus: ConsensusBoundary::from_config(config),
+                message_ids: config.processor.message_ids,
+                capacity: config
+                    .parachains
+                    .overrides
+                    .iter()
+                    .filter_map(|entry| Some((entry.para_id, entry.max_pending_messages?)))
+                    .collect(),
+                max_inflight_per_sender: config.processor.max_inflight_per_sender,
+                verify_parallelism: config.processor.verify_parallelism,
+                recent_content,
+                nonces: NonceTracker::from_config(&config.processor),
+                skip_signatures,
                 sender,
             },
             receiver,
         )
     }
 
+    /// Whether submissions bypass signature verification (dev mode only).
+    pub fn skips_signatures(&self) -> bool {
+        self.skip_signatures
+    }
+
     /// Validate message payload, ensure the signature is correct, and enqueue for relay.
+    ///
+    /// Returns the id under which the message is tracked.
     pub async fn submit_message(
         &self,
         envelope: MessageEnvelope,
-        raw_payload: Vec<u8>,
         signature: &[u8],
-    ) -> Result<(), ProcessorError> {
-        envelope.validate(&self.configured_version)?;
-        self.keys
-            .verify_signature(envelope.sender_para, &raw_payload, signature)?;
+    ) -> Result<String, ProcessorError> {
+        // The signature covers the assets as the client spelled them, so
+        // the payload is captured before they are normalised.
+        let raw_payload = envelope.signing_bytes();
+        let normalized = self.normalized(envelope.clone());
+        self.validate_envelope(&normalized)?;
+        if !self.skip_signatures {
+            self.keys.verify_envelope(&envelope, signature)?;
+        }
+        self.accept(normalized, raw_payload).await
+    }
 
-        let message_id = envelope
-            .message_id
-            .clone()
-            .unwrap_or_else(|| Uuid::new_v4().to_string());
+    /// Submit several envelopes at once, returning one result per envelope
+    /// in input order.
+    ///
+    /// Signatures are verified in parallel off the async runtime; accepted
+    /// messages are then enqueued in order, so a failing entry does not
+    /// affect the others.
+    pub async fn submit_batch(
+        &self,
+        batch: Vec<SignedEnvelope>,
+    ) -> Vec<Result<String, ProcessorError>> {
+        let prepared: Vec<_> = batch
+            .iter()
+            .map(|(envelope, _)| {
+                let normalized = self.normalized(envelope.clone());
+                let validated = self.validate_envelope(&normalized);
+                (normalized, envelope.signing_bytes(), validated)
+            })
+            .collect();
+        let batch = Arc::new(batch);
+        let verifications = if self.skip_signatures {
+            (0..batch.len()).map(|_| Ok(())).collect()
+        } else {
+            self.keys
+                .verify_envelopes(batch.clone(), self.verify_parallelism)
+                .await
+        };
 
+        let mut results = Vec::with_capacity(batch.len());
+        for ((normalized, raw_payload, validated), verified) in
+            prepared.into_iter().zip(verifications)
         {
+            let result = match (validated, verified) {
+                (Err(err), _) => Err(err),
+                (Ok(()), Err(err)) => Err(err.into()),
+                (Ok(()), Ok(())) => self.accept(normalized, raw_payload).await,
+            };
+            results.push(result);
+        }
+        results
+    }
+
+    /// Nonce `sender` must use next, or `None` when nonces are not enforced.
+    pub fn next_nonce(&self, sender: u32) -> Option<u64> {
+        self.nonces.as_ref().map(|nonces| nonces.next(sender))
+    }
+
+    /// Rewrite `envelope`'s assets into canonical MultiLocation form when
+    /// `validation.multilocation_assets` is set, so validation and
+    /// simulation see the balance keys execution uses.
+    pub fn normalized(&self, mut envelope: MessageEnvelope) -> MessageEnvelope {
+        if self.validation.multilocation_assets {
+            envelope.normalize_assets();
+        }
+        envelope
+    }
+
+    /// Consume the envelope's nonce, then assign it an id and enqueue it.
+    async fn accept(
+        &self,
+        envelope: MessageEnvelope,
+        raw_payload: Vec<u8>,
+    ) -> Result<String, ProcessorError> {
+        if let Some(existing) = self.tracked_content_id(&envelope, &raw_payload)? {
+            return Ok(existing);
+        }
+        let Some(nonces) = &self.nonces else {
+            return self.accept_unique(envelope, raw_payload).await;
+        };
+        let sender = envelope.sender_para;
+        let nonce = envelope.nonce;
+        nonces
+            .claim(sender, nonce)
+            .map_err(|expected| ProcessorError::InvalidNonce {
+                sender,
+                expected,
+                got: nonce.map_or_else(|| "none".to_string(), |nonce| nonce.to_string()),
+            })?;
+        let accepted = self.accept_unique(envelope, raw_payload).await;
+        if let (Err(_), Some(nonce)) = (&accepted, nonce) {
+            nonces.release(sender, nonce);
+        }
+        accepted
+    }
+
+    /// Assign an id to a verified, normalised envelope and enqueue it with
+    /// the payload its sender signed.
+    async fn accept_unique(
+        &self,
+        envelope: MessageEnvelope,
+        raw_payload: Vec<u8>,
+    ) -> Result<String, ProcessorError> {
+        let message_id = match (&envelope.message_id, self.message_ids) {
+            (Some(id), _) => id.clone(),
+            (None, MessageIdMode::Random) => Uuid::new_v4().to_string(),
+            (None, MessageIdMode::ContentHash) => content_id(&raw_payload),
+        };
+
+        let Some(recent) = &self.recent_content else {
+            return self.enqueue(message_id, envelope, raw_payload, None).await;
+        };
+        let hash = content_hash(&envelope);
+        recent
+            .claim(hash, &message_id)
+            .map_err(|existing| ProcessorError::DuplicateContent { existing })?;
+        let queued = self.enqueue(message_id, envelope, raw_payload, None).await;
+        if queued.is_err() {
+            recent.release(&hash);
+        }
+        queued
+    }
+
+    /// Id of the tracked message with the same signed content, when ids
+    /// are content hashes, so a resubmission is answered with it instead
+    /// of running again.
+    fn tracked_content_id(
+        &self,
+        envelope: &MessageEnvelope,
+        raw_payload: &[u8],
+    ) -> Result<Option<String>, ProcessorError> {
+        if envelope.message_id.is_some() || self.message_ids != MessageIdMode::ContentHash {
+            return Ok(None);
+        }
+        let message_id = content_id(raw_payload);
+        let messages = self
+            .state
+            .messages
+            .read()
+            .map_err(|_| ProcessorError::StatePoisoned)?;
+        Ok(messages.contains_key(&message_id).then_some(message_id))
+    }
+
+    /// Re-run a previously processed message against current state.
+    ///
+    /// The stored envelope is re-validated and enqueued under a fresh id that
+    /// links back to the original. Its signature was verified on first
+    /// submission, so it is not checked again.
+    pub async fn replay_message(&self, original_id: &str) -> Result<String, ProcessorError> {
+        let mut envelope = {
+            let messages = self
+                .state
+                .messages
+                .read()
+                .map_err(|_| ProcessorError::StatePoisoned)?;
+            let record = messages
+                .get(original_id)
+                .ok_or_else(|| ProcessorError::UnknownMessage(original_id.to_string()))?;
+            if !record.status.is_terminal() {
+                return Err(ProcessorError::NotReplayable(original_id.to_string()));
+            }
+            record
+                .envelope
+                .clone()
+                .ok_or_else(|| ProcessorError::UnknownMessage(original_id.to_string()))?
+        };
+
+        self.validate_envelope(&envelope)?;
+        let message_id = Uuid::new_v4().to_string();
+        envelope.message_id = Some(message_id.clone());
+        let raw_payload = envelope.signing_bytes();
+
+        self.enqueue(
+            message_id,
+            envelope,
+            raw_payload,
+            Some(original_id.to_string()),
+        )
+        .await
+    }
+
+    /// Cancel a message that has not finished yet.
+    ///
+    /// Queued and deferred messages are cancelled on the spot; a queued
+    /// entry stays in the channel and the relay loop skips it when it is
+    /// dequeued. A message the relay has already claimed has its
+    /// cancellation token fired instead, and ends `cancelled` if execution
+    /// stops before committing.
+    pub fn cancel_message(&self, message_id: &str) -> Result<Cancellation, ProcessorError> {
+        {
+            let mut messages = self
+                .state
+                .messages
+                .write()
+                .map_err(|_| ProcessorError::StatePoisoned)?;
+            let record = messages
+                .get_mut(message_id)
+                .ok_or_else(|| ProcessorError::UnknownMessage(message_id.to_string()))?;
+            match record.status {
+                MessageStatus::Pending => {}
+                MessageStatus::Relayed if record.deferred => record.deferred = false,
+                MessageStatus::Relayed => {
+                    // Fired under the lock, so the relay cannot have
+                    // finished and dropped the token in between.
+                    self.state.cancel_execution(message_id);
+                    return Ok(Cancellation::Requested);
+                }
+                _ => return Err(ProcessorError::NotCancellable(message_id.to_string())),
+            }
+            record.status = MessageStatus::Cancelled;
+            if let Some(envelope) = &record.envelope {
+                self.state.release_message(envelope);
+            }
+            self.state.forget_cancellation(message_id);
+        }
+        self.state
+            .publish_status(message_id, &MessageStatus::Cancelled);
+        Ok(Cancellation::Cancelled)
+    }
+
+    /// Send a deferred message back to the relay loop to be executed.
+    pub async fn execute_deferred(&self, message_id: &str) -> Result<(), ProcessorError> {
+        let (envelope, submitted_at) = {
             let mut messages = self
                 .state
                 .messages
                 .write()
                 .map_err(|_| ProcessorError::StatePoisoned)?;
+            let record = messages
+                .get_mut(message_id)
+                .ok_or_else(|| ProcessorError::UnknownMessage(message_id.to_string()))?;
+            if !record.deferred {
+                return Err(ProcessorError::NotDeferred(message_id.to_string()));
+            }
+            let envelope = record
+                .envelope
+                .clone()
+                .ok_or_else(|| ProcessorError::UnknownMessage(message_id.to_string()))?;
+            record.deferred = false;
+            (envelope, record.submitted_at)
+        };
+
+        self.state.relay.message_enqueued();
+        let sent = self
+            .sender
+            .send(QueuedMessage {
+                message_id: message_id.to_string(),
+                raw_payload: envelope.signing_bytes(),
+                envelope,
+                submitted_at,
+                enqueued_at: self.state.clock.now(),
+                release_deferred: true,
+            })
+            .await;
+        if sent.is_err() {
+            self.state.relay.message_dequeued();
+            // Leave the message deferred so it can be triggered again.
+            let mut messages = self
+                .state
+                .messages
+                .write()
+                .map_err(|_| ProcessorError::StatePoisoned)?;
+            if let Some(record) = messages.get_mut(message_id) {
+                if matches!(record.status, MessageStatus::Relayed) {
+                    record.deferred = true;
+                }
+            }
+            return Err(ProcessorError::ChannelClosed);
+        }
+        Ok(())
+    }
+
+    /// Apply every submission check that does not involve the signature.
+    ///
+    /// Envelope and consensus problems are collected together so a client
+    /// sees all of them in one response.
+    pub fn validate_envelope(&self, envelope: &MessageEnvelope) -> Result<(), ProcessorError> {
+        let configured_version = self
+            .version_overrides
+            .get(&envelope.dest_para)
+            .unwrap_or(&self.configured_version);
+        let mut errors = envelope
+            .validate_all(configured_version, &self.validation)
+            .err()
+            .unwrap_or_default();
+        if let Err(err) = self.consensus.check(envelope) {
+            errors.push(err);
+        }
+        if errors.len() > 1 {
+            return Err(ProcessorError::ValidationErrors(errors));
+        }
+        if let Some(err) = errors.pop() {
+            return Err(err.into());
+        }
+        match self.topology.first_blocked_hop(&envelope.hop_path()) {
+            Some((from, to)) => Err(ProcessorError::RouteNotAllowed { from, to }),
+            None => Ok(()),
+        }
+    }
+
+    async fn enqueue(
+        &self,
+        message_id: String,
+        envelope: MessageEnvelope,
+        raw_payload: Vec<u8>,
+        replayed_from: Option<String>,
+    ) -> Result<String, ProcessorError> {
+        let sender_para = envelope.sender_para;
+        let dest_para = envelope.dest_para;
+        if !self
+            .state
+            .reserve_in_flight(sender_para, self.max_inflight_per_sender)
+        {
+            return Err(ProcessorError::TooManyInFlight {
+                para_id: sender_para,
+                limit: self.max_inflight_per_sender.unwrap_or_default(),
+            });
+        }
+        let capacity = self.capacity.get(&dest_para).copied();
+        if !self.state.reserve_pending(dest_para, capacity) {
+            self.state.release_in_flight(sender_para);
+            return Err(ProcessorError::DestinationCongested {
+                para_id: dest_para,
+                capacity: capacity.unwrap_or_default(),
+            });
+        }
+        let queued = self
+            .track_and_send(message_id.clone(), envelope, raw_payload, replayed_from)
+            .await;
+        if queued.is_err() {
+            self.state.release_in_flight(sender_para);
+            self.state.release_pending(dest_para);
+        }
+        queued?;
+        self.state.record_sent(sender_para);
+
+        Ok(message_id)
+    }
+
+    async fn track_and_send(
+        &self,
+        message_id: String,
+        envelope: MessageEnvelope,
+        raw_payload: Vec<u8>,
+        replayed_from: Option<String>,
+    ) -> Result<(), ProcessorError> {
+        let submitted_at = Instant::now();
+        {
+            let mut messages = self
+                .state
+                .messages
+                .write()
+                .map_err(|_| ProcessorError::StatePoisoned)?;
+            // A generated content-hash id is only taken twice by identical
+            // submissions racing past `tracked_content_id`.
+            if envelope.message_id.is_none() && messages.contains_key(&message_id) {
+                return Err(ProcessorError::DuplicateContent {
+                    existing: message_id,
+                });
+            }
             messages.insert(
                 message_id.clone(),
                 MessageRecord {
                     status: MessageStatus::Pending,
-                    hops: vec![envelope.sender_para],
+                    sender_para: envelope.sender_para,
+                    dest_para: envelope.dest_para,
+                    hops: Hop::along(&[envelope.sender_para], HopStatus::Relayed),
+                    envelope: Some(envelope.clone()),
+                    replayed_from,
+                    submitted_at,
+                    ..MessageRecord::default()
                 },
-            );
+            )?;
         }
+        self.state
+            .publish_status(&message_id, &MessageStatus::Pending);
 
-        self.sender
+        self.state.relay.message_enqueued();
+        let sent = self
+            .sender
             .send(QueuedMessage {
                 message_id,
                 envelope,
                 raw_payload,
+                submitted_at,
+                enqueued_at: self.state.clock.now(),
+                release_deferred: false,
             })
-            .await
-            .map_err(|_| ProcessorError::ChannelClosed)
+            .await;
+        if sent.is_err() {
+            self.state.relay.message_dequeued();
+            return Err(ProcessorError::ChannelClosed);
+        }
+        Ok(())
     }
 }
 
@@ -94,49 +540,355 @@ impl MessageProcessor {
 pub enum ProcessorError {
     #[error(transparent)]
     Validation(#[from] MessageValidationError),
+    #[error("message has {} validation errors", .0.len())]
+    ValidationErrors(Vec<MessageValidationError>),
     #[error(transparent)]
     Signature(#[from] crate::crypto::CryptoError),
+    #[error(transparent)]
+    Store(#[from] MessageStoreError),
+    #[error("no channel is open from parachain {from} to {to}")]
+    RouteNotAllowed { from: u32, to: u32 },
+    #[error("message {0} not found")]
+    UnknownMessage(String),
+    #[error("message {0} has not finished processing and cannot be replayed")]
+    NotReplayable(String),
+    #[error("message {0} has already finished and cannot be cancelled")]
+    NotCancellable(String),
+    #[error("message {0} is not waiting for deferred execution")]
+    NotDeferred(String),
+    #[error("parachain {para_id} already has {limit} messages in flight")]
+    TooManyInFlight { para_id: u32, limit: u64 },
+    #[error("parachain {para_id} already has {capacity} messages in flight to it")]
+    DestinationCongested { para_id: u32, capacity: u64 },
+    #[error("identical content was already submitted as message {existing}")]
+    DuplicateContent { existing: String },
+    #[error("parachain {sender} must use nonce {expected} next, got {got}")]
+    InvalidNonce {
+        sender: u32,
+        expected: u64,
+        got: String,
+    },
     #[error("relay channel closed")]
     ChannelClosed,
     #[error("state lock poisoned")]
     StatePoisoned,
 }
 
+/// Result of [`MessageProcessor::cancel_message`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cancellation {
+    /// The message will not run.
+    Cancelled,
+    /// The message is executing; it ends `cancelled` only if it stops
+    /// before committing.
+    Requested,
+}
+
+/// Operator switch that stops the relay loop from draining its queue.
+///
+/// Clones share the same switch. While paused, submissions are still
+/// accepted and wait as `pending` until the queue fills.
+#[derive(Clone)]
+pub struct PauseSwitch {
+    paused: Arc<watch::Sender<bool>>,
+}
+
+impl Default for PauseSwitch {
+    fn default() -> Self {
+        Self {
+            paused: Arc::new(watch::Sender::new(false)),
+        }
+    }
+}
+
+impl PauseSwitch {
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.send_replace(paused);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Resolve immediately when running, otherwise once resumed.
+    pub async fn wait_until_running(&self) {
+        let mut receiver = self.paused.subscribe();
+        // The sender lives in `self`, so the channel cannot close here.
+        let _ = receiver.wait_for(|paused| !*paused).await;
+    }
+}
+
+/// Optional behaviour wired into the relay loop.
+#[derive(Clone, Default)]
+pub struct RelayOptions {
+    /// Notified in the background whenever a message reaches a terminal status.
+    pub webhook: SharedWebhook,
+    /// Receives the submit-to-terminal latency of every relayed message.
+    pub metrics: Arc<Metrics>,
+    /// Checked before each message is taken off the queue.
+    pub pause: PauseSwitch,
+    /// Fails messages fast to destinations that keep failing.
+    pub breaker: Option<Arc<CircuitBreaker>>,
+    pub ordering: RelayOrdering,
+    /// Most messages executing at once under [`RelayOrdering::FullyParallel`];
+    /// unbounded when unset.
+    pub max_parallel: Option<usize>,
+    /// Stop every message at `relayed` until it is explicitly executed.
+    pub defer_execution: bool,
+    /// Fail messages that waited in the queue longer than this.
+    pub max_queue_age: Option<Duration>,
+    /// Engines for particular destinations; every other destination uses
+    /// the engine the loop was started with.
+    pub engines: HashMap<u32, Arc<dyn ExecutionEngine>>,
+}
+
+impl RelayOptions {
+    pub fn from_config(
+        config: &AppConfig,
+        metrics: Arc<Metrics>,
+        pause: PauseSwitch,
+        clock: SharedClock,
+    ) -> Self {
+        Self {
+            webhook: SharedWebhook::from_config(&config.relay),
+            metrics,
+            pause,
+            breaker: CircuitBreaker::from_config(&config.relay, clock).map(Arc::new),
+            ordering: config.relay.ordering,
+            max_parallel: Some(config.relay.max_parallel_messages),
+            defer_execution: config.relay.defer_execution,
+            max_queue_age: (config.relay.max_queue_age_secs > 0)
+                .then(|| Duration::from_secs(config.relay.max_queue_age_secs)),
+            engines: HashMap::new(),
+        }
+    }
+}
+
 /// Run the relay loop, routing queued messages through simulated hops.
+///
+/// Messages are taken off the queue in submission order and scheduled
+/// according to `options.ordering`.
 pub async fn run_relay_loop(
     state: ServiceState,
     engine: Arc<dyn ExecutionEngine>,
     mut receiver: Receiver<QueuedMessage>,
+    options: RelayOptions,
 ) {
-    while let Some(queued) = receiver.recv().await {
-        let message_id = queued.message_id.clone();
-        let hops = vec![queued.envelope.sender_para, queued.envelope.dest_para];
+    state.relay.worker_started();
+    let mut lanes: HashMap<u32, Sender<QueuedMessage>> = HashMap::new();
+    let parallel = Arc::new(Semaphore::new(
+        options.max_parallel.unwrap_or(Semaphore::MAX_PERMITS),
+    ));
+    loop {
+        options.pause.wait_until_running().await;
+        let Some(queued) = receiver.recv().await else {
+            break;
+        };
+        state.relay.message_dequeued();
+        match options.ordering {
+            RelayOrdering::Serial => relay_message(&state, engine.as_ref(), queued, &options),
+            RelayOrdering::FullyParallel => {
+                // Waiting here leaves further messages in the queue, where
+                // they still count against its capacity.
+                let permit = parallel
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("the parallelism semaphore is never closed");
+                let (state, engine, options) = (state.clone(), engine.clone(), options.clone());
+                tokio::spawn(async move {
+                    relay_message(&state, engine.as_ref(), queued, &options);
+                    drop(permit);
+                });
+            }
+            RelayOrdering::SequentialPerDestination => {
+                let lane = lanes
+                    .entry(queued.envelope.dest_para)
+                    .or_insert_with(|| spawn_lane(state.clone(), engine.clone(), options.clone()));
+                // Lanes only stop once their sender is dropped.
+                let _ = lane.send(queued).await;
+            }
+        }
+    }
+    state.relay.worker_stopped();
+}
+
+/// Start a worker that relays one destination's messages in order.
+fn spawn_lane(
+    state: ServiceState,
+    engine: Arc<dyn ExecutionEngine>,
+    options: RelayOptions,
+) -> Sender<QueuedMessage> {
+    let (sender, mut receiver) = mpsc::channel(LANE_CAPACITY);
+    tokio::spawn(async move {
+        state.relay.worker_started();
+        loop {
+            // Messages may already be buffered here when the relay pauses.
+            options.pause.wait_until_running().await;
+            let Some(queued) = receiver.recv().await else {
+                break;
+            };
+            relay_message(&state, engine.as_ref(), queued, &options);
+        }
+        state.relay.worker_stopped();
+    });
+    sender
+}
+
+/// Execute one dequeued message and publish its terminal status.
+fn relay_message(
+    state: &ServiceState,
+    engine: &dyn ExecutionEngine,
+    queued: QueuedMessage,
+    options: &RelayOptions,
+) {
+    let message_id = queued.message_id.clone();
+    if !claim_for_relay(state, &message_id) {
+        return;
+    }
+    let engine = options
+        .engines
+        .get(&queued.envelope.dest_para)
+        .map_or(engine, |engine| engine.as_ref());
+    let path = queued.envelope.hop_path();
+    let cancel = state.cancellation_token(&message_id);
+    let expired = options
+        .max_queue_age
+        .is_some_and(|max_age| state.clock.now().duration_since(queued.enqueued_at) > max_age);
+    let defer = options.defer_execution || queued.envelope.defer_execution;
+    if defer && !queued.release_deferred && !cancel.is_cancelled() && !expired {
+        // Capacity stays reserved until the message reaches a terminal status.
+        if let Some(record) = relay_messages(state).get_mut(&message_id) {
+            record.hops = Hop::along(&path, HopStatus::Relayed);
+            record.deferred = true;
+        }
+        return;
+    }
+    let mut engine_name = None;
 
-        let status = if hops.len() > MAX_HOPS {
-            MessageStatus::Failed {
-                error: "maximum hop count exceeded".to_string(),
+    let dest_para = queued.envelope.dest_para;
+    let result = match &options.breaker {
+        _ if expired => Err(ExecutionFailure::from(ExecutionError::ExpiredInQueue)),
+        Some(breaker) if !breaker.allows(dest_para) => Err(ExecutionFailure::from(
+            ExecutionError::DestinationUnavailable { para_id: dest_para },
+        )),
+        breaker => {
+            engine_name = Some(engine.engine_name().to_string());
+            let result = engine.execute_until_cancelled(&queued.envelope, &cancel);
+            // Failures caused by the message itself say nothing about the
+            // destination's health, so only its own faults are counted.
+            let healthy = match &result {
+                Ok(_) => Some(true),
+                Err(failure) if failure.error.is_destination_fault() => Some(false),
+                Err(_) => None,
+            };
+            if let (Some(breaker), Some(healthy)) = (breaker, healthy) {
+                breaker.record(dest_para, healthy);
             }
-        } else {
-            match engine.execute(&queued.envelope) {
-                Ok(outcome) => MessageStatus::Executed {
-                    outcome: outcome.summary(),
-                },
-                Err(err) => MessageStatus::Failed {
-                    error: err.to_string(),
-                },
+            result
+        }
+    };
+    let outcome = XcmOutcome::of(&queued.envelope, &result);
+    let status = match result {
+        Ok(executed) => {
+            state.record_received(queued.envelope.dest_para);
+            MessageStatus::Executed {
+                outcome,
+                timings: executed.timings,
             }
-        };
+        }
+        Err(failure) if matches!(failure.error, ExecutionError::Cancelled) => {
+            MessageStatus::Cancelled
+        }
+        Err(failure) => MessageStatus::Failed {
+            error: failure.error.to_string(),
+            outcome,
+            logs: failure.logs,
+        },
+    };
+    state.release_message(&queued.envelope);
+    let last = match status {
+        MessageStatus::Executed { .. } => HopStatus::Executed,
+        _ => HopStatus::Failed,
+    };
+    let hops = Hop::along(&path, last);
 
-        let mut messages = match state.messages.write() {
-            Ok(guard) => guard,
-            Err(_) => continue,
-        };
+    {
+        let mut messages = relay_messages(state);
+        state.forget_cancellation(&message_id);
 
         if let Some(record) = messages.get_mut(&message_id) {
-            record.status = status;
+            record.status = status.clone();
             record.hops = hops.clone();
-        } else {
-            messages.insert(message_id, MessageRecord { status, hops });
+            record.engine = engine_name;
+        } else if let Err(err) = messages.insert(
+            message_id.clone(),
+            MessageRecord {
+                status: status.clone(),
+                sender_para: queued.envelope.sender_para,
+                dest_para: queued.envelope.dest_para,
+                hops,
+                envelope: Some(queued.envelope),
+                engine: engine_name,
+                ..MessageRecord::default()
+            },
+        ) {
+            tracing::warn!(
+                target: "xcm_lite::relay",
+                error = %err,
+                "dropping status for untracked message"
+            );
+        }
+    }
+    state.publish_status(&message_id, &status);
+    options
+        .metrics
+        .relay_duration
+        .observe(queued.submitted_at.elapsed());
+
+    if let Some(webhook) = options.webhook.current() {
+        tokio::spawn(async move {
+            if let Err(err) = webhook.notify(&message_id, &status).await {
+                tracing::warn!(
+                    target: "xcm_lite::webhook",
+                    message_id = %message_id,
+                    error = %err,
+                    "failed to deliver status webhook"
+                );
+            }
+        });
+    }
+}
+
+/// Write-lock the message store for the relay, recovering it when poisoned.
+///
+/// Writers update records in place, so a store poisoned by a panicking
+/// writer is still consistent, and dropping the update would leave an
+/// executed message stuck at its earlier status.
+fn relay_messages(state: &ServiceState) -> RwLockWriteGuard<'_, MessageStore> {
+    state.messages.write().unwrap_or_else(|poisoned| {
+        tracing::error!(
+            target: "xcm_lite::relay",
+            "message store lock poisoned; recovering it to record the status"
+        );
+        poisoned.into_inner()
+    })
+}
+
+/// Move a dequeued message from `Pending` to `Relayed`, returning `false`
+/// when it was cancelled while queued.
+///
+/// Claiming under the write lock means a cancellation either lands first,
+/// and the message is skipped, or sees it `relayed` and fires its token.
+fn claim_for_relay(state: &ServiceState, message_id: &str) -> bool {
+    {
+        let mut messages = relay_messages(state);
+        match messages.get_mut(message_id) {
+            Some(record) if matches!(record.status, MessageStatus::Cancelled) => return false,
+            Some(record) => record.status = MessageStatus::Relayed,
+            None => return true,
         }
     }
+    state.publish_status(message_id, &MessageStatus::Relayed);
+    true
 }
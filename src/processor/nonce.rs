@@ -0,0 +1,81 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::config::ProcessorConfig;
+
+/// Next nonce expected from each sender parachain.
+///
+/// Every submission must carry exactly the nonce its sender is expected
+/// to use next, so a captured envelope cannot be submitted again and
+/// messages cannot be silently skipped.
+pub struct NonceTracker {
+    initial: u64,
+    next: Mutex<HashMap<u32, u64>>,
+}
+
+impl NonceTracker {
+    pub fn new(initial: u64) -> Self {
+        Self {
+            initial,
+            next: Mutex::default(),
+        }
+    }
+
+    /// Build the tracker when `processor.enforce_nonces` is set.
+    pub fn from_config(config: &ProcessorConfig) -> Option<Self> {
+        config
+            .enforce_nonces
+            .then(|| Self::new(config.initial_nonce))
+    }
+
+    /// Nonce `sender` must use on its next submission.
+    pub fn next(&self, sender: u32) -> u64 {
+        self.lock().get(&sender).copied().unwrap_or(self.initial)
+    }
+
+    /// Consume `nonce` for `sender`, or return the nonce that was expected.
+    pub fn claim(&self, sender: u32, nonce: Option<u64>) -> Result<(), u64> {
+        let mut next = self.lock();
+        let expected = next.get(&sender).copied().unwrap_or(self.initial);
+        match nonce {
+            Some(nonce) if nonce == expected => {
+                next.insert(sender, expected.saturating_add(1));
+                Ok(())
+            }
+            _ => Err(expected),
+        }
+    }
+
+    /// Hand back a claimed nonce whose submission was not accepted after
+    /// all, unless a later nonce has been claimed since.
+    pub fn release(&self, sender: u32, nonce: u64) {
+        let mut next = self.lock();
+        if next.get(&sender) == Some(&nonce.saturating_add(1)) {
+            next.insert(sender, nonce);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u32, u64>> {
+        // Entries are replaced whole, so a poisoned map is still consistent.
+        self.next.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonces_advance_per_sender_and_roll_back_on_release() {
+        let nonces = NonceTracker::new(1);
+        assert_eq!(nonces.claim(1000, None), Err(1));
+        nonces.claim(1000, Some(1)).expect("first nonce");
+        assert_eq!(nonces.claim(1000, Some(1)), Err(2));
+        assert_eq!(nonces.next(1001), 1);
+
+        nonces.release(1000, 1);
+        assert_eq!(nonces.next(1000), 1);
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    config::{AppConfig, ParachainConfig},
+    domain::{MessageEnvelope, MessageValidationError},
+};
+
+/// Instruction kinds allowed to cross a consensus boundary when
+/// `validation.restrict_cross_consensus` is enabled.
+const CROSS_CONSENSUS_INSTRUCTIONS: &[&str] = &[
+    "transferReserveAsset",
+    "conditionalTransfer",
+    "withdrawAsset",
+    "depositAsset",
+    "queryResponse",
+];
+
+/// Directed channel topology constraining which hops a message may take.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelTopology {
+    /// `None` permits every route; otherwise only the listed `(from, to)` hops.
+    allowed: Option<HashSet<(u32, u32)>>,
+}
+
+impl ChannelTopology {
+    pub fn from_config(config: &ParachainConfig) -> Self {
+        if config.channels.is_empty() {
+            Self::default()
+        } else {
+            Self {
+                allowed: Some(config.channels.iter().copied().collect()),
+            }
+        }
+    }
+
+    /// Return the first hop along `path` that has no open channel.
+    pub fn first_blocked_hop(&self, path: &[u32]) -> Option<(u32, u32)> {
+        let allowed = self.allowed.as_ref()?;
+        path.windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .find(|hop| !allowed.contains(hop))
+    }
+}
+
+/// Consensus systems parachains belong to, used to restrict which
+/// instructions may travel between them.
+#[derive(Debug, Clone, Default)]
+pub struct ConsensusBoundary {
+    /// `None` when the restriction is disabled.
+    consensus: Option<HashMap<u32, String>>,
+}
+
+impl ConsensusBoundary {
+    pub fn from_config(config: &AppConfig) -> Self {
+        if !config.validation.restrict_cross_consensus {
+            return Self::default();
+        }
+        let consensus = config
+            .parachains
+            .overrides
+            .iter()
+            .filter_map(|entry| Some((entry.para_id, entry.consensus_id.clone()?)))
+            .collect();
+        Self {
+            consensus: Some(consensus),
+        }
+    }
+
+    /// Reject instructions that may not cross from the sender's consensus
+    /// system into the destination's.
+    pub fn check(&self, envelope: &MessageEnvelope) -> Result<(), MessageValidationError> {
+        let Some(consensus) = &self.consensus else {
+            return Ok(());
+        };
+        let sender = consensus.get(&envelope.sender_para);
+        let dest = consensus.get(&envelope.dest_para);
+        if sender == dest {
+            return Ok(());
+        }
+        for (idx, instruction) in envelope.instructions.iter().enumerate() {
+            if !CROSS_CONSENSUS_INSTRUCTIONS.contains(&instruction.kind()) {
+                return Err(MessageValidationError::unsupported_instruction(format!(
+                    "instruction {idx} ({}) may not cross from parachain {} to parachain {} in another consensus system",
+                    instruction.kind(),
+                    envelope.sender_para,
+                    envelope.dest_para
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ParachainOverride,
+        domain::{
+            Instruction, SignatureScheme, SigningEncoding, Transact, XcmErrorCode, XcmVersion,
+        },
+    };
+
+    fn topology(channels: Vec<(u32, u32)>) -> ChannelTopology {
+        ChannelTopology::from_config(&ParachainConfig {
+            channels,
+            ..ParachainConfig::default()
+        })
+    }
+
+    #[test]
+    fn permits_everything_without_channels() {
+        assert_eq!(topology(Vec::new()).first_blocked_hop(&[1000, 1001]), None);
+    }
+
+    #[test]
+    fn channels_are_directed() {
+        let topology = topology(vec![(1000, 1001)]);
+        assert_eq!(topology.first_blocked_hop(&[1000, 1001]), None);
+        assert_eq!(
+            topology.first_blocked_hop(&[1001, 1000]),
+            Some((1001, 1000))
+        );
+    }
+
+    #[test]
+    fn transact_may_not_cross_consensus_systems() {
+        let mut config = AppConfig::default();
+        config.validation.restrict_cross_consensus = true;
+        config.parachains.overrides = vec![ParachainOverride {
+            para_id: 1002,
+            consensus_id: Some("kusama".into()),
+            ..ParachainOverride::default()
+        }];
+        let boundary = ConsensusBoundary::from_config(&config);
+        let mut envelope = MessageEnvelope {
+            message_id: None,
+            sender_para: 1000,
+            dest_para: 1001,
+            via: Vec::new(),
+            xcm_version: XcmVersion::V3,
+            instructions: vec![Instruction::Transact(Transact {
+                call_data: "0x00".into(),
+                weight: None,
+                origin_kind: None,
+            })],
+            signature_scheme: SignatureScheme::Ed25519,
+            prehashed: false,
+            defer_execution: false,
+            nonce: None,
+            signing_encoding: SigningEncoding::Json,
+            signature: None,
+        };
+        assert!(boundary.check(&envelope).is_ok());
+
+        envelope.dest_para = 1002;
+        let err = boundary.check(&envelope).unwrap_err();
+        assert_eq!(err.code, XcmErrorCode::UnsupportedInstruction);
+    }
+}
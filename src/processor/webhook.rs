@@ -0,0 +1,211 @@
+use std::{
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{config::RelayConfig, state::MessageStatus};
+
+/// Delay before the first retry; doubled after each failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Per-request timeout for webhook deliveries.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Posts terminal message statuses to a configured HTTP endpoint.
+#[derive(Clone)]
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+    max_attempts: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookPayload<'a> {
+    message_id: &'a str,
+    #[serde(flatten)]
+    status: &'a MessageStatus,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>, max_attempts: u32) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            url: url.into(),
+            max_attempts: max_attempts.max(1),
+        }
+    }
+
+    /// Build a notifier when a webhook URL is configured.
+    pub fn from_config(config: &RelayConfig) -> Option<Self> {
+        config
+            .webhook_url
+            .as_ref()
+            .map(|url| Self::new(url.clone(), config.webhook_max_attempts))
+    }
+
+    /// Deliver a status notification, retrying with exponential backoff.
+    pub async fn notify(
+        &self,
+        message_id: &str,
+        status: &MessageStatus,
+    ) -> Result<(), WebhookError> {
+        let payload = WebhookPayload { message_id, status };
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 1;
+
+        loop {
+            let result = self
+                .client
+                .post(&self.url)
+                .json(&payload)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match result {
+                Ok(_) => return Ok(()),
+                Err(err) if attempt >= self.max_attempts => {
+                    return Err(WebhookError::Delivery {
+                        attempts: attempt,
+                        source: err,
+                    })
+                }
+                Err(err) => {
+                    tracing::debug!(
+                        target: "xcm_lite::webhook",
+                        message_id,
+                        attempt,
+                        error = %err,
+                        "webhook delivery failed; retrying"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// The notifier relay workers deliver through, swapped out when the
+/// webhook settings are reloaded.
+#[derive(Clone, Default)]
+pub struct SharedWebhook {
+    current: Arc<RwLock<Option<WebhookNotifier>>>,
+}
+
+impl SharedWebhook {
+    pub fn from_config(config: &RelayConfig) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(WebhookNotifier::from_config(config))),
+        }
+    }
+
+    /// Notifier for the current settings, when a webhook is configured.
+    pub fn current(&self) -> Option<WebhookNotifier> {
+        // Replaced whole, so a poisoned slot is still consistent.
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn replace(&self, notifier: Option<WebhookNotifier>) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = notifier;
+    }
+}
+
+/// Errors raised when a webhook notification cannot be delivered.
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("webhook delivery failed after {attempts} attempts: {source}")]
+    Delivery {
+        attempts: u32,
+        #[source]
+        source: reqwest::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::execution::XcmOutcome;
+
+    #[derive(Clone, Default)]
+    struct Recorder {
+        calls: Arc<AtomicUsize>,
+        bodies: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    async fn flaky_hook(
+        State(recorder): State<Recorder>,
+        Json(body): Json<serde_json::Value>,
+    ) -> StatusCode {
+        if recorder.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+        recorder.bodies.lock().expect("lock").push(body);
+        StatusCode::OK
+    }
+
+    async fn spawn_hook(recorder: Recorder) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let app = Router::new()
+            .route("/hook", post(flaky_hook))
+            .with_state(recorder);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{addr}/hook")
+    }
+
+    #[tokio::test]
+    async fn retries_until_delivered() {
+        let recorder = Recorder::default();
+        let url = spawn_hook(recorder.clone()).await;
+        let notifier = WebhookNotifier::new(url, 3);
+
+        let status = MessageStatus::Failed {
+            error: "boom".into(),
+            outcome: XcmOutcome::Error {
+                error: "boom".into(),
+            },
+            logs: Vec::new(),
+        };
+        notifier.notify("msg-1", &status).await.expect("delivered");
+
+        assert_eq!(recorder.calls.load(Ordering::SeqCst), 2);
+        let bodies = recorder.bodies.lock().expect("lock");
+        assert_eq!(bodies[0]["messageId"], "msg-1");
+        assert_eq!(bodies[0]["status"], "failed");
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let recorder = Recorder::default();
+        let url = spawn_hook(recorder.clone()).await;
+        let notifier = WebhookNotifier::new(url, 1);
+
+        let status = MessageStatus::Executed {
+            outcome: XcmOutcome::Complete { weight_used: 0 },
+            timings: None,
+        };
+        let err = notifier.notify("msg-1", &status).await.unwrap_err();
+        assert!(matches!(err, WebhookError::Delivery { attempts: 1, .. }));
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing_subscriber::{reload::Handle, EnvFilter, Registry};
+
+use crate::{
+    config::{AppConfig, ConfigSource, Result},
+    processor::webhook::{SharedWebhook, WebhookNotifier},
+};
+
+/// Settings, as dotted config paths, that a reload applies in place. Any
+/// other change is reported but only takes effect after a restart.
+///
+/// Reloads are triggered by `POST /admin/config/reload` rather than SIGHUP,
+/// since this build has no tokio signal support. A signal handler only
+/// needs to call [`ConfigReloader::reload`]. Rate limits are not listed
+/// because the service has no rate-limit settings yet.
+pub const HOT_RELOADABLE: &[&str] = &[
+    "server.log_filter",
+    "relay.webhook_url",
+    "relay.webhook_max_attempts",
+];
+
+/// Handle for swapping the process-wide log filter.
+pub type LogFilterHandle = Handle<EnvFilter, Registry>;
+
+/// Changed settings found by one reload.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadReport {
+    /// Now in effect.
+    pub applied: Vec<String>,
+    /// Still waiting for a restart.
+    pub restart_required: Vec<String>,
+}
+
+/// Re-reads the configuration and applies its [`HOT_RELOADABLE`] settings.
+#[derive(Clone)]
+pub struct ConfigReloader {
+    source: ConfigSource,
+    /// The startup configuration with every applied reload on top.
+    effective: Arc<Mutex<AppConfig>>,
+    webhook: SharedWebhook,
+    log_filter: Option<LogFilterHandle>,
+}
+
+impl ConfigReloader {
+    pub fn new(source: ConfigSource, config: &AppConfig) -> Self {
+        Self {
+            source,
+            effective: Arc::new(Mutex::new(config.clone())),
+            webhook: SharedWebhook::from_config(&config.relay),
+            log_filter: None,
+        }
+    }
+
+    /// Drive `handle` from `server.log_filter`, starting with its current
+    /// value.
+    pub fn with_log_filter(mut self, handle: LogFilterHandle) -> Self {
+        self.log_filter = Some(handle);
+        let directives = self.lock().server.log_filter.clone();
+        if directives.is_some() {
+            self.set_log_filter(directives.as_deref());
+        }
+        self
+    }
+
+    /// Webhook notifier that follows `relay.webhook_*` across reloads.
+    pub fn webhook(&self) -> SharedWebhook {
+        self.webhook.clone()
+    }
+
+    /// The configuration currently in effect.
+    pub fn effective(&self) -> AppConfig {
+        self.lock().clone()
+    }
+
+    /// Load the configuration again and apply the settings that can change
+    /// in place. A configuration that fails to load or validate changes
+    /// nothing.
+    pub fn reload(&self) -> Result<ReloadReport> {
+        let next = AppConfig::load_source(&self.source)?;
+        Ok(self.apply(&next))
+    }
+
+    fn apply(&self, next: &AppConfig) -> ReloadReport {
+        let mut effective = self.lock();
+        let (applied, restart_required): (Vec<_>, Vec<_>) = changed_settings(&effective, next)
+            .into_iter()
+            .partition(|path| HOT_RELOADABLE.contains(&path.as_str()));
+        effective.server.log_filter = next.server.log_filter.clone();
+        effective.relay.webhook_url = next.relay.webhook_url.clone();
+        effective.relay.webhook_max_attempts = next.relay.webhook_max_attempts;
+        drop(effective);
+
+        if applied.iter().any(|path| path == "server.log_filter") {
+            self.set_log_filter(next.server.log_filter.as_deref());
+        }
+        if applied
+            .iter()
+            .any(|path| path.starts_with("relay.webhook_"))
+        {
+            self.webhook
+                .replace(WebhookNotifier::from_config(&next.relay));
+        }
+        tracing::info!(target: "xcm_lite::config", ?applied, "reloaded configuration");
+        if !restart_required.is_empty() {
+            tracing::warn!(
+                target: "xcm_lite::config",
+                settings = ?restart_required,
+                "changed settings take effect after a restart"
+            );
+        }
+        ReloadReport {
+            applied,
+            restart_required,
+        }
+    }
+
+    fn set_log_filter(&self, directives: Option<&str>) {
+        let Some(handle) = &self.log_filter else {
+            return;
+        };
+        // Directives were checked when the configuration was loaded.
+        let filter = directives.map_or_else(EnvFilter::from_default_env, EnvFilter::new);
+        if let Err(err) = handle.reload(filter) {
+            tracing::warn!(
+                target: "xcm_lite::config",
+                error = %err,
+                "failed to swap the log filter"
+            );
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, AppConfig> {
+        // Fields are assigned whole, so a poisoned config is still consistent.
+        self.effective
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Dotted paths of every setting that differs between `old` and `new`.
+fn changed_settings(old: &AppConfig, new: &AppConfig) -> Vec<String> {
+    fn walk(old: &Value, new: &Value, path: String, changed: &mut Vec<String>) {
+        match (old, new) {
+            (Value::Object(old), Value::Object(new)) => {
+                let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+                for key in keys {
+                    let child = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    let value = |map: &serde_json::Map<String, Value>| {
+                        map.get(key).cloned().unwrap_or(Value::Null)
+                    };
+                    walk(&value(old), &value(new), child, changed);
+                }
+            }
+            _ if old != new => changed.push(path),
+            _ => {}
+        }
+    }
+
+    let to_value =
+        |config| serde_json::to_value(config).expect("config serialization is infallible");
+    let mut changed = Vec::new();
+    walk(&to_value(old), &to_value(new), String::new(), &mut changed);
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_hot_settings_and_reports_the_rest() {
+        let config = AppConfig::default();
+        let reloader = ConfigReloader::new(ConfigSource::Default, &config);
+        assert!(reloader.webhook().current().is_none());
+
+        let mut next = config.clone();
+        next.relay.webhook_url = Some("http://127.0.0.1:9/hook".into());
+        next.parachains.count = 6;
+        let report = reloader.apply(&next);
+        assert_eq!(report.applied, ["relay.webhook_url"]);
+        assert_eq!(report.restart_required, ["parachains.count"]);
+        assert!(reloader.webhook().current().is_some());
+        assert_eq!(
+            reloader.effective().parachains.count,
+            config.parachains.count
+        );
+
+        // Unapplied changes keep being reported until the restart.
+        let report = reloader.apply(&next);
+        assert!(report.applied.is_empty());
+        assert_eq!(report.restart_required, ["parachains.count"]);
+    }
+
+    #[test]
+    fn invalid_configuration_changes_nothing() {
+        let path =
+            std::env::temp_dir().join(format!("xcm-lite-reload-{}.toml", std::process::id()));
+        let reloader = ConfigReloader::new(ConfigSource::File(path.clone()), &AppConfig::default());
+
+        std::fs::write(&path, "[server]\nlog_filter = \"xcm_lite=loud\"\n").expect("write config");
+        let rejected = reloader.reload();
+        std::fs::write(&path, "[relay]\nwebhook_max_attempts = 5\n").expect("write config");
+        let accepted = reloader.reload();
+        std::fs::remove_file(&path).ok();
+
+        assert!(rejected
+            .unwrap_err()
+            .to_string()
+            .contains("server.log_filter is invalid"));
+        assert_eq!(
+            accepted.expect("reload").applied,
+            ["relay.webhook_max_attempts"]
+        );
+        assert_eq!(reloader.effective().relay.webhook_max_attempts, 5);
+    }
+}
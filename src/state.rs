@@ -1,88 +1,674 @@
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, PoisonError, RwLock,
+    },
+    time::Instant,
 };
 
+use serde::Serialize;
 use thiserror::Error;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 
-use crate::config::ParachainConfig;
+use crate::{
+    audit::AuditLog,
+    clock::{self, SharedClock},
+    config::{ParachainConfig, StateConfig},
+    domain::{Balance, MessageEnvelope},
+    execution::{ExecutionTimings, XcmOutcome},
+};
 
 /// Shared, concurrent state for the XCM Lite service.
 #[derive(Clone)]
 pub struct ServiceState {
-    pub parachains: Arc<RwLock<HashMap<u32, ParachainState>>>,
-    pub messages: Arc<RwLock<HashMap<String, MessageRecord>>>,
+    /// Per-parachain state behind its own lock, so a panic while mutating
+    /// one parachain poisons only that parachain. The key set is fixed at
+    /// startup.
+    pub parachains: Arc<HashMap<u32, Arc<RwLock<ParachainState>>>>,
+    pub messages: Arc<RwLock<MessageStore>>,
+    /// Sent/received counters per parachain; the key set is fixed at startup.
+    pub traffic: Arc<HashMap<u32, ParachainTraffic>>,
+    /// Parachains marked offline to simulate downtime; the key set is fixed
+    /// at startup.
+    offline: Arc<HashMap<u32, AtomicBool>>,
+    /// Tokens stopping messages the relay has claimed but not finished.
+    cancellations: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    /// Relay queue depth and worker count, for diagnostics.
+    pub relay: Arc<RelayGauges>,
+    /// Hash-chained record of every published status transition.
+    pub audit: AuditLog,
+    /// Source of status timestamps.
+    pub clock: SharedClock,
+    /// Fan-out of every message status transition.
+    events: broadcast::Sender<StatusEvent>,
 }
 
+/// Transitions buffered per subscriber before slow readers start lagging.
+const EVENT_BUFFER: usize = 1_024;
+
 impl ServiceState {
     /// Initialise state structures based on configuration.
-    pub fn initialize(config: &ParachainConfig) -> Result<Self, StateInitError> {
+    pub fn initialize(
+        config: &ParachainConfig,
+        limits: &StateConfig,
+    ) -> Result<Self, StateInitError> {
         let mut parachains = HashMap::new();
+        let mut traffic = HashMap::new();
+        let mut offline = HashMap::new();
         for para_id in config.parachain_ids() {
             if parachains
-                .insert(para_id, ParachainState::default())
+                .insert(
+                    para_id,
+                    Arc::new(RwLock::new(ParachainState::with_max_logs(
+                        limits.max_logs_per_parachain,
+                    ))),
+                )
                 .is_some()
             {
                 return Err(StateInitError::DuplicateParaId(para_id));
             }
+            traffic.insert(para_id, ParachainTraffic::default());
+            offline.insert(para_id, AtomicBool::new(false));
         }
 
         Ok(Self {
-            parachains: Arc::new(RwLock::new(parachains)),
-            messages: Arc::new(RwLock::new(HashMap::new())),
+            parachains: Arc::new(parachains),
+            messages: Arc::new(RwLock::new(MessageStore::with_capacity(
+                limits.max_tracked_messages,
+            ))),
+            traffic: Arc::new(traffic),
+            offline: Arc::new(offline),
+            cancellations: Arc::default(),
+            relay: Arc::default(),
+            audit: AuditLog::default(),
+            clock: clock::system(),
+            events: broadcast::channel(EVENT_BUFFER).0,
         })
     }
 
+    /// Record status transitions in `audit` instead of an unsigned log.
+    pub fn with_audit(mut self, audit: AuditLog) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Read time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Return the count of currently registered parachains.
     pub fn parachain_count(&self) -> usize {
-        self.parachains.read().map(|map| map.len()).unwrap_or(0)
+        self.parachains.len()
+    }
+
+    /// Lock guarding one parachain's state.
+    pub fn parachain(&self, para_id: u32) -> Option<&Arc<RwLock<ParachainState>>> {
+        self.parachains.get(&para_id)
+    }
+
+    /// Whether `para_id` has been marked offline.
+    pub fn is_offline(&self, para_id: u32) -> bool {
+        self.offline
+            .get(&para_id)
+            .is_some_and(|flag| flag.load(Ordering::Acquire))
+    }
+
+    /// Mark `para_id` offline or back online, returning `false` if it is
+    /// not registered.
+    pub fn set_offline(&self, para_id: u32, offline: bool) -> bool {
+        match self.offline.get(&para_id) {
+            Some(flag) => {
+                flag.store(offline, Ordering::Release);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Token the relay watches while executing `message_id`, created on
+    /// first use.
+    pub fn cancellation_token(&self, message_id: &str) -> CancellationToken {
+        // Tokens are inserted and removed whole, so a poisoned map is
+        // still consistent.
+        self.cancellations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(message_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Ask the relay to stop executing `message_id`.
+    pub fn cancel_execution(&self, message_id: &str) {
+        self.cancellation_token(message_id).cancel();
+    }
+
+    /// Drop the token of a message that reached a terminal status.
+    pub fn forget_cancellation(&self, message_id: &str) {
+        self.cancellations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(message_id);
+    }
+
+    /// Deep copy of the parachain states with an empty message store and
+    /// fresh counters, for dry runs. Parachains whose lock is poisoned are
+    /// left out.
+    pub fn snapshot(&self) -> ServiceState {
+        let parachains: HashMap<_, _> = self
+            .parachains
+            .iter()
+            .filter_map(|(para_id, lock)| {
+                let state = lock.read().ok()?.clone();
+                Some((*para_id, Arc::new(RwLock::new(state))))
+            })
+            .collect();
+        let traffic = parachains
+            .keys()
+            .map(|para_id| (*para_id, ParachainTraffic::default()))
+            .collect();
+        let offline = parachains
+            .keys()
+            .map(|para_id| (*para_id, AtomicBool::new(self.is_offline(*para_id))))
+            .collect();
+        Self {
+            parachains: Arc::new(parachains),
+            messages: Arc::new(RwLock::new(MessageStore::default())),
+            traffic: Arc::new(traffic),
+            offline: Arc::new(offline),
+            cancellations: Arc::default(),
+            relay: Arc::default(),
+            audit: AuditLog::default(),
+            clock: self.clock.clone(),
+            events: broadcast::channel(EVENT_BUFFER).0,
+        }
+    }
+
+    /// Receive every status transition published after this call.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<StatusEvent> {
+        self.events.subscribe()
+    }
+
+    /// Announce that `message_id` moved to `status`.
+    pub fn publish_status(&self, message_id: &str, status: &MessageStatus) {
+        let timestamp = self.clock.unix_millis();
+        self.audit.append(message_id, status, timestamp);
+        // Sending only fails when nobody is subscribed.
+        let _ = self.events.send(StatusEvent {
+            message_id: message_id.to_string(),
+            status: status.clone(),
+            timestamp,
+        });
+    }
+
+    /// Count a message accepted from `para_id`.
+    pub fn record_sent(&self, para_id: u32) {
+        if let Some(counters) = self.traffic.get(&para_id) {
+            counters.sent.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count a message now in flight to `para_id`, unless `capacity` of them
+    /// already are. Returns whether the slot was taken.
+    pub fn reserve_pending(&self, para_id: u32, capacity: Option<u64>) -> bool {
+        self.traffic
+            .get(&para_id)
+            .is_none_or(|counters| try_increment(&counters.pending, capacity))
+    }
+
+    /// Count a message now in flight from `para_id`, unless `limit` of them
+    /// already are. Returns whether the slot was taken.
+    pub fn reserve_in_flight(&self, para_id: u32, limit: Option<u64>) -> bool {
+        self.traffic
+            .get(&para_id)
+            .is_none_or(|counters| try_increment(&counters.in_flight, limit))
+    }
+
+    /// Free the slot taken by [`ServiceState::reserve_pending`].
+    pub fn release_pending(&self, para_id: u32) {
+        if let Some(counters) = self.traffic.get(&para_id) {
+            decrement(&counters.pending);
+        }
+    }
+
+    /// Free the slot taken by [`ServiceState::reserve_in_flight`].
+    pub fn release_in_flight(&self, para_id: u32) {
+        if let Some(counters) = self.traffic.get(&para_id) {
+            decrement(&counters.in_flight);
+        }
+    }
+
+    /// Free both slots taken for a message once it reaches a terminal
+    /// status.
+    pub fn release_message(&self, envelope: &MessageEnvelope) {
+        self.release_in_flight(envelope.sender_para);
+        self.release_pending(envelope.dest_para);
+    }
+
+    /// Count a message delivered to `para_id`.
+    pub fn record_received(&self, para_id: u32) {
+        if let Some(counters) = self.traffic.get(&para_id) {
+            counters.received.fetch_add(1, Ordering::Relaxed);
+        }
     }
 }
 
 impl Default for ServiceState {
     fn default() -> Self {
         Self {
-            parachains: Arc::new(RwLock::new(HashMap::new())),
-            messages: Arc::new(RwLock::new(HashMap::new())),
+            parachains: Arc::new(HashMap::new()),
+            messages: Arc::new(RwLock::new(MessageStore::default())),
+            traffic: Arc::new(HashMap::new()),
+            offline: Arc::new(HashMap::new()),
+            cancellations: Arc::default(),
+            relay: Arc::default(),
+            audit: AuditLog::default(),
+            clock: clock::system(),
+            events: broadcast::channel(EVENT_BUFFER).0,
         }
     }
 }
 
+/// A single status transition, as streamed to `/events` subscribers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusEvent {
+    pub message_id: String,
+    #[serde(flatten)]
+    pub status: MessageStatus,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
 /// State associated with a single parachain in the simulation.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ParachainState {
-    pub balances: HashMap<String, u128>,
-    pub logs: Vec<String>,
+    /// Account to the balance it holds of each asset.
+    pub balances: HashMap<String, HashMap<String, Balance>>,
+    /// Most recent log entries, oldest first.
+    pub logs: VecDeque<String>,
+    /// Entries kept in `logs`; zero keeps every entry.
+    #[serde(skip)]
+    max_logs: usize,
+}
+
+impl ParachainState {
+    /// Empty state that keeps at most `max_logs` log entries, or every
+    /// entry when zero.
+    pub fn with_max_logs(max_logs: usize) -> Self {
+        Self {
+            max_logs,
+            ..Self::default()
+        }
+    }
+
+    /// Balance `account` holds of `asset`; zero when it holds none.
+    pub fn balance(&self, account: &str, asset: &str) -> Balance {
+        self.balances
+            .get(account)
+            .and_then(|assets| assets.get(asset))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Balance `account` holds of `asset`, created at zero when absent.
+    pub fn balance_mut(&mut self, account: &str, asset: &str) -> &mut Balance {
+        self.balances
+            .entry(account.to_string())
+            .or_default()
+            .entry(asset.to_string())
+            .or_default()
+    }
+
+    /// Append a log entry, dropping the oldest once the cap is reached.
+    pub fn push_log(&mut self, entry: String) {
+        if self.max_logs > 0 && self.logs.len() >= self.max_logs {
+            self.logs.pop_front();
+        }
+        self.logs.push_back(entry);
+    }
+}
+
+/// Message counters for a single parachain.
+#[derive(Debug, Default)]
+pub struct ParachainTraffic {
+    pub sent: AtomicU64,
+    pub received: AtomicU64,
+    /// Accepted messages destined for this parachain that have not yet
+    /// reached a terminal status.
+    pub pending: AtomicU64,
+    /// Accepted messages sent by this parachain that have not yet reached a
+    /// terminal status.
+    pub in_flight: AtomicU64,
+}
+
+impl ParachainTraffic {
+    pub fn snapshot(&self) -> TrafficSnapshot {
+        TrafficSnapshot {
+            sent: self.sent.load(Ordering::Relaxed),
+            received: self.received.load(Ordering::Relaxed),
+            pending: self.pending.load(Ordering::Relaxed),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Gauges describing the relay queue.
+///
+/// Tokio's channel does not report its length, so the depth is counted
+/// explicitly: incremented before a message is sent into the queue and
+/// decremented when the relay loop takes it out. Treat it as approximate.
+#[derive(Debug, Default)]
+pub struct RelayGauges {
+    pub queue_depth: AtomicU64,
+    /// Relay loops currently running.
+    pub active_workers: AtomicU64,
+}
+
+impl RelayGauges {
+    pub fn message_enqueued(&self) {
+        self.queue_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn message_dequeued(&self) {
+        decrement(&self.queue_depth);
+    }
+
+    pub fn worker_started(&self) {
+        self.active_workers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn worker_stopped(&self) {
+        decrement(&self.active_workers);
+    }
+}
+
+/// Increment unless the gauge has already reached `limit`.
+fn try_increment(gauge: &AtomicU64, limit: Option<u64>) -> bool {
+    gauge
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |value| match limit {
+            Some(limit) if value >= limit => None,
+            _ => Some(value + 1),
+        })
+        .is_ok()
+}
+
+/// Decrement without wrapping below zero.
+fn decrement(gauge: &AtomicU64) {
+    let _ = gauge.fetch_update(Ordering::AcqRel, Ordering::Acquire, |value| {
+        value.checked_sub(1)
+    });
+}
+
+/// Point-in-time copy of a parachain's message counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficSnapshot {
+    pub sent: u64,
+    pub received: u64,
+    pub pending: u64,
+    pub in_flight: u64,
+}
+
+/// Insertion-ordered message records with a bounded capacity.
+///
+/// When full, inserting a new record evicts the oldest record that has
+/// reached a terminal status. If every tracked record is still in flight the
+/// insert is refused instead.
+#[derive(Debug, Default)]
+pub struct MessageStore {
+    records: HashMap<String, MessageRecord>,
+    order: VecDeque<String>,
+    capacity: Option<usize>,
+}
+
+impl MessageStore {
+    /// Create a store that tracks at most `capacity` records.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            records: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: Some(capacity),
+        }
+    }
+
+    /// Insert or replace a record, evicting the oldest terminal record if the
+    /// store is full. Returns the previous record stored under `message_id`.
+    pub fn insert(
+        &mut self,
+        message_id: String,
+        record: MessageRecord,
+    ) -> Result<Option<MessageRecord>, MessageStoreError> {
+        if let Some(existing) = self.records.get_mut(&message_id) {
+            return Ok(Some(std::mem::replace(existing, record)));
+        }
+
+        if let Some(capacity) = self.capacity {
+            if self.records.len() >= capacity {
+                self.evict_oldest_terminal()
+                    .ok_or(MessageStoreError::Full { capacity })?;
+            }
+        }
+
+        self.order.push_back(message_id.clone());
+        self.records.insert(message_id, record);
+        Ok(None)
+    }
+
+    pub fn get(&self, message_id: &str) -> Option<&MessageRecord> {
+        self.records.get(message_id)
+    }
+
+    pub fn get_mut(&mut self, message_id: &str) -> Option<&mut MessageRecord> {
+        self.records.get_mut(message_id)
+    }
+
+    pub fn contains_key(&self, message_id: &str) -> bool {
+        self.records.contains_key(message_id)
+    }
+
+    /// Iterate over records in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &MessageRecord)> {
+        self.order
+            .iter()
+            .filter_map(|id| self.records.get_key_value(id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    fn evict_oldest_terminal(&mut self) -> Option<MessageRecord> {
+        let position = self.order.iter().position(|id| {
+            self.records
+                .get(id)
+                .is_some_and(|record| record.status.is_terminal())
+        })?;
+        let message_id = self.order.remove(position)?;
+        self.records.remove(&message_id)
+    }
+}
+
+/// Errors raised when the message store cannot accept a record.
+#[derive(Debug, Error)]
+pub enum MessageStoreError {
+    #[error("message store is full ({capacity} records still in flight)")]
+    Full { capacity: usize },
 }
 
 /// Record tracking the lifecycle of a submitted XCM message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MessageRecord {
+    #[serde(flatten)]
     pub status: MessageStatus,
-    pub hops: Vec<u32>,
+    pub sender_para: u32,
+    pub dest_para: u32,
+    /// Parachains the message has reached so far, in order.
+    pub hops: Vec<Hop>,
+    /// Envelope as accepted, retained so the message can be replayed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub envelope: Option<MessageEnvelope>,
+    /// Id of the message this one was replayed from, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replayed_from: Option<String>,
+    /// Name of the execution engine that ran the message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// Relayed but held back from execution until explicitly triggered.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deferred: bool,
+    /// When the message was accepted; relay durations are measured from
+    /// here, even for a deferred message executed later.
+    #[serde(skip)]
+    pub submitted_at: Instant,
 }
 
 impl Default for MessageRecord {
     fn default() -> Self {
         Self {
             status: MessageStatus::Pending,
+            sender_para: 0,
+            dest_para: 0,
             hops: Vec::new(),
+            envelope: None,
+            replayed_from: None,
+            engine: None,
+            deferred: false,
+            submitted_at: Instant::now(),
         }
     }
 }
 
+/// One parachain along a message's route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hop {
+    pub para_id: u32,
+    pub role: HopRole,
+    pub status: HopStatus,
+}
+
+impl Hop {
+    /// Hops for `path`, where every parachain before the last has passed
+    /// the message on and the last ended in `last`.
+    pub fn along(path: &[u32], last: HopStatus) -> Vec<Hop> {
+        path.iter()
+            .enumerate()
+            .map(|(index, &para_id)| {
+                let role = match index {
+                    0 => HopRole::Sender,
+                    _ if index + 1 == path.len() => HopRole::Destination,
+                    _ => HopRole::Intermediate,
+                };
+                let status = if index + 1 == path.len() {
+                    last
+                } else {
+                    HopStatus::Relayed
+                };
+                Hop {
+                    para_id,
+                    role,
+                    status,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HopRole {
+    Sender,
+    Intermediate,
+    Destination,
+}
+
+/// How far the message got at one hop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HopStatus {
+    /// The message reached this parachain and, unless it is the
+    /// destination, was passed on.
+    Relayed,
+    Executed,
+    Failed,
+}
+
 /// High-level message processing status values.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
 pub enum MessageStatus {
+    #[default]
     Pending,
     Relayed,
-    Executed { outcome: Option<String> },
-    Failed { error: String },
+    Executed {
+        outcome: XcmOutcome,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timings: Option<ExecutionTimings>,
+    },
+    Failed {
+        error: String,
+        outcome: XcmOutcome,
+        /// Logs of the instructions that ran before the failure.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        logs: Vec<String>,
+    },
+    /// Withdrawn by the submitter, either before it ran or while it was
+    /// executing, in which case none of its effects were committed.
+    Cancelled,
 }
 
-impl Default for MessageStatus {
-    fn default() -> Self {
-        MessageStatus::Pending
+impl MessageStatus {
+    /// Serialized `status` tag, e.g. `executed`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MessageStatus::Pending => "pending",
+            MessageStatus::Relayed => "relayed",
+            MessageStatus::Executed { .. } => "executed",
+            MessageStatus::Failed { .. } => "failed",
+            MessageStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Every `status` tag, in lifecycle order.
+    pub fn names() -> [&'static str; 5] {
+        let outcome = || XcmOutcome::Error {
+            error: String::new(),
+        };
+        [
+            MessageStatus::Pending,
+            MessageStatus::Relayed,
+            MessageStatus::Executed {
+                outcome: outcome(),
+                timings: None,
+            },
+            MessageStatus::Failed {
+                error: String::new(),
+                outcome: outcome(),
+                logs: Vec::new(),
+            },
+            MessageStatus::Cancelled,
+        ]
+        .map(|status| status.name())
+    }
+
+    /// Whether the message has finished processing.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            MessageStatus::Executed { .. }
+                | MessageStatus::Failed { .. }
+                | MessageStatus::Cancelled
+        )
     }
 }
 
@@ -92,3 +678,107 @@ pub enum StateInitError {
     #[error("duplicate parachain id detected: {0}")]
     DuplicateParaId(u32),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(status: MessageStatus) -> MessageRecord {
+        MessageRecord {
+            status,
+            hops: Hop::along(&[1000], HopStatus::Relayed),
+            ..MessageRecord::default()
+        }
+    }
+
+    fn executed() -> MessageStatus {
+        MessageStatus::Executed {
+            outcome: XcmOutcome::Complete { weight_used: 0 },
+            timings: None,
+        }
+    }
+
+    #[test]
+    fn hops_mark_roles_and_the_final_status() {
+        let hops = Hop::along(&[1000, 1001, 1002], HopStatus::Failed);
+        let roles: Vec<_> = hops.iter().map(|hop| hop.role).collect();
+        assert_eq!(
+            roles,
+            [HopRole::Sender, HopRole::Intermediate, HopRole::Destination]
+        );
+        assert_eq!(hops[1].status, HopStatus::Relayed);
+        assert_eq!(hops[2].status, HopStatus::Failed);
+    }
+
+    #[test]
+    fn parachain_logs_keep_only_the_most_recent_entries() {
+        let mut state = ParachainState::with_max_logs(2);
+        for index in 0..3 {
+            state.push_log(format!("entry {index}"));
+        }
+        assert_eq!(state.logs, ["entry 1", "entry 2"]);
+
+        let mut unbounded = ParachainState::default();
+        for index in 0..3 {
+            unbounded.push_log(format!("entry {index}"));
+        }
+        assert_eq!(unbounded.logs.len(), 3);
+    }
+
+    #[test]
+    fn evicts_oldest_terminal_record_beyond_capacity() {
+        let mut store = MessageStore::with_capacity(2);
+        store.insert("a".into(), record(executed())).unwrap();
+        store.insert("b".into(), record(executed())).unwrap();
+        store
+            .insert("c".into(), record(MessageStatus::Pending))
+            .unwrap();
+
+        assert_eq!(store.len(), 2);
+        assert!(!store.contains_key("a"));
+        assert!(store.contains_key("b"));
+        assert!(store.contains_key("c"));
+    }
+
+    #[test]
+    fn skips_pending_records_when_evicting() {
+        let mut store = MessageStore::with_capacity(2);
+        store
+            .insert("a".into(), record(MessageStatus::Pending))
+            .unwrap();
+        store.insert("b".into(), record(executed())).unwrap();
+        store
+            .insert("c".into(), record(MessageStatus::Pending))
+            .unwrap();
+
+        let ids: Vec<_> = store.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn refuses_insert_when_all_records_pending() {
+        let mut store = MessageStore::with_capacity(1);
+        store
+            .insert("a".into(), record(MessageStatus::Pending))
+            .unwrap();
+
+        let err = store
+            .insert("b".into(), record(MessageStatus::Pending))
+            .unwrap_err();
+        assert!(matches!(err, MessageStoreError::Full { capacity: 1 }));
+        assert!(store.contains_key("a"));
+    }
+
+    #[test]
+    fn replacing_existing_record_does_not_evict() {
+        let mut store = MessageStore::with_capacity(1);
+        store
+            .insert("a".into(), record(MessageStatus::Pending))
+            .unwrap();
+        let previous = store.insert("a".into(), record(executed())).unwrap();
+
+        assert!(previous.is_some());
+        assert_eq!(store.len(), 1);
+        assert!(store.get("a").unwrap().status.is_terminal());
+    }
+}